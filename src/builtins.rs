@@ -0,0 +1,163 @@
+use crate::error::{AsteriskError, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Names of the values generated for `${uuid}`, `${now:%Y-%m-%d}` and friends
+const BUILTINS: &[&str] = &["uuid", "now", "unix", "unix_ms", "random_int"];
+
+/// Filters applied with `${VAR|name}`
+const FILTERS: &[&str] = &["base64", "urlencode", "sha256", "json_escape"];
+
+/// Default format of `${now}`
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Whether `name` is a built-in value
+pub fn is_builtin(name: &str) -> bool {
+    BUILTINS.contains(&name)
+}
+
+/// Generates a built-in value; `args` is the text after `name:`
+/// A new value is generated for every reference.
+pub fn evaluate(name: &str, args: Option<&str>) -> Result<String> {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    match (name, args) {
+        ("uuid", None) => Ok(uuid_v4()),
+        ("now", format) => Ok(format_time(SystemTime::now(), format.unwrap_or(DEFAULT_TIME_FORMAT))),
+        ("unix", None) => Ok(since_epoch.as_secs().to_string()),
+        ("unix_ms", None) => Ok(since_epoch.as_millis().to_string()),
+        ("random_int", args) => {
+            let (min, max) = match args.map(|args| args.split_once(':')) {
+                None => (0, 1000),
+                Some(Some((min, max))) => (parse_bound(min)?, parse_bound(max)?),
+                Some(None) => return Err(invalid(format!("random_int expects min:max, got '{}'", args.unwrap_or("")))),
+            };
+            if min > max {
+                return Err(invalid(format!("random_int range {}:{} is empty", min, max)));
+            }
+            Ok(fastrand::i64(min..=max).to_string())
+        }
+        (name, Some(args)) => Err(invalid(format!("{} takes no arguments, got '{}'", name, args))),
+        (name, None) => Err(invalid(format!("Unknown built-in '{}'", name))),
+    }
+}
+
+/// Applies a `|filter` to a resolved value
+pub fn apply_filter(value: &str, filter: &str) -> Result<String> {
+    match filter {
+        "base64" => Ok(BASE64.encode(value)),
+        "urlencode" => Ok(url_encode(value)),
+        "sha256" => Ok(format!("{:x}", Sha256::digest(value.as_bytes()))),
+        "json_escape" => {
+            let quoted = serde_json::Value::String(value.to_string()).to_string();
+            Ok(quoted[1..quoted.len() - 1].to_string())
+        }
+        _ => Err(invalid(format!("Unknown filter '{}' (expected {})", filter, FILTERS.join(", ")))),
+    }
+}
+
+/// Formats a UTC time with `%Y %m %d %H %M %S` (and `%s` seconds since the epoch,
+/// `%3f` milliseconds, `%%`); other characters are copied as is
+pub fn format_time(time: SystemTime, format: &str) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (hour, minute, second) = (seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let mut output = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('%') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let (text, consumed) = match rest.as_bytes() {
+            [b'Y', ..] => (format!("{:04}", year), 1),
+            [b'm', ..] => (format!("{:02}", month), 1),
+            [b'd', ..] => (format!("{:02}", day), 1),
+            [b'H', ..] => (format!("{:02}", hour), 1),
+            [b'M', ..] => (format!("{:02}", minute), 1),
+            [b'S', ..] => (format!("{:02}", second), 1),
+            [b's', ..] => (seconds.to_string(), 1),
+            [b'3', b'f', ..] => (format!("{:03}", since_epoch.subsec_millis()), 2),
+            [b'%', ..] => ("%".to_string(), 1),
+            _ => ("%".to_string(), 0),
+        };
+        output.push_str(&text);
+        rest = &rest[consumed..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Random (version 4) UUID
+fn uuid_v4() -> String {
+    let bits = fastrand::u128(..) & !(0xf000 << 64) & !(0xc << 60) | (0x4000 << 64) | (0x8 << 60);
+    let hex = format!("{:032x}", bits);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn parse_bound(bound: &str) -> Result<i64> {
+    bound.trim().parse().map_err(|_| invalid(format!("random_int bound '{}' is not an integer", bound)))
+}
+
+fn invalid(message: String) -> AsteriskError {
+    AsteriskError::InvalidEnvironmentVariable(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_builtins() {
+        let uuid = evaluate("uuid", None).unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+        assert_ne!(uuid, evaluate("uuid", None).unwrap());
+
+        for _ in 0..50 {
+            let value: i64 = evaluate("random_int", Some("1:3")).unwrap().parse().unwrap();
+            assert!((1..=3).contains(&value));
+        }
+        assert!(evaluate("random_int", Some("5:1")).is_err());
+        assert!(evaluate("unix", Some("x")).is_err());
+
+        let time = UNIX_EPOCH + Duration::from_millis(1_705_314_645_123);
+        assert_eq!(format_time(time, DEFAULT_TIME_FORMAT), "2024-01-15T10:30:45Z");
+        assert_eq!(format_time(time, "%Y%m%d-%H%M%S.%3f %s 100%% %q"), "20240115-103045.123 1705314645 100% %q");
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(apply_filter("user:pass", "base64").unwrap(), "dXNlcjpwYXNz");
+        assert_eq!(apply_filter("a b&c=d/é~", "urlencode").unwrap(), "a%20b%26c%3Dd%2F%C3%A9~");
+        assert_eq!(
+            apply_filter("abc", "sha256").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(apply_filter("say \"hi\"\n", "json_escape").unwrap(), "say \\\"hi\\\"\\n");
+        assert!(apply_filter("x", "rot13").is_err());
+    }
+}
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use crate::compression::Compression;
+use crate::export::ExportFormat;
+use crate::config::{env_flag, env_setting};
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use colored::*;
 
 fn format_help() -> String {
@@ -10,26 +14,61 @@ fn format_help() -> String {
     
     // Usage
     help.push_str(&format!("{}\n", "USAGE:".bold().yellow()));
-    help.push_str(&format!("  {} {} {}\n\n",
+    help.push_str(&format!("  {} {} {}\n",
         "asterisk".bright_green(),
         "<ENDPOINT>".bright_blue(),
         "<METHOD>".bright_blue()
     ));
+    help.push_str(&format!("  {} {} {}\n\n",
+        "asterisk".bright_green(),
+        "run".bright_green(),
+        "<NAME>".bright_blue()
+    ));
     
     // Arguments
     help.push_str(&format!("{}\n", "ARGUMENTS:".bold().yellow()));
     help.push_str(&format!("  {} {}\n", "ENDPOINT".bright_blue(), "API endpoint to test (e.g., users)"));
     help.push_str(&format!("  {} {}\n\n", "METHOD".bright_blue(), "HTTP method (GET, POST, etc.)"));
+
+    // Commands
+    help.push_str(&format!("{}\n", "COMMANDS:".bold().yellow()));
+    help.push_str(&format!("  {} {}\n", "config".bright_green(), "Configuration management (show [--origin], init)"));
+    help.push_str(&format!("  {} {}\n", "secret".bright_green(), "Encrypt credentials into asterisk.config (set, rotate-key)"));
+    help.push_str(&format!("  {} {}\n", "run".bright_green(), "Execute a saved request (--list to show all)"));
+    help.push_str(&format!("  {} {}\n", "file".bright_green(), "Execute requests from a .http / .rest file"));
+    help.push_str(&format!("  {} {}\n", "import".bright_green(), "Import requests from Postman, OpenAPI or HAR"));
+    help.push_str(&format!("  {} {}\n", "ws".bright_green(), "Open a WebSocket (interactive, or -m / --file scripted)"));
+    help.push_str(&format!("  {} {}\n", "gql".bright_green(), "Send a GraphQL query file (--introspect prints the schema)"));
+    help.push_str(&format!("  {} {}\n", "rpc".bright_green(), "Call a JSON-RPC method (--file for batches)"));
+    help.push_str(&format!("  {} {}\n", "from-curl".bright_green(), "Run or save (--save NAME) a curl command"));
+    help.push_str(&format!("  {} {}\n\n", "ops".bright_green(), "List OpenAPI operations"));
     
     // Options
     help.push_str(&format!("{}\n", "OPTIONS:".bold().yellow()));
-    help.push_str(&format!("  {} {} {}\n", "-b, --body".bright_green(), "<JSON>".bright_blue(), "Request body"));
-    help.push_str(&format!("  {} {} {}\n", "-H, --headers".bright_green(), "<HEADERS>".bright_blue(), "HTTP headers (key:value,...)"));
-    help.push_str(&format!("  {} {} {}\n", "-t, --token".bright_green(), "<TOKEN>".bright_blue(), "Bearer token"));
-    help.push_str(&format!("  {} {} {}\n", "-u, --url".bright_green(), "<URL>".bright_blue(), "Base URL (overrides config)"));
-    help.push_str(&format!("  {} {} {}\n", "-p, --profile".bright_green(), "<PROFILE>".bright_blue(), "Configuration profile"));
-    help.push_str(&format!("  {} {}\n", "-v, --verbose".bright_green(), "Enable detailed output"));
-    help.push_str(&format!("  {} {}\n\n", "-h, --help".bright_green(), "Show this help message"));
+    help.push_str(&format!("  {} {} {}\n", "-b, --body".bright_green(), "<JSON>".bright_blue(), "Request body [env: ASTERISK_BODY]"));
+    help.push_str(&format!("  {} {} {}\n", "-H, --headers".bright_green(), "<HEADERS>".bright_blue(), "HTTP headers (key:value,...) [env: ASTERISK_HEADERS]"));
+    help.push_str(&format!("  {} {} {}\n", "-t, --token".bright_green(), "<TOKEN>".bright_blue(), "Bearer token [env: ASTERISK_TOKEN]"));
+    help.push_str(&format!("  {} {} {}\n", "-u, --url".bright_green(), "<URL>".bright_blue(), "Base URL (overrides config) [env: ASTERISK_URL]"));
+    help.push_str(&format!("  {} {} {}\n", "-p, --profile".bright_green(), "<PROFILE>".bright_blue(), "Configuration profile [env: ASTERISK_PROFILE]"));
+    help.push_str(&format!("  {} {} {}\n", "--var".bright_green(), "<KEY=VALUE>".bright_blue(), "Set a profile variable, referenced as ${vars.KEY}"));
+    help.push_str(&format!("  {} {}\n", "-v, --verbose".bright_green(), "Enable detailed output [env: ASTERISK_VERBOSE]"));
+    help.push_str(&format!("  {} {} {}\n", "--as".bright_green(), "<FORMAT>".bright_blue(), "Print as curl, reqwest, python or fetch instead of sending [env: ASTERISK_AS]"));
+    help.push_str(&format!("  {} {}\n", "--mask-secrets".bright_green(), "Replace credentials with $VAR references (with --as) [env: ASTERISK_MASK_SECRETS]"));
+    help.push_str(&format!("  {} {} {}\n", "--config".bright_green(), "<PATH>".bright_blue(), "Config file to use instead of searching [env: ASTERISK_CONFIG]"));
+    help.push_str(&format!("  {} {} {}\n", "--har".bright_green(), "<FILE>".bright_blue(), "Record every exchange as an HTTP Archive, credentials masked [env: ASTERISK_HAR]"));
+    help.push_str(&format!("  {} {}\n", "--har-keep-secrets".bright_green(), "Keep credentials in the --har recording [env: ASTERISK_HAR_KEEP_SECRETS]"));
+    help.push_str(&format!("  {} {}\n", "--sort-keys".bright_green(), "Print JSON object keys in sorted order [env: ASTERISK_SORT_KEYS]"));
+    help.push_str(&format!("  {} {}\n", "--no-pager".bright_green(), "Don't page long responses (color follows the terminal and NO_COLOR) [env: ASTERISK_NO_PAGER]"));
+    help.push_str(&format!("  {} {}\n", "--stream".bright_green(), "Print SSE events / NDJSON lines as they arrive [env: ASTERISK_STREAM]"));
+    help.push_str(&format!("  {} {} {}\n", "--max-events".bright_green(), "<N>".bright_blue(), "Stop streaming after N events [env: ASTERISK_MAX_EVENTS]"));
+    help.push_str(&format!("  {} {} {}\n", "--max-time".bright_green(), "<SECONDS>".bright_blue(), "Stop streaming after a duration [env: ASTERISK_MAX_TIME]"));
+    help.push_str(&format!("  {} {}\n", "--reconnect".bright_green(), "Reconnect closed event streams with Last-Event-ID [env: ASTERISK_RECONNECT]"));
+    help.push_str(&format!("  {} {} {}\n", "--compress-body".bright_green(), "<ENCODING>".bright_blue(), "Send bodies compressed with gzip, deflate, br or zstd [env: ASTERISK_COMPRESS_BODY]"));
+    help.push_str(&format!("  {} {}\n", "--raw-encoding".bright_green(), "Show response bodies as received on the wire [env: ASTERISK_RAW_ENCODING]"));
+    help.push_str(&format!("  {} {} {}\n", "-o, --output".bright_green(), "<FILE>".bright_blue(), "Save the response body to a file [env: ASTERISK_OUTPUT]"));
+    help.push_str(&format!("  {} {}\n", "--resume".bright_green(), "Continue a partial --output download [env: ASTERISK_RESUME]"));
+    help.push_str(&format!("  {} {}\n", "-h, --help".bright_green(), "Show this help message"));
+    help.push_str(&format!("  {}\n\n", "Options take precedence over their ASTERISK_* variables, which take precedence over asterisk.config".italic()));
     
     // Examples
     help.push_str(&format!("{}\n", "EXAMPLES:".bold().yellow()));
@@ -37,8 +76,25 @@ fn format_help() -> String {
     help.push_str(&format!("  {} {}\n", "With body:".bold(), "asterisk sign-up post -b '{\"name\":\"john\"}'"));
     help.push_str(&format!("  {} {}\n", "With profile:".bold(), "asterisk users get --profile staging"));
     help.push_str(&format!("  {} {}\n", "Override URL:".bold(), "asterisk users get -u 'https://api.example.com'"));
+    help.push_str(&format!("  {} {}\n", "Saved request:".bold(), "asterisk run create_user -p staging"));
+    help.push_str(&format!("  {} {}\n", "List saved:".bold(), "asterisk run --list"));
+    help.push_str(&format!("  {} {}\n", "HTTP file:".bold(), "asterisk file api.http --name login"));
+    help.push_str(&format!("  {} {}\n", "Postman:".bold(), "asterisk import postman collection.json --env env.json"));
+    help.push_str(&format!("  {} {}\n", "OpenAPI:".bold(), "asterisk import openapi spec.yaml"));
+    help.push_str(&format!("  {} {}\n", "Operation:".bold(), "asterisk run getUserById id=5"));
+    help.push_str(&format!("  {} {}\n", "Variables:".bold(), "asterisk 'tenants/${vars.tenant}/users' get --var tenant=acme"));
+    help.push_str(&format!("  {} {}\n", "Test data:".bold(), "asterisk orders post -b '{\"id\":\"${uuid}\",\"at\":\"${now}\"}'"));
+    help.push_str(&format!("  {} {}\n", "As curl:".bold(), "asterisk users get --as curl --mask-secrets"));
+    help.push_str(&format!("  {} {}\n", "Stream:".bold(), "asterisk events get --stream --max-events 10"));
+    help.push_str(&format!("  {} {}\n", "WebSocket:".bold(), "asterisk ws chat/room1 -m '{\"type\":\"join\"}'"));
+    help.push_str(&format!("  {} {}\n", "GraphQL:".bold(), "asterisk gql query.graphql --gql-var id=1"));
+    help.push_str(&format!("  {} {}\n", "Schema:".bold(), "asterisk gql --introspect > schema.graphql"));
+    help.push_str(&format!("  {} {}\n", "JSON-RPC:".bold(), "asterisk rpc user.get id=42"));
+    help.push_str(&format!("  {} {}\n", "Download:".bold(), "asterisk files/backup.zip get -o backup.zip --resume"));
+    help.push_str(&format!("  {} {}\n", "From curl:".bold(), "asterisk from-curl --save get_user 'curl https://...'"));
     help.push_str(&format!("  {} {}\n", "Init config:".bold(), "asterisk config init"));
     help.push_str(&format!("  {} {}\n", "Show config:".bold(), "asterisk config show"));
+    help.push_str(&format!("  {} {}\n", "Encrypt token:".bold(), "asterisk secret set staging.token"));
     
     help
 }
@@ -85,9 +141,60 @@ pub struct Cli {
     #[arg(short, long)]
     pub profile: Option<String>,
 
+    /// Profile variable as key=value, overriding the profile's vars, may be repeated
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    pub vars: Vec<String>,
+
     /// Enable detailed output
     #[arg(short, long)]
     pub verbose: bool,
+
+    /// Print the resolved request in this format instead of sending it
+    #[arg(long = "as", value_name = "FORMAT")]
+    pub export_as: Option<ExportFormat>,
+
+    /// Replace credentials with $VAR references in exported requests
+    #[arg(long, requires = "export_as")]
+    pub mask_secrets: bool,
+
+    /// Save the response body to this file instead of printing it
+    #[arg(short, long, value_name = "FILE", conflicts_with = "export_as")]
+    pub output: Option<String>,
+
+    /// Continue a partial --output file with a Range request
+    #[arg(long, requires = "output")]
+    pub resume: bool,
+
+    #[command(flatten)]
+    pub streaming: StreamArgs,
+
+    /// Record every exchange to this file in HAR 1.2 format
+    #[arg(long, value_name = "FILE", global = true)]
+    pub har: Option<String>,
+
+    /// Keep credentials in the --har recording instead of masking them
+    #[arg(long, global = true)]
+    pub har_keep_secrets: bool,
+
+    /// Print JSON object keys in sorted order
+    #[arg(long, global = true)]
+    pub sort_keys: bool,
+
+    /// Print responses directly instead of through $PAGER
+    #[arg(long, global = true)]
+    pub no_pager: bool,
+
+    /// Compress request bodies with this Content-Encoding
+    #[arg(long, value_name = "ENCODING", global = true)]
+    pub compress_body: Option<Compression>,
+
+    /// Show response bodies exactly as received, without undoing Content-Encoding
+    #[arg(long, global = true)]
+    pub raw_encoding: bool,
+
+    /// Project configuration file to use instead of searching for asterisk.config
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<String>,
 }
 
 impl Cli {
@@ -98,8 +205,126 @@ impl Cli {
             std::process::exit(0);
         }
         
-        Self::parse()
+        let mut cli = Self::parse();
+        if let Err(message) = cli.apply_environment() {
+            Self::command().error(ErrorKind::ArgumentConflict, message).exit();
+        }
+        cli
     }
+
+    /// Fills options not given on the command line from their `ASTERISK_*` variables
+    /// Done after parsing, like the profile settings in asterisk.config, so a variable set
+    /// to false doesn't count as a given flag; option dependencies are checked here.
+    fn apply_environment(&mut self) -> Result<(), String> {
+        env_flag_into(&mut self.sort_keys, "ASTERISK_SORT_KEYS")?;
+        env_flag_into(&mut self.no_pager, "ASTERISK_NO_PAGER")?;
+        env_flag_into(&mut self.raw_encoding, "ASTERISK_RAW_ENCODING")?;
+        self.har = self.har.take().or_else(|| env_setting("ASTERISK_HAR"));
+        env_flag_into(&mut self.har_keep_secrets, "ASTERISK_HAR_KEEP_SECRETS")?;
+        if self.har_keep_secrets && self.har.is_none() {
+            return Err("--har-keep-secrets (ASTERISK_HAR_KEEP_SECRETS) requires --har".to_string());
+        }
+        if self.compress_body.is_none() {
+            self.compress_body = env_value("ASTERISK_COMPRESS_BODY", |value| Compression::from_str(value, true))?;
+        }
+
+        match self.command {
+            None => {
+                self.body = self.body.take().or_else(|| env_setting("ASTERISK_BODY"));
+                apply_request_environment(
+                    &mut self.export_as, &mut self.mask_secrets, &mut self.output, &mut self.resume, &mut self.streaming,
+                )
+            }
+            Some(Commands::Run { ref mut overrides, .. }) => apply_request_environment(
+                &mut overrides.export_as,
+                &mut overrides.mask_secrets,
+                &mut overrides.output,
+                &mut overrides.resume,
+                &mut overrides.streaming,
+            ),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+/// Fills the output options of a request from the environment and checks how they combine
+fn apply_request_environment(
+    export_as: &mut Option<ExportFormat>,
+    mask_secrets: &mut bool,
+    output: &mut Option<String>,
+    resume: &mut bool,
+    streaming: &mut StreamArgs,
+) -> Result<(), String> {
+    if export_as.is_none() {
+        *export_as = env_value("ASTERISK_AS", |value| ExportFormat::from_str(value, true))?;
+    }
+    env_flag_into(mask_secrets, "ASTERISK_MASK_SECRETS")?;
+    *output = output.take().or_else(|| env_setting("ASTERISK_OUTPUT"));
+    env_flag_into(resume, "ASTERISK_RESUME")?;
+    env_flag_into(&mut streaming.stream, "ASTERISK_STREAM")?;
+    if streaming.max_events.is_none() {
+        streaming.max_events = env_value("ASTERISK_MAX_EVENTS", |value| value.parse().map_err(|_| "expected a number".to_string()))?;
+    }
+    if streaming.max_time.is_none() {
+        streaming.max_time = env_value("ASTERISK_MAX_TIME", |value| value.parse().map_err(|_| "expected seconds".to_string()))?;
+    }
+    env_flag_into(&mut streaming.reconnect, "ASTERISK_RECONNECT")?;
+    streaming.last_event_id = streaming.last_event_id.take().or_else(|| env_setting("ASTERISK_LAST_EVENT_ID"));
+
+    let stream_options = streaming.max_events.is_some()
+        || streaming.max_time.is_some()
+        || streaming.reconnect
+        || streaming.last_event_id.is_some();
+    let rules = [
+        (*mask_secrets && export_as.is_none(), "--mask-secrets (ASTERISK_MASK_SECRETS) requires --as"),
+        (*resume && output.is_none(), "--resume (ASTERISK_RESUME) requires --output"),
+        (output.is_some() && export_as.is_some(), "--output (ASTERISK_OUTPUT) cannot be used with --as (ASTERISK_AS)"),
+        (streaming.stream && (output.is_some() || export_as.is_some()), "--stream (ASTERISK_STREAM) cannot be used with --output or --as"),
+        (stream_options && !streaming.stream, "--max-events, --max-time, --reconnect and --last-event-id require --stream"),
+    ];
+    match rules.iter().find(|(violated, _)| *violated) {
+        Some((_, message)) => Err(message.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Turns a flag on when its variable is true; a flag given on the command line stays on
+fn env_flag_into(flag: &mut bool, name: &str) -> Result<(), String> {
+    if !*flag {
+        *flag = env_flag(name).map_err(|e| e.to_string())?.unwrap_or(false);
+    }
+    Ok(())
+}
+
+/// Parses the value of an `ASTERISK_*` variable, if set
+fn env_value<T>(name: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    env_setting(name)
+        .map(|value| parse(&value).map_err(|e| format!("Invalid environment variable: {}={} ({})", name, value, e)))
+        .transpose()
+}
+
+/// Options for printing a response body as it arrives
+#[derive(Args, Debug, Clone, Default)]
+pub struct StreamArgs {
+    /// Print SSE events, NDJSON lines or chunks as they arrive
+    #[arg(long, conflicts_with_all = ["output", "export_as"])]
+    pub stream: bool,
+
+    /// Stop streaming after this many events
+    #[arg(long, value_name = "N", requires = "stream")]
+    pub max_events: Option<usize>,
+
+    /// Stop streaming after this many seconds
+    #[arg(long, value_name = "SECONDS", requires = "stream")]
+    pub max_time: Option<f64>,
+
+    /// Reconnect when an event stream closes, resuming with Last-Event-ID
+    #[arg(long, requires = "stream")]
+    pub reconnect: bool,
+
+    /// Last-Event-ID to resume an event stream from
+    #[arg(long, value_name = "ID", requires = "stream")]
+    pub last_event_id: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -109,6 +334,338 @@ pub enum Commands {
         #[command(subcommand)]
         action: ConfigAction,
     },
+    /// Encrypt credentials stored in asterisk.config
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+    /// Execute a saved request from asterisk.config
+    Run {
+        /// Name of the saved request
+        #[arg(value_name = "NAME", required_unless_present = "list")]
+        name: Option<String>,
+
+        /// Variables for placeholders such as path parameters (key=value)
+        #[arg(value_name = "KEY=VALUE")]
+        params: Vec<String>,
+
+        /// List saved requests with their descriptions
+        #[arg(short, long)]
+        list: bool,
+
+        #[command(flatten)]
+        overrides: Box<RequestOverrides>,
+    },
+    /// Execute requests from a .http / .rest file
+    File {
+        /// Path to the .http or .rest file
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Run only the request with this `# @name`
+        #[arg(short, long, conflicts_with = "line")]
+        name: Option<String>,
+
+        /// Run only the request containing this line number
+        #[arg(short, long)]
+        line: Option<usize>,
+
+        /// List requests in the file
+        #[arg(long)]
+        list: bool,
+
+        /// Configuration profile to use
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Profile variable as key=value, overriding the profile's vars, may be repeated
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Base URL (overrides config)
+        #[arg(short, long)]
+        url: Option<String>,
+
+        /// Bearer token
+        #[arg(short, long)]
+        token: Option<String>,
+
+        /// Enable detailed output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// List operations from the configured OpenAPI spec
+    Ops {
+        /// OpenAPI spec to read instead of the configured one
+        #[arg(short, long)]
+        spec: Option<String>,
+    },
+    /// Import profiles and saved requests from other tools
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// Open a WebSocket connection to an endpoint of the profile
+    Ws {
+        /// Endpoint relative to the profile URL, or a full ws:// / wss:// URL
+        #[arg(value_name = "ENDPOINT")]
+        endpoint: String,
+
+        /// Message to send, may be repeated (skips the interactive prompt)
+        #[arg(short, long, value_name = "TEXT")]
+        message: Vec<String>,
+
+        /// Send messages from a file, one per line (`-` reads stdin)
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<String>,
+
+        /// Seconds to wait for replies after the last scripted message
+        #[arg(short, long, value_name = "SECONDS", default_value_t = 2.0)]
+        wait: f64,
+
+        /// Configuration profile to use
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Profile variable as key=value, overriding the profile's vars, may be repeated
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Base URL (overrides config)
+        #[arg(short, long)]
+        url: Option<String>,
+
+        /// Bearer token
+        #[arg(short, long)]
+        token: Option<String>,
+
+        /// HTTP headers for the handshake (key:value,key2:value2)
+        #[arg(short = 'H', long)]
+        headers: Option<String>,
+
+        /// Enable detailed output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Send a GraphQL query to the profile's GraphQL endpoint
+    Gql {
+        /// File containing the query (`-` reads stdin)
+        #[arg(value_name = "QUERY", required_unless_present = "introspect")]
+        query: Option<String>,
+
+        /// GraphQL variable as key=value; JSON values keep their type, may be repeated
+        #[arg(long = "gql-var", value_name = "KEY=VALUE")]
+        gql_vars: Vec<String>,
+
+        /// Variables as a JSON object or a JSON file
+        #[arg(long, value_name = "JSON|FILE")]
+        variables: Option<String>,
+
+        /// Operation to run when the query defines several
+        #[arg(short, long, value_name = "NAME")]
+        operation: Option<String>,
+
+        /// Print the schema as SDL instead of running a query
+        #[arg(long, conflicts_with_all = ["query", "gql_vars", "variables", "operation"])]
+        introspect: bool,
+
+        /// Endpoint relative to the profile URL, or a full URL (overrides graphql_endpoint)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+
+        /// Configuration profile to use
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Profile variable as key=value, overriding the profile's vars, may be repeated
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Base URL (overrides config)
+        #[arg(short, long)]
+        url: Option<String>,
+
+        /// Bearer token
+        #[arg(short, long)]
+        token: Option<String>,
+
+        /// HTTP headers (key:value,key2:value2)
+        #[arg(short = 'H', long)]
+        headers: Option<String>,
+
+        /// Enable detailed output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Call a JSON-RPC 2.0 method on the profile URL
+    Rpc {
+        /// Method to call
+        #[arg(value_name = "METHOD", required_unless_present = "file")]
+        method: Option<String>,
+
+        /// Parameters: key=value for named params, plain values for positional ones
+        #[arg(value_name = "PARAMS")]
+        params: Vec<String>,
+
+        /// Send a batch of calls from a JSON file (`-` reads stdin)
+        #[arg(short, long, value_name = "FILE", conflicts_with_all = ["method", "params"])]
+        file: Option<String>,
+
+        /// Endpoint relative to the profile URL, or a full URL
+        #[arg(short, long)]
+        endpoint: Option<String>,
+
+        /// Configuration profile to use
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Profile variable as key=value, overriding the profile's vars, may be repeated
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Base URL (overrides config)
+        #[arg(short, long)]
+        url: Option<String>,
+
+        /// Bearer token
+        #[arg(short, long)]
+        token: Option<String>,
+
+        /// HTTP headers (key:value,key2:value2)
+        #[arg(short = 'H', long)]
+        headers: Option<String>,
+
+        /// Enable detailed output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Run or save a request given as a curl command line
+    FromCurl {
+        /// The curl command, quoted as one argument or given after `--`
+        #[arg(value_name = "COMMAND", required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+
+        /// Save as a named request in asterisk.config instead of sending it
+        #[arg(short, long, value_name = "NAME")]
+        save: Option<String>,
+
+        /// Print the generated configuration without writing it
+        #[arg(long, requires = "save")]
+        dry_run: bool,
+
+        /// Send to this profile instead of the URL in the command
+        #[arg(short, long, conflicts_with = "save")]
+        profile: Option<String>,
+
+        /// Enable detailed output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ImportSource {
+    /// Import a Postman v2.1 collection
+    Postman {
+        /// Path to the exported collection JSON
+        #[arg(value_name = "COLLECTION")]
+        collection: String,
+
+        /// Postman environment JSON, imported as a profile (may be repeated)
+        #[arg(short, long = "env", value_name = "ENV")]
+        env: Vec<String>,
+
+        /// Print the generated configuration without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Import requests captured in an HTTP Archive (HAR) file
+    Har {
+        /// Path to the .har file
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Print the generated configuration without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Import an OpenAPI 3 document (YAML or JSON)
+    Openapi {
+        /// Path to the OpenAPI spec
+        #[arg(value_name = "SPEC")]
+        spec: String,
+
+        /// Print the generated configuration without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Per-invocation overrides for a saved request
+#[derive(Args, Debug, Default)]
+pub struct RequestOverrides {
+    /// Configuration profile to use
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    /// Base URL (overrides config)
+    #[arg(short, long)]
+    pub url: Option<String>,
+
+    /// Bearer token
+    #[arg(short, long)]
+    pub token: Option<String>,
+
+    /// HTTP headers (key:value,key2:value2), replacing the saved request headers
+    #[arg(short = 'H', long)]
+    pub headers: Option<String>,
+
+    /// HTTP method
+    #[arg(short = 'X', long)]
+    pub method: Option<String>,
+
+    /// Endpoint relative to the base URL
+    #[arg(short, long)]
+    pub endpoint: Option<String>,
+
+    /// JSON request body
+    #[arg(short, long, conflicts_with = "body_file")]
+    pub body: Option<String>,
+
+    /// File containing the request body
+    #[arg(long)]
+    pub body_file: Option<String>,
+
+    /// Query parameter (key=value), may be repeated
+    #[arg(short, long = "query", value_name = "KEY=VALUE")]
+    pub query: Vec<String>,
+
+    /// Profile variable as key=value, overriding the profile's vars, may be repeated
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    pub vars: Vec<String>,
+
+    /// Enable detailed output
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Print the resolved request in this format instead of sending it
+    #[arg(long = "as", value_name = "FORMAT")]
+    pub export_as: Option<ExportFormat>,
+
+    /// Replace credentials with $VAR references in exported requests
+    #[arg(long, requires = "export_as")]
+    pub mask_secrets: bool,
+
+    /// Save the response body to this file instead of printing it
+    #[arg(short, long, value_name = "FILE", conflicts_with = "export_as")]
+    pub output: Option<String>,
+
+    /// Continue a partial --output file with a Range request
+    #[arg(long, requires = "output")]
+    pub resume: bool,
+
+    #[command(flatten)]
+    pub streaming: StreamArgs,
 }
 
 #[derive(Subcommand)]
@@ -118,7 +675,72 @@ pub enum ConfigAction {
         /// Profile to show (defaults to current/default profile)
         #[arg(short, long)]
         profile: Option<String>,
+
+        /// Show where each value comes from ([defaults] or a profile it extends)
+        #[arg(long)]
+        origin: bool,
+
+        /// Profile variable as key=value, overriding the profile's vars, may be repeated
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
     },
     /// Initialize asterisk.config in current directory
     Init,
 }
+
+#[derive(Subcommand)]
+pub enum SecretAction {
+    /// Encrypt a value into asterisk.config as enc:...
+    Set {
+        /// Profile setting to store: token, auth.token, auth.password or auth.value (e.g. staging.token)
+        #[arg(value_name = "PROFILE.SETTING")]
+        setting: String,
+
+        /// Value to encrypt (read from stdin when omitted, keeping it out of shell history)
+        value: Option<String>,
+    },
+    /// Re-encrypt every enc: value with a new key
+    RotateKey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_false_environment_flags() {
+        // Variables set to false must not count as given flags, so `requires` and
+        // `conflicts_with` don't fire for them
+        let names = ["ASTERISK_MASK_SECRETS", "ASTERISK_STREAM", "ASTERISK_RESUME", "ASTERISK_RECONNECT"];
+        for name in names {
+            std::env::set_var(name, "false");
+        }
+        let mut cli = Cli::try_parse_from(["asterisk", "users", "get", "-o", "users.json"]).unwrap();
+        assert_eq!(cli.apply_environment(), Ok(()));
+        assert!(!cli.mask_secrets && !cli.streaming.stream && !cli.resume && !cli.streaming.reconnect);
+
+        std::env::set_var("ASTERISK_RESUME", "1");
+        let mut cli = Cli::try_parse_from(["asterisk", "users", "get"]).unwrap();
+        assert!(cli.apply_environment().unwrap_err().contains("--resume"));
+        let mut cli = Cli::try_parse_from(["asterisk", "users", "get", "-o", "users.json"]).unwrap();
+        assert_eq!(cli.apply_environment(), Ok(()));
+        assert!(cli.resume);
+        for name in names {
+            std::env::remove_var(name);
+        }
+    }
+
+    #[test]
+    fn test_gql_variable_options() {
+        let cli = Cli::try_parse_from([
+            "asterisk", "gql", "user.graphql", "--gql-var", "id=1", "--var", "tenant=acme",
+        ]).unwrap();
+        match cli.command {
+            Some(Commands::Gql { gql_vars, vars, .. }) => {
+                assert_eq!(gql_vars, vec!["id=1"]);
+                assert_eq!(vars, vec!["tenant=acme"]);
+            }
+            _ => panic!("expected gql command"),
+        }
+    }
+}
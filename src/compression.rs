@@ -0,0 +1,154 @@
+use crate::error::{AsteriskError, Result};
+use clap::ValueEnum;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use std::io::{Read, Write};
+use std::sync::OnceLock;
+
+/// `Accept-Encoding` sent unless the request sets its own
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+/// Content codings asterisk can produce and decode
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Deflate,
+    Br,
+    Zstd,
+}
+
+impl Compression {
+    /// Token used in `Content-Encoding`
+    pub fn token(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Deflate => "deflate",
+            Compression::Br => "br",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        match token.trim().to_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Compression::Gzip),
+            "deflate" => Some(Compression::Deflate),
+            "br" => Some(Compression::Br),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Compression settings for every request of the invocation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodingOptions {
+    /// Keep response bodies exactly as received instead of decoding them
+    pub raw: bool,
+
+    /// Compress request bodies with this coding
+    pub compress_body: Option<Compression>,
+}
+
+static OPTIONS: OnceLock<EncodingOptions> = OnceLock::new();
+
+/// Sets the options used for the rest of the invocation
+pub fn configure(options: EncodingOptions) {
+    let _ = OPTIONS.set(options);
+}
+
+/// Options set by `configure`
+pub fn options() -> EncodingOptions {
+    OPTIONS.get().copied().unwrap_or_default()
+}
+
+/// Compresses a request body
+pub fn compress(bytes: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    let compressed = match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()?
+        }
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()?
+        }
+        Compression::Br => {
+            let mut output = Vec::new();
+            let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 5, 22);
+            encoder.write_all(bytes)?;
+            encoder.flush()?;
+            drop(encoder);
+            output
+        }
+        Compression::Zstd => zstd::encode_all(bytes, 0)?,
+    };
+    Ok(compressed)
+}
+
+/// Decodes a body according to its `Content-Encoding`
+/// Codings are listed in the order they were applied, so they are undone from the last one.
+pub fn decode(bytes: &[u8], content_encoding: &str) -> Result<Vec<u8>> {
+    let mut body = bytes.to_vec();
+    for token in content_encoding.split(',').rev().map(str::trim).filter(|token| !token.is_empty()) {
+        if token.eq_ignore_ascii_case("identity") {
+            continue;
+        }
+        let compression = Compression::from_token(token).ok_or_else(|| AsteriskError::Config(
+            format!("Unsupported Content-Encoding '{}' (use --raw-encoding to see the body as received)", token)
+        ))?;
+        body = decode_one(&body, compression).map_err(|e| AsteriskError::Config(
+            format!("Failed to decode {} response body: {}", token, e)
+        ))?;
+    }
+    Ok(body)
+}
+
+fn decode_one(bytes: &[u8], compression: Compression) -> std::io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    match compression {
+        Compression::Gzip => {
+            GzDecoder::new(bytes).read_to_end(&mut output)?;
+        }
+        // Servers send both zlib-wrapped and raw deflate under this name
+        Compression::Deflate => {
+            if ZlibDecoder::new(bytes).read_to_end(&mut output).is_err() {
+                output.clear();
+                flate2::read::DeflateDecoder::new(bytes).read_to_end(&mut output)?;
+            }
+        }
+        Compression::Br => {
+            brotli::Decompressor::new(bytes, 4096).read_to_end(&mut output)?;
+        }
+        Compression::Zstd => {
+            output = zstd::decode_all(bytes)?;
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let body = br#"{"name":"john","tags":["a","a","a","a","a","a","a","a"]}"#;
+        for compression in [Compression::Gzip, Compression::Deflate, Compression::Br, Compression::Zstd] {
+            let compressed = compress(body, compression).unwrap();
+            assert_ne!(compressed, body.to_vec());
+            assert_eq!(decode(&compressed, compression.token()).unwrap(), body.to_vec(), "{:?}", compression);
+        }
+    }
+
+    #[test]
+    fn test_decode_stacked_and_unknown_codings() {
+        let once = compress(b"hello", Compression::Gzip).unwrap();
+        let twice = compress(&once, Compression::Br).unwrap();
+        assert_eq!(decode(&twice, "gzip, br").unwrap(), b"hello");
+        assert_eq!(decode(b"hello", "identity").unwrap(), b"hello");
+        assert!(decode(b"hello", "compress").is_err());
+        assert!(decode(b"not gzip", "gzip").is_err());
+    }
+}
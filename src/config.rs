@@ -1,14 +1,38 @@
-use crate::environment::EnvironmentResolver;
+use crate::environment::{variable_reference, EnvironmentResolver};
 use crate::error::{AsteriskError, Result};
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::openapi::OpenApiSpec;
+use crate::secrets::{self, SecretKey, KEY_FILENAME};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Configuration file name to search for in project directories
 const CONFIG_FILENAME: &str = "asterisk.config";
 
+/// Uncommitted overrides read from next to the project configuration
+const LOCAL_CONFIG_FILENAME: &str = "asterisk.local.config";
+
+/// Dotenv file loaded from next to the project configuration
+const ENV_FILENAME: &str = ".env";
+
+/// User configuration, relative to `$XDG_CONFIG_HOME` or `~/.config`
+const USER_CONFIG_PATH: &str = "asterisk/config.toml";
+
+/// Profile settings `asterisk secret set` can encrypt
+const SECRET_SETTINGS: &[&str] = &["token", "auth.token", "auth.password", "auth.value"];
+
+/// Environment variables used when the matching option is not given on the command line
+pub const PROFILE_ENV: &str = "ASTERISK_PROFILE";
+pub const URL_ENV: &str = "ASTERISK_URL";
+pub const TOKEN_ENV: &str = "ASTERISK_TOKEN";
+pub const HEADERS_ENV: &str = "ASTERISK_HEADERS";
+pub const VERBOSE_ENV: &str = "ASTERISK_VERBOSE";
+
 /// Default configuration template for initialization
 const DEFAULT_CONFIG_TEMPLATE: &str = r#"# Asterisk Configuration
 # Project-level configuration for API testing
@@ -29,8 +53,23 @@ url = "https://staging-api.example.com"
 
 [profiles.production]
 url = "https://api.example.com"
-# token = "$PROD_API_KEY"
+# token = "cmd:pass show api/production"  # Or file:/run/secrets/api_token
 # headers = ["X-Environment:production", "X-Version:1.0"]
+
+# Variables such as DEV_API_KEY may also be set in a .env file next to this
+# file, or per profile with env_file = ".env.staging"
+
+# Personal tokens and overrides belong in asterisk.local.config (next to this
+# file, not committed) or ~/.config/asterisk/config.toml (all projects)
+
+# Shared credentials can be committed encrypted: asterisk secret set staging.token
+
+# Saved requests, runnable with `asterisk run <name>`
+# [requests.health]
+# description = "Service health check"
+# method = "GET"
+# endpoint = "health"
+# expect = { status = 200 }
 "#;
 
 /// Raw configuration structure as parsed from TOML
@@ -38,15 +77,32 @@ url = "https://api.example.com"
 pub struct RawConfig {
     /// Default profile to use when none specified
     pub default_profile: Option<String>,
+
+    /// OpenAPI spec describing the API, relative to the config file
+    pub openapi: Option<String>,
+
+    /// Settings shared by every profile, overridden by the profiles themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<RawProfile>,
     
     /// Map of profile name to profile configuration
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub profiles: HashMap<String, RawProfile>,
+
+    /// Map of saved request name to request definition
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub requests: HashMap<String, RawRequest>,
 }
 
 /// Raw profile configuration before environment variable resolution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RawProfile {
-    /// Base URL for API requests
+    /// Profile whose settings this one inherits and overrides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+
+    /// Base URL for API requests (may be inherited)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub url: String,
     
     /// Bearer token (may contain environment variable references)
@@ -57,11 +113,499 @@ pub struct RawProfile {
     
     /// Enable verbose output by default
     pub verbose: Option<bool>,
+
+    /// Authentication scheme (alternative to `token`)
+    pub auth: Option<RawAuth>,
+
+    /// Profile variables, referenced as `${vars.name}`
+    pub vars: Option<HashMap<String, String>>,
+
+    /// GraphQL endpoint used by `asterisk gql`, relative to `url` or absolute
+    pub graphql_endpoint: Option<String>,
+
+    /// Dotenv file loaded for this profile, relative to the configuration file
+    pub env_file: Option<String>,
+}
+
+/// Profile authentication, selected by the `type` key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RawAuth {
+    /// `Authorization: Bearer <token>`
+    Bearer { token: String },
+
+    /// `Authorization: Basic <base64(username:password)>`
+    Basic { username: String, password: Option<String> },
+
+    /// API key sent as a header or query parameter
+    ApiKey {
+        key: String,
+        value: String,
+        /// Either "header" (default) or "query"
+        #[serde(rename = "in", default, skip_serializing_if = "Option::is_none")]
+        location: Option<String>,
+    },
+}
+
+/// Saved request definition as parsed from a `[requests.<name>]` table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RawRequest {
+    /// Human readable description shown by `asterisk run --list`
+    pub description: Option<String>,
+
+    /// HTTP method (defaults to GET)
+    pub method: Option<String>,
+
+    /// Endpoint relative to the profile URL (may contain environment variable references)
+    pub endpoint: String,
+
+    /// HTTP headers in "key:value" format, added on top of the profile headers
+    pub headers: Option<Vec<String>>,
+
+    /// Inline request body
+    pub body: Option<String>,
+
+    /// Path to a file holding the request body, relative to the config file
+    pub body_file: Option<String>,
+
+    /// Query parameters appended to the request URL
+    pub query: Option<HashMap<String, String>>,
+
+    /// Expectations checked against the response
+    pub expect: Option<RawExpectation>,
+}
+
+/// Response expectations for a saved request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RawExpectation {
+    /// Expected HTTP status code
+    pub status: Option<u16>,
+
+    /// Substrings that must appear in the response body
+    pub body_contains: Option<Vec<String>>,
+
+    /// Require the response to conform to the configured OpenAPI spec
+    pub contract: Option<bool>,
+
+    /// JSON Schema file (relative to the config file) the response body must match
+    pub schema: Option<String>,
+}
+
+/// Layer each value of a merged profile came from, keyed by field name,
+/// `headers.<name>` (lowercase) or `vars.<name>`
+pub type Origins = BTreeMap<String, String>;
+
+impl RawConfig {
+    /// Profiles from the root of `name`'s `extends` chain down to `name` itself
+    fn inheritance_chain<'a>(&'a self, name: &'a str) -> Result<Vec<(&'a str, &'a RawProfile)>> {
+        let mut chain: Vec<(&str, &RawProfile)> = Vec::new();
+        let mut current = name;
+        loop {
+            if chain.iter().any(|(seen, _)| *seen == current) {
+                let mut cycle: Vec<&str> = chain.iter().map(|(seen, _)| *seen).collect();
+                cycle.push(current);
+                return Err(AsteriskError::Config(format!("Profile inheritance cycle: {}", cycle.join(" -> "))));
+            }
+            let profile = match self.profiles.get(current) {
+                Some(profile) => profile,
+                None if chain.is_empty() => {
+                    return Err(AsteriskError::Config(format!("Profile '{}' not found in configuration", current)));
+                }
+                None => {
+                    return Err(AsteriskError::Config(format!(
+                        "Profile '{}' extends unknown profile '{}'", chain[chain.len() - 1].0, current
+                    )));
+                }
+            };
+            chain.push((current, profile));
+            match profile.extends {
+                Some(ref parent) => current = parent,
+                None => break,
+            }
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// Builds profile `name` from `[defaults]` and its `extends` chain
+    /// Each layer overrides the one before it: `[defaults]`, then the root ancestor down
+    /// to the profile itself. Headers are merged by name and variables by key.
+    pub fn merged_profile(&self, name: &str) -> Result<(RawProfile, Origins)> {
+        let tables = self.defaults.iter()
+            .map(|defaults| ("[defaults]".to_string(), defaults))
+            .chain(self.inheritance_chain(name)?.into_iter()
+                .map(|(table, profile)| (format!("[profiles.{}]", table), profile)));
+        Ok(merge_tables(tables))
+    }
+
+    /// Overlays a configuration file of higher precedence
+    /// Profiles and `[defaults]` are merged key by key; saved requests replace by name.
+    fn overlay(&mut self, upper: RawConfig) {
+        if upper.default_profile.is_some() {
+            self.default_profile = upper.default_profile;
+        }
+        if upper.openapi.is_some() {
+            self.openapi = upper.openapi;
+        }
+        if let Some(defaults) = upper.defaults {
+            self.defaults.get_or_insert_with(RawProfile::default).overlay(&defaults);
+        }
+        for (name, profile) in upper.profiles {
+            match self.profiles.get_mut(&name) {
+                Some(existing) => existing.overlay(&profile),
+                None => {
+                    self.profiles.insert(name, profile);
+                }
+            }
+        }
+        self.requests.extend(upper.requests);
+    }
+}
+
+/// Applies profile tables in order, recording which one set each value
+fn merge_tables<'a>(tables: impl IntoIterator<Item = (String, &'a RawProfile)>) -> (RawProfile, Origins) {
+    let mut profile = RawProfile::default();
+    let mut origins = Origins::new();
+    for (label, table) in tables {
+        profile.apply_layer(table, &label, &mut origins);
+    }
+    (profile, origins)
+}
+
+/// Where the values of profile `name` come from across configuration files
+fn layered_origins(layers: &[(ConfigSource, RawConfig)], merged: &RawConfig, name: &str) -> Result<Origins> {
+    let mut tables: Vec<(String, &RawProfile)> = layers.iter()
+        .filter_map(|(source, config)| Some((format!("{} [defaults]", source.layer.name()), config.defaults.as_ref()?)))
+        .collect();
+    for (table, _) in merged.inheritance_chain(name)? {
+        tables.extend(layers.iter().filter_map(|(source, config)| {
+            Some((format!("{} [profiles.{}]", source.layer.name(), table), config.profiles.get(table)?))
+        }));
+    }
+    Ok(merge_tables(tables).1)
+}
+
+/// Configuration file layers, from lowest to highest precedence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigLayer {
+    /// `~/.config/asterisk/config.toml`, for personal profiles and tokens
+    User,
+
+    /// `asterisk.config` found from the working directory
+    Project,
+
+    /// `asterisk.local.config` next to the project file, kept out of version control
+    Local,
+}
+
+impl ConfigLayer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigLayer::User => "user",
+            ConfigLayer::Project => "project",
+            ConfigLayer::Local => "local",
+        }
+    }
+}
+
+/// How the project configuration file is found
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ConfigLocation {
+    /// Nearest asterisk.config from the working directory up to the git root
+    #[default]
+    Discovered,
+
+    /// Given with `--config`
+    Flag(PathBuf),
+
+    /// Given by the `ASTERISK_CONFIG` environment variable
+    Environment(PathBuf),
+}
+
+impl ConfigLocation {
+    /// Uses the `--config` value, then `ASTERISK_CONFIG`, then discovery
+    pub fn detect(flag: Option<&str>) -> Self {
+        match (flag, std::env::var("ASTERISK_CONFIG").ok().filter(|path| !path.is_empty())) {
+            (Some(path), _) => ConfigLocation::Flag(absolute(path)),
+            (None, Some(path)) => ConfigLocation::Environment(absolute(&path)),
+            (None, None) => ConfigLocation::Discovered,
+        }
+    }
+
+    /// How the project file was located, for `config show`
+    pub fn describe(&self) -> String {
+        match self {
+            ConfigLocation::Discovered => match std::env::current_dir() {
+                Ok(dir) => format!("searched from {} up to the git root", dir.display()),
+                Err(_) => "searched from the working directory".to_string(),
+            },
+            ConfigLocation::Flag(_) => "set with --config".to_string(),
+            ConfigLocation::Environment(_) => "set by ASTERISK_CONFIG".to_string(),
+        }
+    }
+}
+
+/// Value of an `ASTERISK_*` setting; empty values count as unset
+pub fn env_setting(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Value of a boolean `ASTERISK_*` setting (`1`/`0`, `true`/`false`, `yes`/`no`, `on`/`off`)
+pub fn env_flag(name: &str) -> Result<Option<bool>> {
+    match env_setting(name).map(|value| value.to_lowercase()).as_deref() {
+        None => Ok(None),
+        Some("1" | "true" | "yes" | "on") => Ok(Some(true)),
+        Some("0" | "false" | "no" | "off") => Ok(Some(false)),
+        Some(value) => Err(AsteriskError::InvalidEnvironmentVariable(
+            format!("{}={} (expected true or false)", name, value)
+        )),
+    }
+}
+
+/// `path` relative to `dir`, for paths written into a config file in `dir`
+/// Falls back to `path` when either cannot be resolved, or to the absolute path when
+/// they share no prefix (another drive on Windows).
+fn relative_path(path: &Path, dir: &Path) -> PathBuf {
+    let (Ok(path), Ok(dir)) = (fs::canonicalize(path), fs::canonicalize(dir)) else {
+        return path.to_path_buf();
+    };
+    let common = path.components().zip(dir.components()).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return path;
+    }
+    let mut relative: PathBuf = dir.components().skip(common).map(|_| "..").collect();
+    relative.extend(path.components().skip(common));
+    relative
+}
+
+/// The .gitignore in `dir` that should list `name` but does not
+/// Only in a git repository root or next to an existing .gitignore.
+fn missing_ignore(dir: &Path, name: &str) -> Option<PathBuf> {
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() && !dir.join(".git").exists() {
+        return None;
+    }
+    let content = fs::read_to_string(&gitignore).unwrap_or_default();
+    let listed = content.lines().any(|line| line.trim().trim_start_matches('/') == name);
+    (!listed).then_some(gitignore)
+}
+
+/// Adds `name` to the .gitignore in `dir` (see `missing_ignore`)
+/// Returns the .gitignore path when it was changed.
+fn ignore_file(dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    let Some(gitignore) = missing_ignore(dir, name) else {
+        return Ok(None);
+    };
+
+    let mut content = fs::read_to_string(&gitignore).unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!("{}\n", name));
+    fs::write(&gitignore, content)?;
+    Ok(Some(gitignore))
+}
+
+/// Parses a configuration file for editing in place
+fn read_document(path: &Path) -> Result<toml_edit::DocumentMut> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AsteriskError::Config(format!("Failed to read config file {}: {}", path.display(), e)))?;
+    content.parse()
+        .map_err(|e| AsteriskError::Config(format!("Failed to parse config file {}: {}", path.display(), e)))
+}
+
+fn write_document(path: &Path, document: &toml_edit::DocumentMut) -> Result<()> {
+    fs::write(path, document.to_string())
+        .map_err(|e| AsteriskError::Config(format!("Failed to write config file {}: {}", path.display(), e)))
+}
+
+/// Writes a document next to `path` as `<path>.tmp`, to be moved into place with `fs::rename`
+fn write_temp_document(path: &Path, document: &toml_edit::DocumentMut) -> Result<PathBuf> {
+    let temp = sibling(path, "tmp");
+    write_document(&temp, document)?;
+    Ok(temp)
+}
+
+/// `path` with `.suffix` appended, e.g. asterisk.config.bak
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), suffix))
+}
+
+/// Replaces re-encrypted configs and then the key file, for `rotate_secret_key`
+/// Every config is written to a temporary file (recorded in `staged`) before any is replaced,
+/// and each replaced config is backed up first (recorded in `replaced`) so the caller can
+/// roll back.
+fn replace_documents<'a>(
+    documents: Vec<(PathBuf, toml_edit::DocumentMut, usize)>,
+    new_key: &'a SecretKey,
+    staged: &mut Vec<(PathBuf, PathBuf, usize)>,
+    replaced: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<Option<&'a Path>> {
+    for (path, document, count) in documents {
+        let temp = sibling(&path, "tmp");
+        staged.push((path.clone(), temp, count));
+        write_temp_document(&path, &document)?;
+    }
+    new_key.stage()?;
+
+    for (path, temp, _) in staged.iter() {
+        let backup = sibling(path, "bak");
+        fs::copy(path, &backup).map_err(|e| AsteriskError::Config(
+            format!("Failed to back up config file {}: {}", path.display(), e)
+        ))?;
+        replaced.push((path.clone(), backup));
+        fs::rename(temp, path).map_err(|e| AsteriskError::Config(
+            format!("Failed to replace config file {}: {}", path.display(), e)
+        ))?;
+    }
+    new_key.replace_key_file()
+}
+
+/// The first `enc:` string in a TOML item
+fn find_encrypted(item: &toml_edit::Item) -> Option<&str> {
+    match item {
+        toml_edit::Item::Table(table) => table.iter().find_map(|(_, item)| find_encrypted(item)),
+        toml_edit::Item::ArrayOfTables(tables) => tables.iter()
+            .flat_map(|table| table.iter())
+            .find_map(|(_, item)| find_encrypted(item)),
+        toml_edit::Item::Value(value) => find_encrypted_value(value),
+        toml_edit::Item::None => None,
+    }
+}
+
+fn find_encrypted_value(value: &toml_edit::Value) -> Option<&str> {
+    match value {
+        toml_edit::Value::String(string) if secrets::is_encrypted(string.value()) => Some(string.value()),
+        toml_edit::Value::Array(array) => array.iter().find_map(find_encrypted_value),
+        toml_edit::Value::InlineTable(table) => table.iter().find_map(|(_, value)| find_encrypted_value(value)),
+        _ => None,
+    }
+}
+
+/// Re-encrypts the `enc:` strings in a TOML item, returning how many there were
+fn reencrypt(item: &mut toml_edit::Item, old_key: &mut SecretKey, new_key: &mut SecretKey) -> Result<usize> {
+    match item {
+        toml_edit::Item::Table(table) => table.iter_mut()
+            .map(|(_, item)| reencrypt(item, old_key, new_key))
+            .sum(),
+        toml_edit::Item::ArrayOfTables(tables) => tables.iter_mut()
+            .flat_map(|table| table.iter_mut())
+            .map(|(_, item)| reencrypt(item, old_key, new_key))
+            .sum(),
+        toml_edit::Item::Value(value) => reencrypt_value(value, old_key, new_key),
+        toml_edit::Item::None => Ok(0),
+    }
+}
+
+fn reencrypt_value(value: &mut toml_edit::Value, old_key: &mut SecretKey, new_key: &mut SecretKey) -> Result<usize> {
+    match value {
+        toml_edit::Value::String(string) if secrets::is_encrypted(string.value()) => {
+            let plaintext = old_key.decrypt(string.value())?;
+            let decor = string.decor().clone();
+            *string = toml_edit::Formatted::new(new_key.encrypt(&plaintext)?);
+            *string.decor_mut() = decor;
+            Ok(1)
+        }
+        toml_edit::Value::Array(array) => array.iter_mut()
+            .map(|value| reencrypt_value(value, old_key, new_key))
+            .sum(),
+        toml_edit::Value::InlineTable(table) => table.iter_mut()
+            .map(|(_, value)| reencrypt_value(value, old_key, new_key))
+            .sum(),
+        _ => Ok(0),
+    }
+}
+
+/// Resolves a path against the working directory
+fn absolute(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    match std::env::current_dir() {
+        Ok(dir) if path.is_relative() => dir.join(path),
+        _ => path,
+    }
+}
+
+/// A configuration file in effect and its layer
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    pub layer: ConfigLayer,
+    pub path: PathBuf,
+}
+
+impl RawProfile {
+    /// The token and auth secret, which may name a secret source
+    fn credentials(&self) -> Vec<&str> {
+        let auth = match self.auth {
+            Some(RawAuth::Bearer { ref token }) => Some(token.as_str()),
+            Some(RawAuth::Basic { ref password, .. }) => password.as_deref(),
+            Some(RawAuth::ApiKey { ref value, .. }) => Some(value.as_str()),
+            None => None,
+        };
+        self.token.as_deref().into_iter().chain(auth).collect()
+    }
+
+    /// Overlays the same profile table from a configuration file of higher precedence
+    fn overlay(&mut self, upper: &RawProfile) {
+        if upper.extends.is_some() {
+            self.extends = upper.extends.clone();
+        }
+        self.apply_layer(upper, "", &mut Origins::new());
+    }
+
+    /// Overrides this profile with the values set in `layer`
+    fn apply_layer(&mut self, layer: &RawProfile, label: &str, origins: &mut Origins) {
+        if !layer.url.is_empty() {
+            self.url = layer.url.clone();
+            origins.insert("url".to_string(), label.to_string());
+        }
+
+        // `token` and `auth` are alternatives, so a layer setting either replaces both
+        if layer.token.is_some() || layer.auth.is_some() {
+            self.token = layer.token.clone();
+            self.auth = layer.auth.clone();
+            origins.remove("token");
+            origins.remove("auth");
+            let field = if layer.token.is_some() { "token" } else { "auth" };
+            origins.insert(field.to_string(), label.to_string());
+        }
+
+        if let Some(verbose) = layer.verbose {
+            self.verbose = Some(verbose);
+            origins.insert("verbose".to_string(), label.to_string());
+        }
+        if let Some(ref endpoint) = layer.graphql_endpoint {
+            self.graphql_endpoint = Some(endpoint.clone());
+            origins.insert("graphql_endpoint".to_string(), label.to_string());
+        }
+        if let Some(ref env_file) = layer.env_file {
+            self.env_file = Some(env_file.clone());
+            origins.insert("env_file".to_string(), label.to_string());
+        }
+
+        // A header replaces an inherited one with the same name, keeping its position
+        for header in layer.headers.iter().flatten() {
+            let name = header.split(':').next().unwrap_or("").trim().to_lowercase();
+            let headers = self.headers.get_or_insert_with(Vec::new);
+            match headers.iter().position(|existing| existing.split(':').next().unwrap_or("").trim().eq_ignore_ascii_case(&name)) {
+                Some(index) => headers[index] = header.clone(),
+                None => headers.push(header.clone()),
+            }
+            origins.insert(format!("headers.{}", name), label.to_string());
+        }
+
+        for (key, value) in layer.vars.iter().flatten() {
+            self.vars.get_or_insert_with(HashMap::new).insert(key.clone(), value.clone());
+            origins.insert(format!("vars.{}", key), label.to_string());
+        }
+    }
 }
 
 /// Resolved configuration with environment variables expanded
 #[derive(Debug, Clone)]
 pub struct ResolvedConfig {
+    /// Name of the profile in use (None when no config file was found)
+    pub profile: Option<String>,
+
     /// Base URL for API requests
     pub url: String,
     
@@ -70,14 +614,123 @@ pub struct ResolvedConfig {
     
     /// HTTP headers as HeaderMap
     pub headers: HeaderMap,
+
+    /// Query parameters added by the profile (e.g. API key auth)
+    pub query: Vec<(String, String)>,
     
     /// Enable verbose output
     pub verbose: bool,
+
+    /// Resolved credentials, used to mask exported requests
+    pub secrets: Vec<Secret>,
+
+    /// GraphQL endpoint of the profile (resolved)
+    pub graphql_endpoint: Option<String>,
+}
+
+/// A resolved credential and the variable it can be referenced by
+#[derive(Debug, Clone, PartialEq)]
+pub struct Secret {
+    /// Resolved value as sent on the wire
+    pub value: String,
+
+    /// Variable name (without `$`) used in place of the value
+    pub variable: String,
+}
+
+impl ResolvedConfig {
+    /// Drops the profile credentials unless `url` is on the profile URL's origin
+    /// Saved requests may hold absolute URLs of other hosts (e.g. from a HAR import), which
+    /// must not receive the profile's token, auth headers or API key. Returns whether
+    /// credentials were dropped.
+    pub fn restrict_to_origin(&mut self, url: &str) -> bool {
+        let origin = |url: &str| Url::parse(url).ok().map(|url| url.origin());
+        if origin(&self.url) == origin(url) {
+            return false;
+        }
+
+        let mut headers = HeaderMap::new();
+        let mut dropped = self.token.take().is_some() || !self.query.is_empty();
+        for (name, value) in &self.headers {
+            let text = String::from_utf8_lossy(value.as_bytes());
+            if is_credential_header(name, value) || self.secrets.iter().any(|secret| text.contains(&secret.value)) {
+                dropped = true;
+            } else {
+                headers.append(name, value.clone());
+            }
+        }
+        self.headers = headers;
+        self.query.clear();
+        self.secrets.clear();
+        dropped
+    }
+}
+
+/// Whether a header carries credentials: authorization and cookie headers, and auth-generated headers
+pub fn is_credential_header(name: &HeaderName, value: &HeaderValue) -> bool {
+    value.is_sensitive() || [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE].contains(name)
+}
+
+impl Secret {
+    /// Names the secret after the variable it was resolved from, or `fallback`
+    fn new(value: String, raw: Option<&str>, fallback: &str) -> Self {
+        let variable = raw.and_then(variable_reference).unwrap_or(fallback).to_string();
+        Self { value, variable }
+    }
+}
+
+/// Summary of merging imported profiles and requests into asterisk.config
+#[derive(Debug)]
+pub struct ImportOutcome {
+    /// Config file that was written
+    pub path: PathBuf,
+
+    /// Whether a new config file was created
+    pub created: bool,
+
+    /// Names of profiles that were added
+    pub added_profiles: Vec<String>,
+
+    /// Names of saved requests that were added
+    pub added_requests: Vec<String>,
+
+    /// Profiles and requests skipped because the name already exists
+    pub skipped: Vec<String>,
+}
+
+/// Result of encrypting a value into asterisk.config
+#[derive(Debug)]
+pub struct SecretUpdate {
+    /// Config file that was written
+    pub path: PathBuf,
+
+    /// Key file created for the first secret
+    pub created_key: Option<PathBuf>,
+
+    /// .gitignore the created key file (and its backups) was added to
+    pub ignored_in: Option<PathBuf>,
+}
+
+/// Result of re-encrypting every `enc:` value with a new key
+#[derive(Debug)]
+pub struct KeyRotation {
+    /// Config files that were rewritten, with the number of values in each
+    pub files: Vec<(PathBuf, usize)>,
+
+    /// The new key file; `None` for a passphrase
+    pub key_file: Option<PathBuf>,
+
+    /// Config files with `enc:` values under another key file, which were not rotated
+    pub skipped: Vec<PathBuf>,
 }
 
 /// Configuration manager that handles loading, parsing, and resolving configurations
 pub struct ConfigManager {
     environment_resolver: EnvironmentResolver,
+    variable_overrides: HashMap<String, String>,
+    location: ConfigLocation,
+    /// Directory the user config is found in; `None` uses `$XDG_CONFIG_HOME` or `~/.config`
+    config_home: Option<PathBuf>,
 }
 
 impl ConfigManager {
@@ -85,11 +738,25 @@ impl ConfigManager {
     pub fn new() -> Self {
         Self {
             environment_resolver: EnvironmentResolver::new(),
+            variable_overrides: HashMap::new(),
+            location: ConfigLocation::Discovered,
+            config_home: None,
         }
     }
 
+    /// Sets how the project configuration file is found
+    pub fn set_location(&mut self, location: ConfigLocation) {
+        self.location = location;
+    }
+
+    /// How the project configuration file is found
+    pub fn location(&self) -> &ConfigLocation {
+        &self.location
+    }
+
     /// Loads and resolves configuration for the current working directory
-    /// Applies CLI overrides and returns the final resolved configuration
+    /// Applies CLI overrides and returns the final resolved configuration. Options not given
+    /// on the command line fall back to `ASTERISK_*` environment variables, then the config.
     pub fn load_resolved_config(
         &mut self,
         profile_override: Option<&str>,
@@ -98,16 +765,36 @@ impl ConfigManager {
         headers_override: Option<&str>,
         verbose_override: bool,
     ) -> Result<ResolvedConfig> {
+        let (profile_env, url_env, token_env, headers_env) =
+            (env_setting(PROFILE_ENV), env_setting(URL_ENV), env_setting(TOKEN_ENV), env_setting(HEADERS_ENV));
+        let profile_override = profile_override.or(profile_env.as_deref());
+        let url_override = url_override.or(url_env.as_deref());
+        // A token from the environment is masked as a reference to that variable
+        let token_variable = if token_override.is_none() && token_env.is_some() { TOKEN_ENV } else { "API_TOKEN" };
+        let token_override = token_override.or(token_env.as_deref());
+        let headers_override = headers_override.or(headers_env.as_deref());
+        let verbose_env = env_flag(VERBOSE_ENV)?;
+
         // Find and load project configuration
         let raw_config = match self.find_and_load_config()? {
             Some(config) => config,
             None => {
                 // No config file found, use defaults with CLI overrides
+                self.environment_resolver.set_variables(HashMap::new());
+                self.environment_resolver.set_overrides(self.variable_overrides.clone());
+                self.environment_resolver.set_env_files(&[])?;
                 return Ok(ResolvedConfig {
+                    profile: None,
                     url: url_override.unwrap_or("http://localhost:3000").to_string(),
                     token: token_override.map(|t| t.to_string()),
                     headers: self.parse_headers_string(headers_override.unwrap_or(""), token_override)?,
-                    verbose: verbose_override,
+                    query: Vec::new(),
+                    verbose: verbose_override || verbose_env.unwrap_or(false),
+                    secrets: token_override
+                        .map(|token| Secret::new(token.to_string(), None, token_variable))
+                        .into_iter()
+                        .collect(),
+                    graphql_endpoint: None,
                 });
             }
         };
@@ -117,11 +804,24 @@ impl ConfigManager {
             .or(raw_config.default_profile.as_deref())
             .unwrap_or("dev");
 
-        // Get the specified profile
-        let raw_profile = raw_config.profiles.get(profile_name)
-            .ok_or_else(|| AsteriskError::Config(
-                format!("Profile '{}' not found in configuration", profile_name)
-            ))?;
+        // Get the specified profile with its defaults and parents applied
+        let (raw_profile, _) = raw_config.merged_profile(profile_name)?;
+        if raw_profile.url.is_empty() {
+            return Err(AsteriskError::Config(
+                format!("Profile '{}' has no URL and none is inherited", profile_name)
+            ));
+        }
+
+        // Profile variables take precedence over environment variables,
+        // command line variables over both
+        self.environment_resolver.set_variables(raw_profile.vars.clone().unwrap_or_default());
+        self.environment_resolver.set_overrides(self.variable_overrides.clone());
+        let env_files = self.env_files(&raw_config, profile_name, &raw_profile)?;
+        self.environment_resolver.set_env_files(&env_files)?;
+        let layers = self.load_layers()?;
+        let chain: Vec<String> = raw_config.inheritance_chain(profile_name)?.into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
 
         // Resolve environment variables in profile
         let resolved_url = if let Some(url_override) = url_override {
@@ -130,35 +830,123 @@ impl ConfigManager {
             self.environment_resolver.resolve(&raw_profile.url)?
         };
 
-        let resolved_token = if let Some(token_override) = token_override {
-            Some(token_override.to_string())
-        } else if let Some(ref token) = raw_profile.token {
-            Some(self.environment_resolver.resolve(token)?)
-        } else {
-            None
+        let raw_token = match raw_profile.auth {
+            Some(RawAuth::Bearer { ref token }) if raw_profile.token.is_none() => Some(token.as_str()),
+            _ => raw_profile.token.as_deref(),
+        };
+        let resolved_token = match (token_override, raw_token) {
+            (Some(token_override), _) => Some(token_override.to_string()),
+            (None, Some(token)) => Some(self.resolve_credential(&layers, &chain, token)?),
+            (None, None) => None,
         };
 
+        let mut secrets: Vec<Secret> = resolved_token.iter()
+            .map(|token| Secret::new(token.clone(), raw_token.filter(|_| token_override.is_none()), token_variable))
+            .collect();
+
         // Parse headers with CLI override taking precedence
-        let headers = if let Some(headers_override) = headers_override {
+        let mut headers = if let Some(headers_override) = headers_override {
             self.parse_headers_string(headers_override, resolved_token.as_deref())?
         } else {
-            self.parse_profile_headers(raw_profile, resolved_token.as_deref())?
+            self.parse_profile_headers(&raw_profile, resolved_token.as_deref())?
         };
 
+        // Apply non-bearer authentication unless a token was given on the command line
+        let mut query = Vec::new();
+        if token_override.is_none() {
+            if let Some(ref auth) = raw_profile.auth {
+                self.apply_auth(auth, &layers, &chain, &mut headers, &mut query, &mut secrets)?;
+            }
+        }
+
         // Determine verbose setting
-        let verbose = verbose_override || raw_profile.verbose.unwrap_or(false);
+        let verbose = verbose_override || verbose_env.or(raw_profile.verbose).unwrap_or(false);
+
+        let graphql_endpoint = match raw_profile.graphql_endpoint {
+            Some(ref endpoint) => Some(self.environment_resolver.resolve(endpoint)?),
+            None => None,
+        };
 
         Ok(ResolvedConfig {
+            profile: Some(profile_name.to_string()),
             url: resolved_url,
             token: resolved_token,
             headers,
+            query,
             verbose,
+            secrets: secrets.into_iter().filter(|secret| !secret.value.is_empty()).collect(),
+            graphql_endpoint,
         })
     }
 
+    /// Resolves a profile credential, which may name a secret source
+    /// Relative `file:` paths and the `enc:` key file are found next to the configuration
+    /// file that sets the value. `cmd:` only runs from the user and local files, as the
+    /// project file may come with a cloned repository.
+    fn resolve_credential(&mut self, layers: &[(ConfigSource, RawConfig)], chain: &[String], value: &str) -> Result<String> {
+        let source = layers.iter().rev()
+            .find(|(_, config)| config.defaults.iter()
+                .chain(chain.iter().filter_map(|name| config.profiles.get(name)))
+                .any(|profile| profile.credentials().contains(&value)))
+            .map(|(source, _)| source);
+        let dir = source.and_then(|source| source.path.parent());
+        let allow_commands = source.is_some_and(|source| source.layer != ConfigLayer::Project);
+        self.environment_resolver.resolve_secret(value, dir, allow_commands)
+    }
+
+    /// Applies basic and API key authentication to the request headers or query
+    fn apply_auth(
+        &mut self,
+        auth: &RawAuth,
+        layers: &[(ConfigSource, RawConfig)],
+        chain: &[String],
+        headers: &mut HeaderMap,
+        query: &mut Vec<(String, String)>,
+        secrets: &mut Vec<Secret>,
+    ) -> Result<()> {
+        match auth {
+            RawAuth::Bearer { .. } => {}
+            RawAuth::Basic { username, password } => {
+                let username = self.environment_resolver.resolve(username)?;
+                let password = match password {
+                    Some(password) => self.resolve_credential(layers, chain, password)?,
+                    None => String::new(),
+                };
+                let credentials = BASE64.encode(format!("{}:{}", username, password));
+                secrets.push(Secret::new(credentials.clone(), None, "API_BASIC_CREDENTIALS"));
+                let mut value = HeaderValue::from_str(&format!("Basic {}", credentials))
+                    .map_err(|_| AsteriskError::InvalidHeaders)?;
+                value.set_sensitive(true);
+                headers.insert(AUTHORIZATION, value);
+            }
+            RawAuth::ApiKey { key, value: raw_value, location } => {
+                let value = self.resolve_credential(layers, chain, raw_value)?;
+                secrets.push(Secret::new(value.clone(), Some(raw_value), "API_KEY"));
+                match location.as_deref().unwrap_or("header") {
+                    "query" => query.push((key.clone(), value)),
+                    _ => {
+                        let mut header_value = HeaderValue::from_str(&value)
+                            .map_err(|_| AsteriskError::InvalidHeaders)?;
+                        header_value.set_sensitive(true);
+                        headers.insert(
+                            HeaderName::from_bytes(key.as_bytes()).map_err(|_| AsteriskError::InvalidHeaders)?,
+                            header_value,
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Finds asterisk.config by walking up the directory tree
-    /// Returns the path to the config file if found
+    /// Returns the path to the config file if found. A path given with `--config` or
+    /// `ASTERISK_CONFIG` is returned as is, so a missing file is reported when loading.
     pub fn find_config_file(&self) -> Option<PathBuf> {
+        match self.location {
+            ConfigLocation::Flag(ref path) | ConfigLocation::Environment(ref path) => return Some(path.clone()),
+            ConfigLocation::Discovered => {}
+        }
         let mut current_dir = std::env::current_dir().ok()?;
         
         loop {
@@ -182,29 +970,107 @@ impl ConfigManager {
         None
     }
 
-    /// Loads and parses configuration from file
-    fn find_and_load_config(&self) -> Result<Option<RawConfig>> {
-        let config_path = match self.find_config_file() {
-            Some(path) => path,
-            None => return Ok(None),
+    /// Finds the user configuration file, if it exists
+    fn find_user_config_file(&self) -> Option<PathBuf> {
+        let config_home = match (&self.config_home, std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty())) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(dir)) => PathBuf::from(dir),
+            (None, None) => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
         };
+        let path = config_home.join(USER_CONFIG_PATH);
+        path.exists().then_some(path)
+    }
 
-        let config_content = fs::read_to_string(&config_path)
-            .map_err(|e| AsteriskError::Config(
-                format!("Failed to read config file {}: {}", config_path.display(), e)
-            ))?;
+    /// Configuration files in effect, from lowest to highest precedence
+    pub fn config_sources(&self) -> Vec<ConfigSource> {
+        let mut sources = Vec::new();
+        if let Some(path) = self.find_user_config_file() {
+            sources.push(ConfigSource { layer: ConfigLayer::User, path });
+        }
+        if let Some(path) = self.find_config_file() {
+            let local = path.with_file_name(LOCAL_CONFIG_FILENAME);
+            sources.push(ConfigSource { layer: ConfigLayer::Project, path });
+            if local.exists() {
+                sources.push(ConfigSource { layer: ConfigLayer::Local, path: local });
+            }
+        }
+        sources
+    }
 
-        let raw_config: RawConfig = toml::from_str(&config_content)
-            .map_err(|e| AsteriskError::Config(
-                format!("Failed to parse config file {}: {}", config_path.display(), e)
-            ))?;
+    /// Parses every configuration file in effect, from lowest to highest precedence
+    fn load_layers(&self) -> Result<Vec<(ConfigSource, RawConfig)>> {
+        self.config_sources().into_iter()
+            .map(|source| {
+                let located = match (source.layer, &self.location) {
+                    (ConfigLayer::Project, ConfigLocation::Flag(_) | ConfigLocation::Environment(_)) => {
+                        format!(" ({})", self.location.describe())
+                    }
+                    _ => String::new(),
+                };
+                let config_content = fs::read_to_string(&source.path)
+                    .map_err(|e| AsteriskError::Config(
+                        format!("Failed to read config file {}{}: {}", source.path.display(), located, e)
+                    ))?;
+
+                let raw_config: RawConfig = toml::from_str(&config_content)
+                    .map_err(|e| AsteriskError::Config(
+                        format!("Failed to parse config file {}: {}", source.path.display(), e)
+                    ))?;
+                Ok((source, raw_config))
+            })
+            .collect()
+    }
 
-        // Validate configuration
+    /// Loads the configuration files and merges them into one
+    fn find_and_load_config(&self) -> Result<Option<RawConfig>> {
+        let mut layers = self.load_layers()?.into_iter().map(|(_, config)| config);
+        let Some(mut raw_config) = layers.next() else {
+            return Ok(None);
+        };
+        for upper in layers {
+            raw_config.overlay(upper);
+        }
+
+        // Layers may be partial, so only the merged result is validated
         self.validate_config(&raw_config)?;
 
         Ok(Some(raw_config))
     }
 
+    /// Directory of the highest-precedence configuration file matching `defines`
+    /// Paths in a configuration file are relative to that file.
+    fn defining_dir(&self, defines: impl Fn(&RawConfig) -> bool) -> Result<Option<PathBuf>> {
+        Ok(self.load_layers()?.into_iter()
+            .rev()
+            .find(|(_, config)| defines(config))
+            .and_then(|(source, _)| source.path.parent().map(Path::to_path_buf)))
+    }
+
+    /// Dotenv files for a profile: the project `.env`, then the profile's `env_file`
+    /// The `env_file` path is relative to the configuration file that sets it.
+    fn env_files(&self, config: &RawConfig, name: &str, profile: &RawProfile) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = self.find_config_file()
+            .map(|path| path.with_file_name(ENV_FILENAME))
+            .filter(|path| path.exists())
+            .into_iter()
+            .collect();
+
+        if let Some(ref env_file) = profile.env_file {
+            let chain: Vec<&str> = config.inheritance_chain(name)?.into_iter().map(|(name, _)| name).collect();
+            let dir = self.defining_dir(|layer| {
+                layer.defaults.as_ref().is_some_and(|defaults| defaults.env_file.is_some())
+                    || chain.iter().any(|name| layer.profiles.get(*name).is_some_and(|profile| profile.env_file.is_some()))
+            })?;
+            let path = match dir {
+                Some(dir) => dir.join(env_file),
+                None => PathBuf::from(env_file),
+            };
+            files.retain(|file| *file != path);
+            files.push(path);
+        }
+        Ok(files)
+    }
+
     /// Validates the loaded configuration
     fn validate_config(&self, config: &RawConfig) -> Result<()> {
         if config.profiles.is_empty() {
@@ -217,25 +1083,76 @@ impl ConfigManager {
         if let Some(ref default_profile) = config.default_profile {
             if !config.profiles.contains_key(default_profile) {
                 return Err(AsteriskError::Config(
-                    format!("Default profile '{}' not found in profiles", default_profile)
+                    format!("Default profile '{}' not found in profiles", default_profile)
+                ));
+            }
+        }
+
+        if config.defaults.as_ref().is_some_and(|defaults| defaults.extends.is_some()) {
+            return Err(AsteriskError::Config("[defaults] cannot use 'extends'".to_string()));
+        }
+
+        // Validate each profile, and [defaults] with the same rules
+        let layers = config.defaults.iter()
+            .map(|defaults| ("[defaults]".to_string(), defaults))
+            .chain(config.profiles.iter().map(|(name, profile)| (format!("profile '{}'", name), profile)));
+        for (name, profile) in layers {
+            // Validate header format if present
+            if let Some(ref headers) = profile.headers {
+                for header in headers {
+                    if !header.contains(':') {
+                        return Err(AsteriskError::Config(
+                            format!("Invalid header format in {}: '{}'. Expected 'key:value'", name, header)
+                        ));
+                    }
+                }
+            }
+
+            if profile.token.is_some() && profile.auth.is_some() {
+                return Err(AsteriskError::Config(
+                    format!("The {} cannot set both 'token' and 'auth'", name)
+                ));
+            }
+
+            if let Some(RawAuth::ApiKey { location: Some(ref location), .. }) = profile.auth {
+                if location != "header" && location != "query" {
+                    return Err(AsteriskError::Config(
+                        format!("Invalid API key location in {}: '{}'. Expected 'header' or 'query'", name, location)
+                    ));
+                }
+            }
+        }
+
+        // Profiles only used as a base through `extends` may leave the URL to their children
+        for name in config.profiles.keys() {
+            let (profile, _) = config.merged_profile(name)?;
+            let is_parent = config.profiles.values().any(|other| other.extends.as_deref() == Some(name.as_str()));
+            if profile.url.is_empty() && !is_parent {
+                return Err(AsteriskError::Config(
+                    format!("Profile '{}' must have a non-empty URL", name)
+                ));
+            }
+        }
+
+        // Validate each saved request
+        for (name, request) in &config.requests {
+            if request.endpoint.is_empty() {
+                return Err(AsteriskError::Config(
+                    format!("Request '{}' must have a non-empty endpoint", name)
                 ));
             }
-        }
 
-        // Validate each profile
-        for (name, profile) in &config.profiles {
-            if profile.url.is_empty() {
+            if request.body.is_some() && request.body_file.is_some() {
                 return Err(AsteriskError::Config(
-                    format!("Profile '{}' must have a non-empty URL", name)
+                    format!("Request '{}' cannot set both 'body' and 'body_file'", name)
                 ));
             }
 
-            // Validate header format if present
-            if let Some(ref headers) = profile.headers {
+            if let Some(ref headers) = request.headers {
                 for header in headers {
                     if !header.contains(':') {
                         return Err(AsteriskError::Config(
-                            format!("Invalid header format in profile '{}': '{}'. Expected 'key:value'", name, header)
+                            format!("Invalid header format in request '{}': '{}'. Expected 'key:value'", name, header)
                         ));
                     }
                 }
@@ -245,6 +1162,119 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Returns all saved requests from the project configuration, sorted by name
+    pub fn saved_requests(&self) -> Result<Vec<(String, RawRequest)>> {
+        let mut requests: Vec<(String, RawRequest)> = match self.find_and_load_config()? {
+            Some(config) => config.requests.into_iter().collect(),
+            None => Vec::new(),
+        };
+        requests.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(requests)
+    }
+
+    /// Looks up a saved request by name
+    pub fn saved_request(&self, name: &str) -> Result<RawRequest> {
+        let config = self.find_and_load_config()?.ok_or_else(|| AsteriskError::Config(
+            format!("Request '{}' not found: no {} file", name, CONFIG_FILENAME)
+        ))?;
+
+        let mut request = config.requests.get(name).cloned().ok_or_else(|| AsteriskError::Config(
+            format!("Request '{}' not found in configuration", name)
+        ))?;
+
+        // Body and schema files are relative to the config file, not the working directory
+        if let Some(config_dir) = self.defining_dir(|config| config.requests.contains_key(name))? {
+            if let Some(ref body_file) = request.body_file {
+                request.body_file = Some(config_dir.join(body_file).display().to_string());
+            }
+            if let Some(schema) = request.expect.as_mut().and_then(|expect| expect.schema.as_mut()) {
+                *schema = config_dir.join(&*schema).display().to_string();
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Loads the OpenAPI spec referenced by the `openapi` key, if configured
+    pub fn openapi_spec(&self) -> Result<Option<OpenApiSpec>> {
+        let config = match self.find_and_load_config()? {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+        let spec_path = match config.openapi {
+            Some(spec_path) => PathBuf::from(spec_path),
+            None => return Ok(None),
+        };
+
+        // Spec paths are relative to the config file
+        let spec_path = match self.defining_dir(|config| config.openapi.is_some())? {
+            Some(config_dir) if spec_path.is_relative() => config_dir.join(spec_path),
+            _ => spec_path,
+        };
+        OpenApiSpec::load(&spec_path).map(Some)
+    }
+
+    /// Finds the profile whose URL is the longest prefix of `url`
+    /// Returns the profile name and its resolved base URL. Profiles whose URL cannot be
+    /// resolved (e.g. a missing environment variable) are skipped.
+    pub fn match_profile(&self, url: &str) -> Result<Option<(String, String)>> {
+        let config = match self.find_and_load_config()? {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+
+        // The default profile wins ties
+        let mut names: Vec<&String> = config.profiles.keys().collect();
+        names.sort_by_key(|name| (Some(name.as_str()) != config.default_profile.as_deref(), name.as_str()));
+
+        let mut best: Option<(String, String)> = None;
+        for name in names {
+            let (profile, _) = config.merged_profile(name)?;
+            if profile.url.is_empty() {
+                continue;
+            }
+            let mut resolver = EnvironmentResolver::new();
+            resolver.set_variables(profile.vars.clone().unwrap_or_default());
+            resolver.set_overrides(self.variable_overrides.clone());
+            resolver.set_env_files(&self.env_files(&config, name, &profile)?)?;
+
+            let base = match resolver.resolve(&profile.url) {
+                Ok(base) => base.trim_end_matches('/').to_string(),
+                Err(_) => continue,
+            };
+            let matches = url.strip_prefix(&base)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || rest.starts_with('?'));
+            if matches && best.as_ref().is_none_or(|(_, current)| base.len() > current.len()) {
+                best = Some((name.clone(), base));
+            }
+        }
+        Ok(best)
+    }
+
+    /// Sets variables supplied on the command line, which override profile variables
+    pub fn set_variable_overrides(&mut self, variables: HashMap<String, String>) {
+        self.variable_overrides = variables;
+    }
+
+    /// Resolves environment variable references in a configuration value
+    pub fn resolve_value(&mut self, value: &str) -> Result<String> {
+        self.environment_resolver.resolve(value)
+    }
+
+    /// Resolves `${vars.name}` and built-in references in a value given on the command line
+    pub fn resolve_inline(&mut self, value: &str) -> Result<String> {
+        self.environment_resolver.resolve_inline(value)
+    }
+
+    /// Resolves and adds "key:value" headers, replacing existing values with the same name
+    pub fn apply_headers(&mut self, header_strings: &[String], headers: &mut HeaderMap) -> Result<()> {
+        for header_str in header_strings {
+            let resolved_header = self.environment_resolver.resolve(header_str)?;
+            self.parse_single_header(&resolved_header, headers)?;
+        }
+        Ok(())
+    }
+
     /// Parses headers from profile configuration
     fn parse_profile_headers(&mut self, profile: &RawProfile, token: Option<&str>) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
@@ -260,10 +1290,7 @@ impl ConfigManager {
 
         // Add profile headers
         if let Some(ref header_strings) = profile.headers {
-            for header_str in header_strings {
-                let resolved_header = self.environment_resolver.resolve(header_str)?;
-                self.parse_single_header(&resolved_header, &mut headers)?;
-            }
+            self.apply_headers(header_strings, &mut headers)?;
         }
 
         Ok(headers)
@@ -309,11 +1336,20 @@ impl ConfigManager {
         Ok(())
     }
 
-    /// Creates a default configuration file in the current directory
+    /// Path for a new project configuration: the `--config` / `ASTERISK_CONFIG` path,
+    /// or asterisk.config in the current directory
+    fn new_config_path(&self) -> Result<PathBuf> {
+        match self.location {
+            ConfigLocation::Flag(ref path) | ConfigLocation::Environment(ref path) => Ok(path.clone()),
+            ConfigLocation::Discovered => Ok(std::env::current_dir()
+                .map_err(|e| AsteriskError::Config(format!("Cannot get current directory: {}", e)))?
+                .join(CONFIG_FILENAME)),
+        }
+    }
+
+    /// Creates a default configuration file in the current directory (or at `--config`)
     pub fn create_default_config(&self) -> Result<PathBuf> {
-        let config_path = std::env::current_dir()
-            .map_err(|e| AsteriskError::Config(format!("Cannot get current directory: {}", e)))?
-            .join(CONFIG_FILENAME);
+        let config_path = self.new_config_path()?;
 
         if config_path.exists() {
             return Err(AsteriskError::Config(
@@ -329,12 +1365,294 @@ impl ConfigManager {
         Ok(config_path)
     }
 
+    /// The .gitignore next to the project config that does not list asterisk.local.config yet
+    /// Only offered in a git repository root or next to an existing .gitignore.
+    pub fn local_config_gitignore(&self) -> Result<Option<PathBuf>> {
+        Ok(missing_ignore(&self.new_config_dir()?, LOCAL_CONFIG_FILENAME))
+    }
+
+    /// Adds asterisk.local.config to the .gitignore from `local_config_gitignore`
+    /// Returns the .gitignore path when it was changed.
+    pub fn ignore_local_config(&self) -> Result<Option<PathBuf>> {
+        ignore_file(&self.new_config_dir()?, LOCAL_CONFIG_FILENAME)
+    }
+
+    fn new_config_dir(&self) -> Result<PathBuf> {
+        let path = self.new_config_path()?;
+        Ok(path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf())
+    }
+
+    /// Encrypts `value` into a profile setting of asterisk.config, e.g. `staging.token`
+    /// The key file is created (and git-ignored) on first use. The file is edited in
+    /// place, so comments and formatting are kept.
+    pub fn set_secret(&self, setting: &str, value: &str) -> Result<SecretUpdate> {
+        let (profile, field) = setting.split_once('.')
+            .filter(|(profile, field)| !profile.is_empty() && SECRET_SETTINGS.contains(field))
+            .ok_or_else(|| AsteriskError::Config(format!(
+                "Expected PROFILE.SETTING with a setting of {}, got '{}'", SECRET_SETTINGS.join(", "), setting
+            )))?;
+        let path = self.find_config_file().filter(|path| path.exists()).ok_or_else(|| AsteriskError::Config(
+            "No asterisk.config found. Run 'asterisk config init' first".to_string()
+        ))?;
+        let config_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut document = read_document(&path)?;
+        // New values reuse the salt of the file's values, so loading it derives one key
+        let shared_salt = find_encrypted(document.as_item()).map(str::to_string);
+
+        let profile_item = document.get_mut("profiles")
+            .and_then(|profiles| profiles.get_mut(profile))
+            .filter(|item| item.is_table_like())
+            .ok_or_else(|| AsteriskError::Config(
+                format!("Profile '{}' is not defined in {}", profile, path.display())
+            ))?;
+        let (item, key) = match field.split_once('.') {
+            Some((auth, key)) => {
+                let auth = profile_item.get_mut(auth).filter(|item| item.is_table_like()).ok_or_else(|| {
+                    AsteriskError::Config(format!("Profile '{}' has no auth table in {}", profile, path.display()))
+                })?;
+                (auth, key)
+            }
+            None if profile_item.get("auth").is_some() => return Err(AsteriskError::Config(format!(
+                "Profile '{}' uses auth; encrypt auth.token, auth.password or auth.value instead", profile
+            ))),
+            None => (profile_item, field),
+        };
+
+        let (mut secret_key, created_key) = SecretKey::load_or_create(&config_dir)?;
+        if let Some(ref existing) = shared_salt {
+            secret_key.share_salt(existing);
+        }
+        let encrypted = toml_edit::Value::from(secret_key.encrypt(value)?);
+        match item.get_mut(key) {
+            Some(toml_edit::Item::Value(existing)) => {
+                let decor = existing.decor().clone();
+                *existing = encrypted;
+                *existing.decor_mut() = decor;
+            }
+            _ => {
+                item[key] = toml_edit::Item::Value(encrypted);
+                // Inline tables can't hold comments, so respacing them loses nothing
+                if let Some(inline) = item.as_inline_table_mut() {
+                    inline.fmt();
+                }
+            }
+        }
+        write_document(&path, &document)?;
+
+        let ignored_in = match created_key {
+            Some(ref key_file) if key_file.parent() == Some(config_dir.as_path()) => ignore_file(&config_dir, &format!("{}*", KEY_FILENAME))?,
+            _ => None,
+        };
+        Ok(SecretUpdate { path, created_key, ignored_in })
+    }
+
+    /// Re-encrypts every `enc:` value of the configuration files with a new key
+    /// Only files decrypted with the project key are rotated; a user config next to its own
+    /// key file is left alone. Configs are written to temporary files first, and replaced
+    /// ones are restored along with the key when any step fails. The previous key file is
+    /// kept as `asterisk.key.old`.
+    pub fn rotate_secret_key(&self) -> Result<KeyRotation> {
+        let config_dir = self.find_config_file()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .ok_or_else(|| AsteriskError::Config("No asterisk.config found".to_string()))?;
+        let mut old_key = SecretKey::load(&config_dir)?;
+        let mut new_key = old_key.rotate()?;
+
+        let mut documents = Vec::new();
+        let mut skipped = Vec::new();
+        for source in self.config_sources() {
+            let mut document = read_document(&source.path)?;
+            if !old_key.decrypts(source.path.parent().unwrap_or(Path::new(""))) {
+                if find_encrypted(document.as_item()).is_some() {
+                    skipped.push(source.path);
+                }
+                continue;
+            }
+            let count = reencrypt(document.as_item_mut(), &mut old_key, &mut new_key).map_err(|e| {
+                AsteriskError::SecretSource(format!("{} ({})", source.path.display(), e))
+            })?;
+            if count > 0 {
+                documents.push((source.path, document, count));
+            }
+        }
+
+        let mut staged = Vec::new();
+        let mut replaced = Vec::new();
+        let result = replace_documents(documents, &new_key, &mut staged, &mut replaced);
+
+        // Backups are restored on failure; temporary files are left only by a failure
+        for (path, backup) in &replaced {
+            let _ = if result.is_ok() { fs::remove_file(backup) } else { fs::rename(backup, path) };
+        }
+        for (_, temp, _) in &staged {
+            let _ = fs::remove_file(temp);
+        }
+        if result.is_err() {
+            new_key.discard_staged();
+        }
+
+        Ok(KeyRotation {
+            key_file: result?.map(Path::to_path_buf),
+            files: staged.into_iter().map(|(path, _, count)| (path, count)).collect(),
+            skipped,
+        })
+    }
+
+    /// Writes imported profiles and requests to asterisk.config
+    /// Creates a new file in the current directory when none exists; otherwise new
+    /// entries are appended so existing content and comments are preserved.
+    /// Entries whose name already exists are skipped.
+    pub fn import_config(&self, imported: RawConfig, source: &str) -> Result<ImportOutcome> {
+        let existing = self.find_and_load_config()?;
+        let mut fragment = RawConfig {
+            default_profile: None,
+            openapi: None,
+            defaults: None,
+            profiles: HashMap::new(),
+            requests: HashMap::new(),
+        };
+        let mut skipped = Vec::new();
+        let target = match self.find_config_file() {
+            Some(path) => path,
+            None => self.new_config_path()?,
+        };
+
+        for (name, profile) in imported.profiles {
+            if existing.as_ref().is_some_and(|c| c.profiles.contains_key(&name)) {
+                skipped.push(format!("profile '{}'", name));
+            } else {
+                fragment.profiles.insert(name, profile);
+            }
+        }
+        for (name, request) in imported.requests {
+            if existing.as_ref().is_some_and(|c| c.requests.contains_key(&name)) {
+                skipped.push(format!("request '{}'", name));
+            } else {
+                fragment.requests.insert(name, request);
+            }
+        }
+        skipped.sort();
+
+        // Top-level keys must precede every table, so they are inserted ahead of the first one
+        let mut top_level = String::new();
+        if let Some(openapi) = imported.openapi {
+            // The spec path is given relative to the current directory, but read relative to the config file
+            let config_dir = target.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            let openapi = relative_path(Path::new(&openapi), config_dir).display().to_string();
+            match existing.as_ref().and_then(|c| c.openapi.as_deref()) {
+                Some(current) if current != openapi => skipped.push(format!("openapi (already set to '{}')", current)),
+                Some(_) => {}
+                None => {
+                    top_level.push_str(&format!("openapi = {}\n", toml::Value::String(openapi.clone())));
+                    fragment.openapi = Some(openapi);
+                }
+            }
+        }
+
+        let mut added_profiles: Vec<String> = fragment.profiles.keys().cloned().collect();
+        let mut added_requests: Vec<String> = fragment.requests.keys().cloned().collect();
+        added_profiles.sort();
+        added_requests.sort();
+
+        let (path, created, content) = match (existing, self.find_config_file()) {
+            (Some(mut config), Some(path)) => {
+                let openapi = fragment.openapi.take();
+                let appended = Self::serialize_config(&fragment)?;
+
+                // Validate the merged result before touching the file
+                config.openapi = config.openapi.or(openapi);
+                config.profiles.extend(fragment.profiles);
+                config.requests.extend(fragment.requests);
+                self.validate_config(&config)?;
+
+                let mut content = fs::read_to_string(&path)?;
+                if !top_level.is_empty() {
+                    let insert_at = content.lines()
+                        .scan(0, |offset, line| {
+                            let start = *offset;
+                            *offset += line.len() + 1;
+                            Some((start, line))
+                        })
+                        .find(|(_, line)| line.trim_start().starts_with('['))
+                        .map(|(start, _)| start)
+                        .unwrap_or(content.len());
+                    content.insert_str(insert_at, &format!("{}\n", top_level));
+                }
+                if !appended.is_empty() {
+                    content.push_str(&format!("\n# Imported from {}\n{}", source, appended));
+                }
+                (path, false, content)
+            }
+            _ => {
+                fragment.default_profile = imported.default_profile;
+                self.validate_config(&fragment)?;
+
+                let content = format!(
+                    "# Asterisk Configuration\n# Imported from {}\n\n{}",
+                    source,
+                    Self::serialize_config(&fragment)?
+                );
+                (target, true, content)
+            }
+        };
+
+        fs::write(&path, content).map_err(|e| AsteriskError::Config(
+            format!("Failed to write config file {}: {}", path.display(), e)
+        ))?;
+
+        Ok(ImportOutcome {
+            path,
+            created,
+            added_profiles,
+            added_requests,
+            skipped,
+        })
+    }
+
+    /// Serializes configuration as TOML with keys in sorted order
+    pub fn serialize_config(config: &RawConfig) -> Result<String> {
+        let value = toml::Value::try_from(config)
+            .map_err(|e| AsteriskError::Config(format!("Failed to serialize config: {}", e)))?;
+        toml::to_string_pretty(&value)
+            .map_err(|e| AsteriskError::Config(format!("Failed to serialize config: {}", e)))
+    }
+
     /// Returns the current resolved configuration as a display string
-    pub fn format_current_config(&mut self, profile: Option<&str>) -> Result<String> {
+    /// With `show_origin`, each value is annotated with the layer that set it.
+    pub fn format_current_config(&mut self, profile: Option<&str>, show_origin: bool) -> Result<String> {
         let config = self.load_resolved_config(profile, None, None, None, false)?;
+        let mut origins = match (show_origin, config.profile.as_deref(), self.find_and_load_config()?) {
+            (true, Some(name), Some(raw_config)) => layered_origins(&self.load_layers()?, &raw_config, name)?,
+            _ => Origins::new(),
+        };
+        // Environment settings replace the config values entirely
+        for (key, variable) in [("profile", PROFILE_ENV), ("url", URL_ENV), ("verbose", VERBOSE_ENV), ("token", TOKEN_ENV)] {
+            if env_setting(variable).is_some() {
+                origins.insert(key.to_string(), format!("environment {}", variable));
+            }
+        }
+        if env_setting(HEADERS_ENV).is_some() {
+            origins.retain(|key, _| !key.starts_with("headers."));
+            origins.insert("headers".to_string(), format!("environment {}", HEADERS_ENV));
+        }
+        for name in self.variable_overrides.keys() {
+            origins.insert(format!("vars.{}", name), "command line --var".to_string());
+        }
+        let origin = |keys: &[&str]| {
+            if !show_origin {
+                return String::new();
+            }
+            let label = keys.iter().find_map(|key| origins.get(*key)).map(String::as_str).unwrap_or("built-in default");
+            format!("  # {}", label)
+        };
         
         let mut output = String::new();
-        output.push_str(&format!("URL: {}\n", config.url));
+        if show_origin {
+            if let Some(ref name) = config.profile {
+                output.push_str(&format!("Profile: {}{}\n", name, origin(&["profile"])));
+            }
+        }
+        output.push_str(&format!("URL: {}{}\n", config.url, origin(&["url"])));
         
         if let Some(ref token) = config.token {
             // Mask token for security
@@ -343,20 +1661,58 @@ impl ConfigManager {
             } else {
                 "***".to_string()
             };
-            output.push_str(&format!("Token: {}\n", masked_token));
+            output.push_str(&format!("Token: {}{}\n", masked_token, origin(&["token", "auth"])));
         } else {
             output.push_str("Token: None\n");
         }
         
-        output.push_str(&format!("Verbose: {}\n", config.verbose));
+        output.push_str(&format!("Verbose: {}{}\n", config.verbose, origin(&["verbose"])));
+
+        if let Some(ref endpoint) = config.graphql_endpoint {
+            output.push_str(&format!("GraphQL: {}{}\n", endpoint, origin(&["graphql_endpoint"])));
+        }
         
         if !config.headers.is_empty() {
             output.push_str("Headers:\n");
             for (name, value) in &config.headers {
+                // Headers not listed in a layer were added by its token or auth
+                let header_key = format!("headers.{}", name);
+                let from = origin(&[header_key.as_str(), "headers", "token", "auth"]);
                 if name == "authorization" {
-                    output.push_str("  authorization: Bearer ***\n");
+                    let scheme = value.to_str().ok()
+                        .and_then(|v| v.split_whitespace().next())
+                        .unwrap_or("Bearer");
+                    output.push_str(&format!("  authorization: {} ***{}\n", scheme, from));
+                } else if value.is_sensitive() {
+                    output.push_str(&format!("  {}: ***{}\n", name, from));
+                } else {
+                    output.push_str(&format!("  {}: {}{}\n", name, value.to_str().unwrap_or("***"), from));
+                }
+            }
+        }
+
+        // Templates rather than expanded values, which may hold secrets from the environment
+        let variables = self.environment_resolver.variable_templates();
+        if !variables.is_empty() {
+            output.push_str("Variables:\n");
+            for (name, template, error) in variables {
+                let from = origin(&[format!("vars.{}", name).as_str()]);
+                match error {
+                    None => output.push_str(&format!("  {}: {}{}\n", name, template, from)),
+                    Some(e) => output.push_str(&format!("  {}: {} (unresolved: {}){}\n", name, template, e, from)),
+                }
+            }
+        }
+
+        // Values are not shown, as dotenv files usually hold secrets
+        let variables = self.environment_resolver.env_file_variables();
+        if !variables.is_empty() {
+            output.push_str("Env variables:\n");
+            for (name, path) in variables {
+                if std::env::var_os(name).is_some() {
+                    output.push_str(&format!("  {}: process environment (overrides {})\n", name, path.display()));
                 } else {
-                    output.push_str(&format!("  {}: {}\n", name, value.to_str().unwrap_or("***")));
+                    output.push_str(&format!("  {}: {}\n", name, path.display()));
                 }
             }
         }
@@ -378,6 +1734,14 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    /// A manager that never reads the user config of the machine running the tests
+    fn test_manager() -> ConfigManager {
+        ConfigManager {
+            config_home: Some(env::temp_dir().join("asterisk-tests-without-config-home")),
+            ..ConfigManager::new()
+        }
+    }
+
     #[test]
     fn test_config_parsing() {
         let config_content = r#"
@@ -401,43 +1765,485 @@ token = "$PROD_TOKEN"
         assert_eq!(config.profiles["dev"].token, Some("$DEV_TOKEN".to_string()));
     }
 
+    #[test]
+    fn test_saved_request_parsing() {
+        let config_content = r#"
+[profiles.dev]
+url = "http://localhost:3000"
+
+[requests.create_user]
+description = "Create a test user"
+method = "POST"
+endpoint = "users"
+headers = ["X-Request-Source:asterisk"]
+body = '{"name":"john"}'
+query = { notify = "false" }
+expect = { status = 201, body_contains = ["john"] }
+"#;
+
+        let config: RawConfig = toml::from_str(config_content).unwrap();
+        let request = &config.requests["create_user"];
+        assert_eq!(request.method.as_deref(), Some("POST"));
+        assert_eq!(request.endpoint, "users");
+        assert_eq!(request.query.as_ref().unwrap()["notify"], "false");
+        assert_eq!(request.expect.as_ref().unwrap().status, Some(201));
+
+        let manager = test_manager();
+        assert!(manager.validate_config(&config).is_ok());
+
+        // Invalid: both body and body_file
+        let mut config = config;
+        config.requests.get_mut("create_user").unwrap().body_file = Some("user.json".to_string());
+        assert!(manager.validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_serialize_config_round_trip() {
+        let mut profiles = HashMap::new();
+        profiles.insert("staging".to_string(), RawProfile {
+            extends: None,
+            url: "${vars.baseUrl}".to_string(),
+            token: None,
+            headers: None,
+            verbose: None,
+            auth: Some(RawAuth::ApiKey {
+                key: "X-Api-Key".to_string(),
+                value: "$API_KEY".to_string(),
+                location: Some("query".to_string()),
+            }),
+            vars: Some(HashMap::from([("baseUrl".to_string(), "https://staging.example.com".to_string())])),
+            graphql_endpoint: None,
+            env_file: None,
+        });
+        let config = RawConfig {
+            default_profile: Some("staging".to_string()),
+            openapi: None,
+            defaults: None,
+            profiles,
+            requests: HashMap::new(),
+        };
+
+        let serialized = ConfigManager::serialize_config(&config).unwrap();
+        assert!(serialized.contains("[profiles.staging.auth]"));
+        assert!(serialized.contains("type = \"api_key\""));
+
+        let parsed: RawConfig = toml::from_str(&serialized).unwrap();
+        assert!(matches!(parsed.profiles["staging"].auth, Some(RawAuth::ApiKey { ref location, .. }) if location.as_deref() == Some("query")));
+        assert_eq!(parsed.profiles["staging"].vars.as_ref().unwrap()["baseUrl"], "https://staging.example.com");
+    }
+
     #[test]
     fn test_config_validation() {
-        let manager = ConfigManager::new();
+        let manager = test_manager();
         
         // Valid config
         let config = RawConfig {
             default_profile: Some("dev".to_string()),
+            openapi: None,
+            defaults: None,
             profiles: {
                 let mut profiles = HashMap::new();
                 profiles.insert("dev".to_string(), RawProfile {
+                    extends: None,
                     url: "http://localhost:3000".to_string(),
                     token: None,
                     headers: None,
                     verbose: None,
+                    auth: None,
+                    vars: None,
+                    graphql_endpoint: None,
+                    env_file: None,
                 });
                 profiles
             },
+            requests: HashMap::new(),
         };
         assert!(manager.validate_config(&config).is_ok());
 
         // Invalid: missing default profile
         let config = RawConfig {
             default_profile: Some("nonexistent".to_string()),
+            openapi: None,
+            defaults: None,
             profiles: {
                 let mut profiles = HashMap::new();
                 profiles.insert("dev".to_string(), RawProfile {
+                    extends: None,
                     url: "http://localhost:3000".to_string(),
                     token: None,
                     headers: None,
                     verbose: None,
+                    auth: None,
+                    vars: None,
+                    graphql_endpoint: None,
+                    env_file: None,
                 });
                 profiles
             },
+            requests: HashMap::new(),
         };
         assert!(manager.validate_config(&config).is_err());
     }
 
+    #[test]
+    fn test_profile_inheritance() {
+        let config: RawConfig = toml::from_str(r#"
+default_profile = "staging"
+
+[defaults]
+headers = ["Accept:application/json", "X-Client:asterisk"]
+verbose = false
+
+[profiles.base]
+token = "$BASE_TOKEN"
+headers = ["X-Team:payments", "x-client:asterisk-ci"]
+vars = { tenant = "acme", region = "eu" }
+
+[profiles.staging]
+extends = "base"
+url = "https://staging.example.com"
+auth = { type = "basic", username = "ci" }
+headers = ["X-Environment:staging"]
+vars = { region = "us" }
+"#).unwrap();
+
+        let manager = test_manager();
+        assert!(manager.validate_config(&config).is_ok());
+
+        let (profile, origins) = config.merged_profile("staging").unwrap();
+        assert_eq!(profile.url, "https://staging.example.com");
+        assert_eq!(profile.headers.unwrap(), vec![
+            "Accept:application/json",
+            "x-client:asterisk-ci",
+            "X-Team:payments",
+            "X-Environment:staging",
+        ]);
+        assert!(profile.token.is_none());
+        assert!(matches!(profile.auth, Some(RawAuth::Basic { .. })));
+        assert_eq!(profile.vars.unwrap(), HashMap::from([
+            ("tenant".to_string(), "acme".to_string()),
+            ("region".to_string(), "us".to_string()),
+        ]));
+        assert_eq!(origins["url"], "[profiles.staging]");
+        assert_eq!(origins["headers.accept"], "[defaults]");
+        assert_eq!(origins["headers.x-client"], "[profiles.base]");
+        assert_eq!(origins["vars.tenant"], "[profiles.base]");
+        assert_eq!(origins["auth"], "[profiles.staging]");
+        assert!(!origins.contains_key("token"));
+
+        // A profile only used as a base may omit the URL, but cannot be used directly
+        let (base, _) = config.merged_profile("base").unwrap();
+        assert!(base.url.is_empty());
+        assert!(config.merged_profile("missing").is_err());
+    }
+
+    #[test]
+    fn test_layered_config() {
+        let layer = |content: &str| -> RawConfig { toml::from_str(content).unwrap() };
+        let source = |layer| ConfigSource { layer, path: PathBuf::from("asterisk.config") };
+        let layers = vec![
+            (source(ConfigLayer::User), layer(r#"
+default_profile = "mine"
+
+[defaults]
+headers = ["X-User:me"]
+
+[profiles.mine]
+url = "http://localhost:9000"
+
+[profiles.staging]
+token = "$MY_STAGING_TOKEN"
+"#)),
+            (source(ConfigLayer::Project), layer(r#"
+default_profile = "staging"
+
+[profiles.staging]
+url = "https://staging.example.com"
+headers = ["X-Environment:staging"]
+
+[requests.health]
+endpoint = "health"
+"#)),
+            (source(ConfigLayer::Local), layer(r#"
+[profiles.staging]
+headers = ["X-Environment:staging-local"]
+"#)),
+        ];
+
+        let mut merged = layers[0].1.clone();
+        for (_, upper) in layers[1..].iter().cloned() {
+            merged.overlay(upper);
+        }
+        assert!(test_manager().validate_config(&merged).is_ok());
+        assert_eq!(merged.default_profile.as_deref(), Some("staging"));
+        assert_eq!(merged.profiles.len(), 2);
+        assert!(merged.requests.contains_key("health"));
+
+        let (staging, _) = merged.merged_profile("staging").unwrap();
+        assert_eq!(staging.url, "https://staging.example.com");
+        assert_eq!(staging.token.as_deref(), Some("$MY_STAGING_TOKEN"));
+        assert_eq!(staging.headers.unwrap(), vec!["X-User:me", "X-Environment:staging-local"]);
+
+        let origins = layered_origins(&layers, &merged, "staging").unwrap();
+        assert_eq!(origins["url"], "project [profiles.staging]");
+        assert_eq!(origins["token"], "user [profiles.staging]");
+        assert_eq!(origins["headers.x-user"], "user [defaults]");
+        assert_eq!(origins["headers.x-environment"], "local [profiles.staging]");
+    }
+
+    #[test]
+    fn test_explicit_config_location() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("ci.toml");
+        fs::write(&path, "[profiles.ci]\nurl = \"http://ci.internal\"\n").unwrap();
+
+        let mut manager = test_manager();
+        manager.set_location(ConfigLocation::Flag(path.clone()));
+        assert_eq!(manager.find_config_file(), Some(path.clone()));
+        assert!(manager.config_sources().iter().any(|source| source.layer == ConfigLayer::Project && source.path == path));
+        assert_eq!(manager.match_profile("http://ci.internal/users").unwrap(), Some(("ci".to_string(), "http://ci.internal".to_string())));
+
+        // A missing explicit file is an error rather than falling back to discovery
+        manager.set_location(ConfigLocation::Environment(temp_dir.path().join("missing.toml")));
+        let error = manager.saved_requests().unwrap_err().to_string();
+        assert!(error.contains("ASTERISK_CONFIG"), "{}", error);
+    }
+
+    #[test]
+    fn test_import_openapi_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let spec_path = temp_dir.path().join("specs").join("api.json");
+        fs::create_dir(temp_dir.path().join("specs")).unwrap();
+        fs::write(&spec_path, r#"{"openapi":"3.0.0","info":{"title":"API","version":"1"},"paths":{}}"#).unwrap();
+        fs::create_dir(temp_dir.path().join("project")).unwrap();
+        let path = temp_dir.path().join("project").join(CONFIG_FILENAME);
+        fs::write(&path, "[profiles.api]\nurl = \"http://api\"\n").unwrap();
+
+        // The spec path is rewritten relative to the config file rather than the current directory
+        let mut manager = test_manager();
+        manager.set_location(ConfigLocation::Flag(path.clone()));
+        let imported = RawConfig {
+            default_profile: None,
+            openapi: Some(spec_path.display().to_string()),
+            defaults: None,
+            profiles: HashMap::new(),
+            requests: HashMap::new(),
+        };
+        manager.import_config(imported, "api.json").unwrap();
+        let config = manager.find_and_load_config().unwrap().unwrap();
+        assert_eq!(config.openapi.as_deref().map(Path::new), Some(Path::new("../specs/api.json")));
+        assert!(manager.openapi_spec().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_config_show_variables() {
+        // A variable no other test reads, since tests share the process environment
+        env::set_var("ASTERISK_TEST_SHOW_SECRET", "hunter2");
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILENAME);
+        fs::write(&path, "[profiles.dev]\nurl = \"http://dev\"\n[profiles.dev.vars]\nkey = \"${ASTERISK_TEST_SHOW_SECRET}\"\nmissing = \"${vars.nope}\"\n").unwrap();
+        let mut manager = test_manager();
+        manager.set_location(ConfigLocation::Flag(path));
+
+        let output = manager.format_current_config(Some("dev"), false).unwrap();
+        env::remove_var("ASTERISK_TEST_SHOW_SECRET");
+        assert!(output.contains("  key: ${ASTERISK_TEST_SHOW_SECRET}\n"), "{}", output);
+        assert!(output.contains("  missing: ${vars.nope} (unresolved: "), "{}", output);
+        assert!(!output.contains("hunter2"), "{}", output);
+    }
+
+    #[test]
+    fn test_credential_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILENAME);
+        fs::write(temp_dir.path().join("token"), "from-file\n").unwrap();
+        fs::write(&path, "[profiles.file]\nurl = \"http://api\"\ntoken = \"file:token\"\n\n[profiles.cmd]\nurl = \"http://api\"\ntoken = \"cmd:echo project\"\n").unwrap();
+        let mut manager = test_manager();
+        manager.set_location(ConfigLocation::Flag(path));
+
+        // Relative to the config file rather than the current directory
+        let config = manager.load_resolved_config(Some("file"), None, None, None, false).unwrap();
+        assert_eq!(config.token.as_deref(), Some("from-file"));
+
+        // Commands only run from files that are not shared with the project
+        let error = manager.load_resolved_config(Some("cmd"), None, None, None, false).unwrap_err();
+        assert!(matches!(error, AsteriskError::SecretSource(_)), "{}", error);
+        if cfg!(unix) {
+            fs::write(temp_dir.path().join(LOCAL_CONFIG_FILENAME), "[profiles.cmd]\ntoken = \"cmd:echo local\"\n").unwrap();
+            let config = manager.load_resolved_config(Some("cmd"), None, None, None, false).unwrap();
+            assert_eq!(config.token.as_deref(), Some("local"));
+        }
+    }
+
+    #[test]
+    fn test_restrict_to_origin() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILENAME);
+        fs::write(&path, concat!(
+            "[profiles.token]\nurl = \"https://api.example.com/v1\"\ntoken = \"t0ken\"\n",
+            "headers = [\"X-Client: cli\", \"X-Auth-Copy: t0ken\"]\n\n",
+            "[profiles.key]\nurl = \"https://api.example.com\"\n",
+            "auth = { type = \"api_key\", key = \"api_key\", value = \"k3y\", in = \"query\" }\n",
+        )).unwrap();
+        let mut manager = test_manager();
+        manager.set_location(ConfigLocation::Flag(path));
+
+        let mut config = manager.load_resolved_config(Some("token"), None, None, None, false).unwrap();
+        assert!(!config.restrict_to_origin("https://api.example.com/v1/users"));
+        assert!(config.headers.contains_key(AUTHORIZATION));
+
+        assert!(config.restrict_to_origin("https://cdn.example.com/config.json"));
+        assert_eq!(config.token, None);
+        assert!(!config.headers.contains_key(AUTHORIZATION));
+        assert!(!config.headers.contains_key("x-auth-copy"));
+        assert_eq!(config.headers["x-client"], "cli");
+        assert!(config.secrets.is_empty());
+
+        let mut config = manager.load_resolved_config(Some("key"), None, None, None, false).unwrap();
+        assert!(config.restrict_to_origin("http://api.example.com/users"));
+        assert!(config.query.is_empty());
+        assert!(!config.restrict_to_origin("https://elsewhere.example.com/"));
+    }
+
+    #[test]
+    fn test_user_config_and_gitignore() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(project.join(".git")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("home").join("asterisk")).unwrap();
+        fs::write(temp_dir.path().join("home").join(USER_CONFIG_PATH), "[profiles.mine]\nurl = \"http://mine\"\n").unwrap();
+
+        let mut manager = test_manager();
+        manager.config_home = Some(temp_dir.path().join("home"));
+        manager.set_location(ConfigLocation::Flag(project.join(CONFIG_FILENAME)));
+        let path = manager.create_default_config().unwrap();
+        assert_eq!(manager.config_sources()[0].layer, ConfigLayer::User);
+        assert_eq!(manager.load_resolved_config(Some("mine"), None, None, None, false).unwrap().url, "http://mine");
+
+        // The .gitignore next to --config, not the one in the current directory
+        let gitignore = project.join(".gitignore");
+        assert_eq!(path.parent(), Some(project.as_path()));
+        assert_eq!(manager.local_config_gitignore().unwrap(), Some(gitignore.clone()));
+        assert_eq!(manager.ignore_local_config().unwrap(), Some(gitignore.clone()));
+        assert_eq!(fs::read_to_string(&gitignore).unwrap(), "asterisk.local.config\n");
+        assert_eq!(manager.local_config_gitignore().unwrap(), None);
+    }
+
+    #[test]
+    fn test_secret_set_and_rotate() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILENAME);
+        fs::write(&path, "[profiles.staging]\nurl = \"http://staging\"\ntoken = \"$OLD\"  # shared\n").unwrap();
+        let mut manager = test_manager();
+        manager.set_location(ConfigLocation::Flag(path.clone()));
+
+        let update = manager.set_secret("staging.token", "s3cret").unwrap();
+        assert_eq!(update.created_key, Some(temp_dir.path().join(KEY_FILENAME)));
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("token = \"enc:") && content.contains("  # shared"), "{}", content);
+        assert!(!content.contains("s3cret"));
+        assert!(manager.set_secret("staging.url", "x").is_err());
+        assert!(manager.set_secret("prod.token", "x").is_err());
+
+        // A fresh manager, as the resolver keeps decrypted values for the invocation
+        let load_token = || {
+            let mut manager = test_manager();
+            manager.set_location(ConfigLocation::Flag(path.clone()));
+            manager.load_resolved_config(Some("staging"), None, None, None, false).unwrap().token
+        };
+
+        assert_eq!(load_token().as_deref(), Some("s3cret"));
+
+        let rotation = manager.rotate_secret_key().unwrap();
+        assert_eq!(rotation.files, vec![(path.clone(), 1)]);
+        assert_eq!(rotation.key_file, Some(temp_dir.path().join(KEY_FILENAME)));
+        assert_ne!(fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(load_token().as_deref(), Some("s3cret"));
+        assert!(temp_dir.path().join("asterisk.key.old").exists());
+        assert!(!temp_dir.path().join("asterisk.key.new").exists());
+        assert!(!temp_dir.path().join(format!("{}.tmp", CONFIG_FILENAME)).exists());
+    }
+
+    #[test]
+    fn test_rotate_secret_key_layers_and_rollback() {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().join("home");
+        let user_path = home.join(USER_CONFIG_PATH);
+        let project = temp_dir.path().join("project");
+        let path = project.join(CONFIG_FILENAME);
+        fs::create_dir_all(user_path.parent().unwrap()).unwrap();
+        fs::create_dir_all(&project).unwrap();
+        fs::write(&user_path, "[profiles.mine]\nurl = \"http://mine\"\ntoken = \"x\"\n").unwrap();
+        fs::write(&path, "[profiles.staging]\nurl = \"http://staging\"\ntoken = \"x\"\n").unwrap();
+
+        let manager = |location: &Path| {
+            let mut manager = test_manager();
+            manager.config_home = Some(home.clone());
+            manager.set_location(ConfigLocation::Flag(location.to_path_buf()));
+            manager
+        };
+        manager(&user_path).set_secret("mine.token", "user-secret").unwrap();
+        manager(&path).set_secret("staging.token", "s3cret").unwrap();
+        let load_token = |profile: &str| manager(&path).load_resolved_config(Some(profile), None, None, None, false).unwrap().token;
+
+        // The user config keeps its own key
+        let rotation = manager(&path).rotate_secret_key().unwrap();
+        assert_eq!(rotation.files, vec![(path.clone(), 1)]);
+        assert_eq!(rotation.skipped, vec![user_path.clone()]);
+        assert_eq!(load_token("mine").as_deref(), Some("user-secret"));
+        assert_eq!(load_token("staging").as_deref(), Some("s3cret"));
+
+        // A key file that cannot be replaced leaves the configs and the key as they were
+        let key = fs::read_to_string(project.join(KEY_FILENAME)).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(project.join("asterisk.key.old")).unwrap();
+        fs::create_dir_all(project.join("asterisk.key.old").join("blocked")).unwrap();
+        assert!(manager(&path).rotate_secret_key().is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(fs::read_to_string(project.join(KEY_FILENAME)).unwrap(), key);
+        assert_eq!(load_token("staging").as_deref(), Some("s3cret"));
+        for leftover in ["asterisk.key.new", "asterisk.config.tmp", "asterisk.config.bak"] {
+            assert!(!project.join(leftover).exists(), "{}", leftover);
+        }
+    }
+
+    #[test]
+    fn test_env_flag() {
+        // A variable no other test reads, since tests share the process environment
+        let name = "ASTERISK_TEST_ENV_FLAG";
+        assert_eq!(env_flag(name).unwrap(), None);
+        for (value, expected) in [("1", true), ("Yes", true), ("on", true), ("false", false), ("0", false)] {
+            env::set_var(name, value);
+            assert_eq!(env_flag(name).unwrap(), Some(expected), "{}", value);
+        }
+        env::set_var(name, "");
+        assert_eq!(env_flag(name).unwrap(), None);
+        env::set_var(name, "maybe");
+        assert!(env_flag(name).is_err());
+        env::remove_var(name);
+    }
+
+    #[test]
+    fn test_inheritance_validation() {
+        let manager = test_manager();
+        let cycle: RawConfig = toml::from_str(r#"
+[profiles.a]
+extends = "b"
+url = "http://a"
+
+[profiles.b]
+extends = "a"
+"#).unwrap();
+        let error = manager.validate_config(&cycle).unwrap_err().to_string();
+        assert!(error.contains("cycle"), "{}", error);
+
+        let unknown: RawConfig = toml::from_str("[profiles.a]\nextends = \"base\"\nurl = \"http://a\"\n").unwrap();
+        assert!(manager.validate_config(&unknown).is_err());
+
+        let no_url: RawConfig = toml::from_str("[defaults]\nverbose = true\n\n[profiles.a]\ntoken = \"x\"\n").unwrap();
+        assert!(manager.validate_config(&no_url).is_err());
+    }
+
     #[test]
     fn test_create_default_config() {
         let temp_dir = TempDir::new().unwrap();
@@ -445,7 +2251,7 @@ token = "$PROD_TOKEN"
         
         env::set_current_dir(temp_dir.path()).unwrap();
         
-        let manager = ConfigManager::new();
+        let manager = test_manager();
         let config_path = manager.create_default_config().unwrap();
         
         assert!(config_path.exists());
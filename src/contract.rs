@@ -0,0 +1,545 @@
+use crate::http::HttpResponse;
+use crate::openapi::{OpenApiSpec, Operation};
+use regex::Regex;
+use reqwest::Url;
+use serde_json::Value;
+use std::fmt;
+
+/// Maximum `$ref` / combinator nesting followed while validating
+const MAX_VALIDATION_DEPTH: usize = 32;
+
+/// A single contract violation at a JSON pointer location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// JSON pointer into the response body, `#` for the root
+    pub pointer: String,
+
+    /// Description of the violation
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pointer, self.message)
+    }
+}
+
+/// Outcome of checking a response against an OpenAPI spec
+#[derive(Debug)]
+pub enum ContractResult {
+    /// No operation in the spec matches the request
+    Unmatched,
+
+    /// Response conforms to the matched operation
+    Valid { operation: String },
+
+    /// Response violates the matched operation
+    Invalid { operation: String, violations: Vec<Violation> },
+
+    /// The configured spec could not be loaded
+    SpecError(String),
+}
+
+/// Validates responses against the operations of an OpenAPI spec
+pub struct ContractValidator {
+    spec: OpenApiSpec,
+    operations: Vec<Operation>,
+    base_paths: Vec<String>,
+}
+
+impl ContractValidator {
+    /// Creates a validator for a spec
+    pub fn new(spec: OpenApiSpec) -> Self {
+        let operations = spec.operations();
+
+        // Server URLs may carry a base path (`https://api.example.com/v1`)
+        let mut base_paths: Vec<String> = spec.document.get("servers").and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|server| server.get("url").and_then(Value::as_str))
+            .map(|url| match url.split_once("://") {
+                Some((_, rest)) => rest.find('/').map(|i| rest[i..].to_string()).unwrap_or_default(),
+                None => url.to_string(),
+            })
+            .map(|path| path.trim_end_matches('/').to_string())
+            .filter(|path| !path.is_empty() && !path.contains('{'))
+            .collect();
+        base_paths.sort_by_key(|path| std::cmp::Reverse(path.len()));
+
+        Self { spec, operations, base_paths }
+    }
+
+    /// Matches the request to an operation and validates status, content type and body
+    pub fn validate(&self, method: &str, url: &str, response: &HttpResponse) -> ContractResult {
+        let operation = match self.match_operation(method, url) {
+            Some(operation) => operation,
+            None => return ContractResult::Unmatched,
+        };
+        let name = operation.name();
+        let violations = self.validate_response(operation, response);
+
+        if violations.is_empty() {
+            ContractResult::Valid { operation: name }
+        } else {
+            ContractResult::Invalid { operation: name, violations }
+        }
+    }
+
+    /// Finds the operation for a method and URL, preferring literal path segments
+    fn match_operation(&self, method: &str, url: &str) -> Option<&Operation> {
+        let path = Url::parse(url).map(|u| u.path().to_string()).unwrap_or_else(|_| url.to_string());
+        let mut candidates = vec![path.clone()];
+        for base_path in &self.base_paths {
+            if let Some(stripped) = path.strip_prefix(base_path.as_str()) {
+                candidates.push(if stripped.is_empty() { "/".to_string() } else { stripped.to_string() });
+            }
+        }
+
+        let method = method.to_uppercase();
+        self.operations.iter()
+            .filter(|op| op.method == method)
+            .filter_map(|op| {
+                candidates.iter()
+                    .filter_map(|candidate| template_match(&op.path, candidate))
+                    .min()
+                    .map(|params| (params, op))
+            })
+            .min_by_key(|(params, _)| *params)
+            .map(|(_, op)| op)
+    }
+
+    fn validate_response(&self, operation: &Operation, response: &HttpResponse) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let responses = operation.definition.get("responses").and_then(Value::as_object);
+
+        // Exact status, then range (2XX), then default
+        let status = response.status.to_string();
+        let range = format!("{}XX", &status[..1]);
+        let declared = responses.and_then(|r| {
+            r.get(&status)
+                .or_else(|| r.iter().find(|(code, _)| code.eq_ignore_ascii_case(&range)).map(|(_, v)| v))
+                .or_else(|| r.get("default"))
+        });
+        let declared = match declared {
+            Some(declared) => self.spec.resolve(declared),
+            None => {
+                violations.push(Violation {
+                    pointer: "#".to_string(),
+                    message: format!("status {} is not declared for this operation", response.status),
+                });
+                return violations;
+            }
+        };
+
+        let content = match declared.get("content").and_then(Value::as_object) {
+            Some(content) if !content.is_empty() => content,
+            _ => {
+                if !response.body.trim().is_empty() || response.saved_to.is_some() {
+                    violations.push(Violation {
+                        pointer: "#".to_string(),
+                        message: format!("status {} declares no response body", response.status),
+                    });
+                }
+                return violations;
+            }
+        };
+
+        let content_type = response.content_type().unwrap_or_default();
+        let media = content.get(&content_type).or_else(|| {
+            content.iter()
+                .find(|(declared, _)| media_type_matches(declared, &content_type))
+                .map(|(_, media)| media)
+        });
+        let media = match media {
+            Some(media) => media,
+            None => {
+                let declared: Vec<&str> = content.keys().map(String::as_str).collect();
+                violations.push(Violation {
+                    pointer: "#".to_string(),
+                    message: format!(
+                        "content type '{}' is not declared (expected {})",
+                        if content_type.is_empty() { "none" } else { &content_type },
+                        declared.join(", ")
+                    ),
+                });
+                return violations;
+            }
+        };
+
+        if let Some(schema) = media.get("schema") {
+            if let Some(ref path) = response.saved_to {
+                violations.push(Violation {
+                    pointer: "#".to_string(),
+                    message: format!("body was saved to {} and not checked against the schema", path.display()),
+                });
+            } else if content_type.contains("json") {
+                match serde_json::from_str::<Value>(&response.body) {
+                    Ok(body) => violations.extend(validate_schema(&self.spec.document, schema, &body)),
+                    Err(e) => violations.push(Violation {
+                        pointer: "#".to_string(),
+                        message: format!("body is not valid JSON: {}", e),
+                    }),
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// Returns the number of templated segments when `path` matches `template`
+fn template_match(template: &str, path: &str) -> Option<usize> {
+    let template_segments: Vec<&str> = template.trim_matches('/').split('/').collect();
+    let path_segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if template_segments.len() != path_segments.len() {
+        return None;
+    }
+
+    let mut params = 0;
+    for (expected, actual) in template_segments.iter().zip(&path_segments) {
+        if expected.starts_with('{') && expected.ends_with('}') {
+            if actual.is_empty() {
+                return None;
+            }
+            params += 1;
+        } else if expected != actual {
+            return None;
+        }
+    }
+    Some(params)
+}
+
+/// Matches media ranges such as `application/*` and `*/*`
+fn media_type_matches(declared: &str, actual: &str) -> bool {
+    let declared = declared.split(';').next().unwrap_or("").trim().to_lowercase();
+    match declared.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((kind, "*")) => actual.split('/').next() == Some(kind),
+        _ => declared == actual,
+    }
+}
+
+/// Validates a JSON value against a JSON Schema / OpenAPI schema object
+/// `root` is the document used to resolve local `$ref`s. Violations are sorted by
+/// pointer, so the order doesn't depend on the order of object keys.
+pub fn validate_schema(root: &Value, schema: &Value, instance: &Value) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    Validator { root }.check(schema, instance, "#", 0, &mut violations);
+    violations.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+    violations
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl Validator<'_> {
+    fn check(&self, schema: &Value, instance: &Value, pointer: &str, depth: usize, out: &mut Vec<Violation>) {
+        if depth > MAX_VALIDATION_DEPTH {
+            return;
+        }
+
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return push(out, pointer, "no value is allowed here".to_string()),
+            _ => schema,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match reference.strip_prefix('#').and_then(|p| self.root.pointer(p)) {
+                Some(target) => self.check(target, instance, pointer, depth + 1, out),
+                None => push(out, pointer, format!("unresolvable $ref '{}'", reference)),
+            }
+            return;
+        }
+
+        // OpenAPI 3.0 `nullable: true`
+        if instance.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+            return;
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !types.is_empty() && !types.iter().any(|t| type_matches(t, instance)) {
+                return push(out, pointer, format!("expected {}, got {}", types.join(" or "), type_name(instance)));
+            }
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(instance) {
+                let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                push(out, pointer, format!("{} is not one of {}", instance, allowed.join(", ")));
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if constant != instance {
+                push(out, pointer, format!("expected {}, got {}", constant, instance));
+            }
+        }
+
+        match instance {
+            Value::Object(object) => {
+                if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                    for name in required.iter().filter_map(Value::as_str) {
+                        if !object.contains_key(name) {
+                            out.push(Violation {
+                                pointer: format!("{}/{}", pointer, escape_pointer(name)),
+                                message: "required property is missing".to_string(),
+                            });
+                        }
+                    }
+                }
+
+                let properties = schema.get("properties").and_then(Value::as_object);
+                for (name, value) in object {
+                    let child = format!("{}/{}", pointer, escape_pointer(name));
+                    match properties.and_then(|p| p.get(name)) {
+                        Some(property) => self.check(property, value, &child, depth + 1, out),
+                        None => match schema.get("additionalProperties") {
+                            Some(Value::Bool(false)) => out.push(Violation {
+                                pointer: child,
+                                message: "additional property is not allowed".to_string(),
+                            }),
+                            Some(additional @ Value::Object(_)) => self.check(additional, value, &child, depth + 1, out),
+                            _ => {}
+                        },
+                    }
+                }
+
+                check_count(schema, ("minProperties", "maxProperties"), object.len(), "properties", pointer, out);
+            }
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (index, item) in items.iter().enumerate() {
+                        self.check(item_schema, item, &format!("{}/{}", pointer, index), depth + 1, out);
+                    }
+                }
+                check_count(schema, ("minItems", "maxItems"), items.len(), "items", pointer, out);
+            }
+            Value::String(text) => {
+                check_count(schema, ("minLength", "maxLength"), text.chars().count(), "characters", pointer, out);
+                if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                    if let Ok(regex) = Regex::new(pattern) {
+                        if !regex.is_match(text) {
+                            out.push(Violation {
+                                pointer: pointer.to_string(),
+                                message: format!("\"{}\" does not match pattern '{}'", text, pattern),
+                            });
+                        }
+                    }
+                }
+            }
+            Value::Number(number) => {
+                if let Some(value) = number.as_f64() {
+                    check_range(schema, value, pointer, out);
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for part in all {
+                self.check(part, instance, pointer, depth + 1, out);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any.iter().any(|part| self.is_valid(part, instance, depth)) {
+                out.push(Violation { pointer: pointer.to_string(), message: "does not match any schema in anyOf".to_string() });
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let matches = one.iter().filter(|part| self.is_valid(part, instance, depth)).count();
+            if matches != 1 {
+                out.push(Violation {
+                    pointer: pointer.to_string(),
+                    message: format!("must match exactly one schema in oneOf (matched {})", matches),
+                });
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.is_valid(not, instance, depth) {
+                out.push(Violation { pointer: pointer.to_string(), message: "must not match the 'not' schema".to_string() });
+            }
+        }
+    }
+
+    fn is_valid(&self, schema: &Value, instance: &Value, depth: usize) -> bool {
+        let mut violations = Vec::new();
+        self.check(schema, instance, "#", depth + 1, &mut violations);
+        violations.is_empty()
+    }
+}
+
+fn check_count(schema: &Value, (min_key, max_key): (&str, &str), count: usize, unit: &str, pointer: &str, out: &mut Vec<Violation>) {
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64) {
+        if (count as u64) < min {
+            push(out, pointer, format!("expected at least {} {}, got {}", min, unit, count));
+        }
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64) {
+        if (count as u64) > max {
+            push(out, pointer, format!("expected at most {} {}, got {}", max, unit, count));
+        }
+    }
+}
+
+fn check_range(schema: &Value, value: f64, pointer: &str, out: &mut Vec<Violation>) {
+    let mut violation = |message: String| push(out, pointer, message);
+    let exclusive = |key: &str| schema.get(key).and_then(Value::as_bool) == Some(true);
+
+    if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+        if value < minimum || (exclusive("exclusiveMinimum") && value == minimum) {
+            violation(format!("{} is below the minimum {}", value, minimum));
+        }
+    }
+    if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+        if value > maximum || (exclusive("exclusiveMaximum") && value == maximum) {
+            violation(format!("{} is above the maximum {}", value, maximum));
+        }
+    }
+    // JSON Schema 2020-12 numeric exclusive bounds
+    if let Some(bound) = schema.get("exclusiveMinimum").and_then(Value::as_f64) {
+        if value <= bound {
+            violation(format!("{} must be greater than {}", value, bound));
+        }
+    }
+    if let Some(bound) = schema.get("exclusiveMaximum").and_then(Value::as_f64) {
+        if value >= bound {
+            violation(format!("{} must be less than {}", value, bound));
+        }
+    }
+    if let Some(multiple) = schema.get("multipleOf").and_then(Value::as_f64) {
+        if multiple > 0.0 && (value / multiple).fract().abs() > f64::EPSILON {
+            violation(format!("{} is not a multiple of {}", value, multiple));
+        }
+    }
+}
+
+fn type_matches(expected: &str, instance: &Value) -> bool {
+    match expected {
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "boolean" => instance.is_boolean(),
+        "null" => instance.is_null(),
+        "number" => instance.is_number(),
+        "integer" => instance.as_i64().is_some() || instance.as_u64().is_some()
+            || instance.as_f64().is_some_and(|f| f.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn push(out: &mut Vec<Violation>, pointer: &str, message: String) {
+    out.push(Violation { pointer: pointer.to_string(), message });
+}
+
+/// Escapes a key for use in a JSON pointer (RFC 6901)
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info: { title: Users, version: "1.0" }
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users/{id}:
+    get:
+      operationId: getUserById
+      responses:
+        "200":
+          description: OK
+          content:
+            application/json:
+              schema: { $ref: "#/components/schemas/User" }
+        "404": { description: Missing }
+  /users/me:
+    get:
+      operationId: getCurrentUser
+      responses:
+        "200": { description: OK }
+components:
+  schemas:
+    User:
+      type: object
+      required: [id, email]
+      additionalProperties: false
+      properties:
+        id: { type: integer, minimum: 1 }
+        email: { type: string, pattern: "@" }
+        tags: { type: array, items: { type: string } }
+        nickname: { type: string, nullable: true }
+"##;
+
+    fn response(status: u16, content_type: &str, body: &str) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        if !content_type.is_empty() {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        }
+        HttpResponse { status, headers, body: body.to_string(), ..Default::default() }
+    }
+
+    fn validator() -> ContractValidator {
+        ContractValidator::new(OpenApiSpec::parse(SPEC).unwrap())
+    }
+
+    #[test]
+    fn test_matches_operation_with_base_path() {
+        let validator = validator();
+        let ok = response(200, "application/json; charset=utf-8", r#"{"id":5,"email":"a@b.c","nickname":null}"#);
+        assert!(matches!(
+            validator.validate("GET", "https://api.example.com/v1/users/5", &ok),
+            ContractResult::Valid { ref operation } if operation == "getUserById"
+        ));
+        assert!(matches!(
+            validator.validate("GET", "https://api.example.com/v1/users/me", &response(200, "", "")),
+            ContractResult::Valid { ref operation } if operation == "getCurrentUser"
+        ));
+        assert!(matches!(validator.validate("POST", "http://x/v1/users/5", &ok), ContractResult::Unmatched));
+    }
+
+    #[test]
+    fn test_reports_violations_with_pointers() {
+        let body = r#"{"id":0,"email":"nope","tags":["a",3],"extra":true}"#;
+        let result = validator().validate("GET", "https://api.example.com/v1/users/5", &response(200, "application/json", body));
+        let violations = match result {
+            ContractResult::Invalid { violations, .. } => violations,
+            other => panic!("expected violations, got {:?}", other),
+        };
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["#/email", "#/extra", "#/id", "#/tags/1"]);
+    }
+
+    #[test]
+    fn test_status_and_content_type() {
+        let validator = validator();
+        let url = "https://api.example.com/v1/users/5";
+        let result = validator.validate("GET", url, &response(500, "application/json", "{}"));
+        assert!(matches!(result, ContractResult::Invalid { ref violations, .. } if violations[0].message.contains("500")));
+
+        let result = validator.validate("GET", url, &response(200, "text/html", "<html>"));
+        assert!(matches!(result, ContractResult::Invalid { ref violations, .. } if violations[0].message.contains("text/html")));
+    }
+}
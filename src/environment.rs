@@ -1,11 +1,36 @@
+use crate::builtins;
 use crate::error::{AsteriskError, Result};
+use crate::secrets::{self, SecretKey};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Prefix of references that only look up profile variables, as in `${vars.tenant_id}`
+const VARS_PREFIX: &str = "vars.";
+
+/// Prefixes of credentials read from a file or from the output of a command
+const FILE_SECRET_PREFIX: &str = "file:";
+const COMMAND_SECRET_PREFIX: &str = "cmd:";
 
 /// Resolves environment variables in configuration values
 /// Supports $VAR_NAME and ${VAR_NAME} syntax
 pub struct EnvironmentResolver {
     cache: HashMap<String, String>,
+    /// Profile variables, which may themselves contain references
+    variables: HashMap<String, String>,
+    /// Variables given on the command line, used as is
+    overrides: HashMap<String, String>,
+    /// Profile variables being expanded, to detect cycles
+    expanding: Vec<String>,
+    /// Variables loaded from dotenv files, with the file each came from
+    env_files: HashMap<String, (String, PathBuf)>,
+    /// Credentials read from `file:`, `cmd:` and `enc:` sources, by directory and source
+    secrets: HashMap<(Option<PathBuf>, String), String>,
+    /// Keys for `enc:` values by directory, loaded on first use
+    secret_keys: HashMap<PathBuf, SecretKey>,
 }
 
 impl EnvironmentResolver {
@@ -13,131 +38,513 @@ impl EnvironmentResolver {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            variables: HashMap::new(),
+            overrides: HashMap::new(),
+            expanding: Vec::new(),
+            env_files: HashMap::new(),
+            secrets: HashMap::new(),
+            secret_keys: HashMap::new(),
         }
     }
 
     /// Resolves environment variables in a string value
-    /// Returns error if any referenced variable is not found
+    /// Supports `${VAR:-default}`, `${VAR:?message}`, `${VAR:+alternative}` and `$$` for a
+    /// literal `$`. Substituted values are never expanded again. Returns error if a referenced
+    /// variable without a default is not found.
     pub fn resolve(&mut self, value: &str) -> Result<String> {
         if !value.contains('$') {
             return Ok(value.to_string());
         }
 
-        let mut result = value.to_string();
-        let mut chars = value.chars().peekable();
-        let mut current_pos = 0;
-
-        while let Some(ch) = chars.next() {
-            if ch == '$' {
-                let start_pos = current_pos;
-                
-                // Handle ${VAR_NAME} syntax
-                let (var_name, end_pos) = if chars.peek() == Some(&'{') {
-                    chars.next(); // consume '{'
-                    let mut var_name = String::new();
-                    let mut found_closing = false;
-                    
-                    for ch in chars.by_ref() {
-                        if ch == '}' {
-                            found_closing = true;
-                            break;
-                        }
-                        if ch.is_alphanumeric() || ch == '_' {
-                            var_name.push(ch);
-                        } else {
-                            return Err(AsteriskError::InvalidEnvironmentVariable(
-                                format!("Invalid character '{}' in environment variable name", ch)
-                            ));
-                        }
-                    }
-                    
-                    if !found_closing {
-                        return Err(AsteriskError::InvalidEnvironmentVariable(
-                            "Unterminated environment variable reference (missing '}')".to_string()
-                        ));
-                    }
-                    
-                    {
-                        let name_len = var_name.len();
-                        (var_name, current_pos + name_len + 3) // ${ + name + }
-                    }
-                } 
+        let chars: Vec<char> = value.chars().collect();
+        let mut position = 0;
+        self.expand(&chars, &mut position, false, true)
+    }
+
+    /// Expands references from `position` to the end of the input, or up to the `}` closing
+    /// a `${VAR:-word}` when `nested`. Variables are only looked up when `evaluate` is set, so
+    /// words that end up unused never fail.
+    fn expand(&mut self, chars: &[char], position: &mut usize, nested: bool, evaluate: bool) -> Result<String> {
+        let mut result = String::new();
+
+        while let Some(&ch) = chars.get(*position) {
+            if nested && ch == '}' {
+                return Ok(result);
+            }
+            *position += 1;
+            if ch != '$' {
+                result.push(ch);
+                continue;
+            }
+
+            match chars.get(*position) {
+                // $$ is a literal dollar sign
+                Some('$') => {
+                    *position += 1;
+                    result.push('$');
+                }
+                // Handle ${VAR_NAME} syntax, with an optional operator
+                Some('{') => {
+                    *position += 1;
+                    result.push_str(&self.expand_braced(chars, position, evaluate)?);
+                }
                 // Handle $VAR_NAME syntax
-                else {
-                    let mut var_name = String::new();
-                    
-                    while let Some(&ch) = chars.peek() {
-                        if ch.is_alphanumeric() || ch == '_' {
-                            var_name.push(ch);
-                            chars.next();
-                        } else {
-                            break;
-                        }
-                    }
-                    
+                _ => {
+                    let var_name = take_name(chars, position);
                     if var_name.is_empty() {
                         return Err(AsteriskError::InvalidEnvironmentVariable(
                             "Empty environment variable name after '$'".to_string()
                         ));
                     }
-                    
-                    {
-                        let name_len = var_name.len();
-                        (var_name, current_pos + name_len + 1) // $ + name
+                    if evaluate {
+                        result.push_str(&self.required_var(&var_name)?);
                     }
+                }
+            }
+        }
+
+        if nested {
+            return Err(AsteriskError::InvalidEnvironmentVariable(
+                "Unterminated environment variable reference (missing '}')".to_string()
+            ));
+        }
+        Ok(result)
+    }
+
+    /// Expands a `${...}` reference, with `position` just past the opening brace
+    fn expand_braced(&mut self, chars: &[char], position: &mut usize, evaluate: bool) -> Result<String> {
+        let mut var_name = take_name(chars, position);
+        if var_name.is_empty() {
+            return Err(AsteriskError::InvalidEnvironmentVariable(
+                "Empty environment variable name".to_string()
+            ));
+        }
+        if var_name == VARS_PREFIX.trim_end_matches('.') && chars.get(*position) == Some(&'.') {
+            *position += 1;
+            let name = take_name(chars, position);
+            if name.is_empty() {
+                return Err(AsteriskError::InvalidEnvironmentVariable(
+                    format!("Empty variable name after '{}'", VARS_PREFIX)
+                ));
+            }
+            var_name = format!("{}{}", VARS_PREFIX, name);
+        }
+
+        // Built-in arguments, as in ${now:%Y-%m-%d} or ${random_int:1:100}
+        let mut args = None;
+        if builtins::is_builtin(&var_name)
+            && chars.get(*position) == Some(&':')
+            && !matches!(chars.get(*position + 1), Some('-' | '?' | '+'))
+        {
+            *position += 1;
+            let start = *position;
+            while !matches!(chars.get(*position), None | Some('}' | '|')) {
+                *position += 1;
+            }
+            args = Some(chars[start..*position].iter().collect::<String>());
+        }
+
+        // Filters, as in ${API_KEY|base64|urlencode}
+        let mut filters = Vec::new();
+        while chars.get(*position) == Some(&'|') {
+            *position += 1;
+            let filter = take_name(chars, position);
+            if filter.is_empty() {
+                return Err(AsteriskError::InvalidEnvironmentVariable(
+                    format!("Empty filter name in reference to '{}'", var_name)
+                ));
+            }
+            filters.push(filter);
+        }
+
+        let operator = match (chars.get(*position), chars.get(*position + 1)) {
+            (Some('}'), _) => {
+                *position += 1;
+                if !evaluate {
+                    return Ok(String::new());
+                }
+                let mut value = match args {
+                    Some(ref args) => builtins::evaluate(&var_name, Some(args))?,
+                    None => self.required_var(&var_name)?,
                 };
+                for filter in &filters {
+                    value = builtins::apply_filter(&value, filter)?;
+                }
+                return Ok(value);
+            }
+            (Some(':'), Some(&operator @ ('-' | '?' | '+'))) if args.is_none() && filters.is_empty() => {
+                *position += 2;
+                operator
+            }
+            (Some(&ch), _) => {
+                return Err(AsteriskError::InvalidEnvironmentVariable(
+                    format!("Invalid character '{}' in environment variable name", ch)
+                ));
+            }
+            (None, _) => {
+                return Err(AsteriskError::InvalidEnvironmentVariable(
+                    "Unterminated environment variable reference (missing '}')".to_string()
+                ));
+            }
+        };
+
+        // Unset and empty variables are treated alike, as in the shell
+        let current = if evaluate { self.lookup(&var_name)?.filter(|value| !value.is_empty()) } else { None };
+        let word_used = evaluate && match operator {
+            '+' => current.is_some(),
+            _ => current.is_none(),
+        };
+        let word = self.expand(chars, position, true, word_used)?;
+        *position += 1; // consume '}'
+
+        match (operator, current) {
+            ('-', Some(value)) => Ok(value),
+            ('-', None) => Ok(word),
+            ('?', Some(value)) => Ok(value),
+            ('?', None) if !evaluate => Ok(String::new()),
+            ('?', None) if word.is_empty() => Err(missing(var_name)),
+            ('?', None) => Err(missing(format!("{}: {}", var_name, word))),
+            (_, Some(_)) => Ok(word),
+            (_, None) => Ok(String::new()),
+        }
+    }
+
+    /// Expands only `${vars.name}` and built-in references such as `${uuid}`, leaving any
+    /// other `$` untouched. Used for values typed on the command line, where `$` is often
+    /// meant literally.
+    pub fn resolve_inline(&mut self, value: &str) -> Result<String> {
+        let chars: Vec<char> = value.chars().collect();
+        let mut result = String::new();
+        let mut position = 0;
 
-                // Validate variable name
-                if var_name.is_empty() {
-                    return Err(AsteriskError::InvalidEnvironmentVariable(
-                        "Empty environment variable name".to_string()
-                    ));
+        while let Some(&ch) = chars.get(position) {
+            if ch == '$' && chars.get(position + 1) == Some(&'{') {
+                let mut name_end = position + 2;
+                let name = take_name(&chars, &mut name_end);
+                let is_vars = name == VARS_PREFIX.trim_end_matches('.') && chars.get(name_end) == Some(&'.');
+                if is_vars || builtins::is_builtin(&name) {
+                    position += 2; // skip "${"
+                    result.push_str(&self.expand_braced(&chars, &mut position, true)?);
+                    continue;
                 }
+            }
+            result.push(ch);
+            position += 1;
+        }
+        Ok(result)
+    }
 
-                if !var_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
-                    return Err(AsteriskError::InvalidEnvironmentVariable(
-                        format!("Invalid environment variable name: {}", var_name)
-                    ));
+    /// Resolves a credential, which may also be read from a file (`file:PATH`), the
+    /// output of a command (`cmd:COMMAND`) or encrypted (`enc:...`, see `asterisk secret`).
+    /// `dir` is the directory of the configuration file that sets it, where relative paths
+    /// and the key file are found; commands only run with `allow_commands`. Each source is
+    /// read at most once per invocation.
+    pub fn resolve_secret(&mut self, value: &str, dir: Option<&Path>, allow_commands: bool) -> Result<String> {
+        let is_source = value.starts_with(FILE_SECRET_PREFIX)
+            || value.starts_with(COMMAND_SECRET_PREFIX)
+            || secrets::is_encrypted(value);
+        if !is_source {
+            return self.resolve(value);
+        }
+        if value.starts_with(COMMAND_SECRET_PREFIX) && !allow_commands {
+            return Err(AsteriskError::SecretSource(format!(
+                "'{}' is only run from the user or local config, not from a file that may come with the project",
+                value
+            )));
+        }
+        let cache_key = (dir.map(Path::to_path_buf), value.to_string());
+        if let Some(secret) = self.secrets.get(&cache_key) {
+            return Ok(secret.clone());
+        }
+
+        let secret = if let Some(path) = value.strip_prefix(FILE_SECRET_PREFIX) {
+            let path = PathBuf::from(self.resolve(path.trim())?);
+            let path = match dir {
+                Some(dir) if path.is_relative() => dir.join(path),
+                _ => path,
+            };
+            let content = fs::read_to_string(&path)
+                .map_err(|e| AsteriskError::SecretSource(format!("Failed to read {}: {}", path.display(), e)))?;
+            content.trim_end_matches(['\r', '\n']).to_string()
+        } else if let Some(command) = value.strip_prefix(COMMAND_SECRET_PREFIX) {
+            run_secret_command(command.trim())?.trim_end_matches(['\r', '\n']).to_string()
+        } else {
+            let dir = dir.map(Path::to_path_buf).unwrap_or_default();
+            let key = match self.secret_keys.entry(dir) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let key = SecretKey::load(entry.key())?;
+                    entry.insert(key)
                 }
+            };
+            key.decrypt(value)?
+        };
+        self.secrets.insert(cache_key, secret.clone());
+        Ok(secret)
+    }
 
-                // Resolve variable value
-                let var_value = self.get_env_var(&var_name)?;
-                
-                // Replace in result string
-                let pattern = if value.chars().nth(start_pos + 1) == Some('{') {
-                    format!("${{{}}}", var_name)
-                } else {
-                    format!("${}", var_name)
-                };
-                
-                result = result.replace(&pattern, &var_value);
-                current_pos = end_pos;
-            } else {
-                current_pos += ch.len_utf8();
+    /// Sets profile variables, which are referenced as `${vars.name}`
+    /// Their values may reference environment variables and other profile variables.
+    pub fn set_variables(&mut self, variables: HashMap<String, String>) {
+        self.variables = variables;
+    }
+
+    /// Sets variables given on the command line, which replace profile variables
+    pub fn set_overrides(&mut self, overrides: HashMap<String, String>) {
+        self.overrides = overrides;
+    }
+
+    /// Profile and command line variables as written, sorted by name
+    /// A variable that cannot be expanded carries the error. Expanded values are not
+    /// returned, as they may include secrets taken from the environment.
+    pub fn variable_templates(&mut self) -> Vec<(String, String, Option<AsteriskError>)> {
+        let mut names: Vec<String> = self.variables.keys().chain(self.overrides.keys()).cloned().collect();
+        names.sort();
+        names.dedup();
+        names.into_iter()
+            .map(|name| {
+                let template = self.overrides.get(&name).or_else(|| self.variables.get(&name)).cloned().unwrap_or_default();
+                let error = self.profile_var(&name).err();
+                (name, template, error)
+            })
+            .collect()
+    }
+
+    /// Loads variables from dotenv files, later files overriding earlier ones
+    /// Variables set in the process environment still take precedence.
+    pub fn set_env_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        self.env_files.clear();
+        for path in paths {
+            let content = fs::read_to_string(path)
+                .map_err(|e| AsteriskError::Config(format!("Failed to read env file {}: {}", path.display(), e)))?;
+            let variables = parse_dotenv(&content)
+                .map_err(|e| AsteriskError::Config(format!("Invalid env file {}: {}", path.display(), e)))?;
+            for (name, value) in variables {
+                self.env_files.insert(name, (value, path.clone()));
             }
         }
+        Ok(())
+    }
 
-        Ok(result)
+    /// Variables loaded from dotenv files and the file each came from, sorted by name
+    pub fn env_file_variables(&self) -> Vec<(&str, &Path)> {
+        let mut variables: Vec<(&str, &Path)> = self.env_files.iter()
+            .map(|(name, (_, path))| (name.as_str(), path.as_path()))
+            .collect();
+        variables.sort();
+        variables
+    }
+
+    /// Gets a variable that must be set
+    fn required_var(&mut self, name: &str) -> Result<String> {
+        self.lookup(name)?.ok_or_else(|| missing(name.to_string()))
+    }
+
+    /// Looks up `vars.name` in the profile variables, any other name in the built-ins and
+    /// then the environment
+    fn lookup(&mut self, name: &str) -> Result<Option<String>> {
+        if let Some(name) = name.strip_prefix(VARS_PREFIX) {
+            return self.profile_var(name);
+        }
+        if builtins::is_builtin(name) {
+            return builtins::evaluate(name, None).map(Some);
+        }
+        Ok(self.get_env_var(name))
+    }
+
+    /// Expands a profile variable, failing when it refers back to itself
+    fn profile_var(&mut self, name: &str) -> Result<Option<String>> {
+        if let Some(value) = self.overrides.get(name) {
+            return Ok(Some(value.clone()));
+        }
+        let Some(template) = self.variables.get(name).cloned() else {
+            return Ok(None);
+        };
+
+        if let Some(start) = self.expanding.iter().position(|expanding| expanding == name) {
+            let mut cycle = self.expanding[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(AsteriskError::InvalidEnvironmentVariable(
+                format!("Variable cycle: {}", cycle.join(" -> "))
+            ));
+        }
+        self.expanding.push(name.to_string());
+        let value = self.resolve(&template);
+        self.expanding.pop();
+        value.map(Some)
     }
 
     /// Gets environment variable value with caching
-    fn get_env_var(&mut self, name: &str) -> Result<String> {
+    fn get_env_var(&mut self, name: &str) -> Option<String> {
         if let Some(cached_value) = self.cache.get(name) {
-            return Ok(cached_value.clone());
+            return Some(cached_value.clone());
         }
 
         match env::var(name) {
             Ok(value) => {
                 self.cache.insert(name.to_string(), value.clone());
-                Ok(value)
+                Some(value)
+            }
+            Err(_) => self.env_files.get(name).map(|(value, _)| value.clone()),
+        }
+    }
+}
+
+/// Runs a `cmd:` secret command through the shell and returns its output
+/// The command can prompt on the terminal, e.g. for a GPG passphrase.
+fn run_secret_command(command: &str) -> Result<String> {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let output = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| AsteriskError::SecretSource(format!("Failed to run '{}': {}", command, e)))?;
+
+    if !output.status.success() {
+        return Err(AsteriskError::SecretSource(format!("'{}' failed ({})", command, output.status)));
+    }
+    let secret = String::from_utf8(output.stdout)
+        .map_err(|_| AsteriskError::SecretSource(format!("'{}' printed invalid UTF-8", command)))?;
+    if secret.trim().is_empty() {
+        return Err(AsteriskError::SecretSource(format!("'{}' printed nothing", command)));
+    }
+    Ok(secret)
+}
+
+/// Error for a variable that is not set, with an optional message after the name
+fn missing(name: String) -> AsteriskError {
+    if name.starts_with(VARS_PREFIX) {
+        AsteriskError::UndefinedVariable(name)
+    } else {
+        AsteriskError::MissingEnvironmentVariable(name)
+    }
+}
+
+/// Reads a variable name (letters, digits and underscores) starting at `position`
+fn take_name(chars: &[char], position: &mut usize) -> String {
+    let mut name = String::new();
+    while let Some(&ch) = chars.get(*position) {
+        if !(ch.is_alphanumeric() || ch == '_') {
+            break;
+        }
+        name.push(ch);
+        *position += 1;
+    }
+    name
+}
+
+/// Parses dotenv content into variables, in file order
+/// Lines are `KEY=value`, optionally prefixed with `export`. Single-quoted values are
+/// literal, double-quoted values support escapes and both may span lines; unquoted
+/// values end at a ` #` comment.
+fn parse_dotenv(content: &str) -> std::result::Result<Vec<(String, String)>, String> {
+    let mut variables = Vec::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export").filter(|rest| rest.starts_with(char::is_whitespace)).map_or(line, str::trim_start);
+        let (name, value) = line.split_once('=')
+            .ok_or_else(|| format!("line {}: expected KEY=value", line_number))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("line {}: invalid variable name '{}'", line_number, name));
+        }
+
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let mut quoted = value[1..].to_string();
+                let end = loop {
+                    if let Some(end) = closing_quote(&quoted, quote) {
+                        break end;
+                    }
+                    match lines.next() {
+                        Some((_, next)) => {
+                            quoted.push('\n');
+                            quoted.push_str(next);
+                        }
+                        None => return Err(format!("line {}: unterminated {} quote", line_number, quote)),
+                    }
+                };
+                let rest = quoted[end + 1..].trim();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    return Err(format!("line {}: unexpected '{}' after closing quote", line_number, rest));
+                }
+                quoted.truncate(end);
+                if quote == '"' { unescape(&quoted) } else { quoted }
+            }
+            _ => {
+                let end = value.char_indices()
+                    .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+                    .map_or(value.len(), |(i, _)| i);
+                value[..end].trim_end().to_string()
             }
-            Err(_) => Err(AsteriskError::MissingEnvironmentVariable(name.to_string())),
+        };
+        variables.push((name.to_string(), value));
+    }
+    Ok(variables)
+}
+
+/// Byte index of the quote closing a value, skipping escaped double quotes
+fn closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quote == '"' => escaped = true,
+            _ if c == quote => return Some(index),
+            _ => {}
         }
     }
+    None
+}
 
-    /// Clears the environment variable cache
-    pub fn clear_cache(&mut self) {
-        self.cache.clear();
+/// Expands the escapes allowed in double-quoted dotenv values
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some(escaped @ ('"' | '\\' | '$')) => result.push(escaped),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Returns the variable name when a value is exactly one `$VAR`, `${VAR}` or
+/// `${VAR:?message}` reference
+pub fn variable_reference(value: &str) -> Option<&str> {
+    let name = value.strip_prefix('$')?;
+    let name = match name.strip_prefix('{') {
+        Some(braced) => {
+            let braced = braced.strip_suffix('}')?;
+            braced.split_once(":?").map_or(braced, |(name, _)| name)
+        }
+        None => name,
+    };
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(name)
+    } else {
+        None
     }
 }
 
@@ -185,6 +592,130 @@ mod tests {
         env::remove_var("API_HOST");
     }
 
+    #[test]
+    fn test_shell_operators() {
+        env::set_var("TEST_OPS_SET", "value");
+        env::set_var("TEST_OPS_EMPTY", "");
+        let mut resolver = EnvironmentResolver::new();
+        assert_eq!(resolver.resolve("${TEST_OPS_UNSET:-fallback}").unwrap(), "fallback");
+        assert_eq!(resolver.resolve("${TEST_OPS_EMPTY:-fallback}").unwrap(), "fallback");
+        assert_eq!(resolver.resolve("${TEST_OPS_SET:-fallback}").unwrap(), "value");
+        assert_eq!(resolver.resolve("${TEST_OPS_UNSET:-$TEST_OPS_SET/x}").unwrap(), "value/x");
+        assert_eq!(resolver.resolve("${TEST_OPS_SET:-$TEST_OPS_UNSET}").unwrap(), "value");
+        assert_eq!(resolver.resolve("${TEST_OPS_SET:+on}|${TEST_OPS_UNSET:+on}").unwrap(), "on|");
+        assert_eq!(resolver.resolve("${TEST_OPS_SET:?not used}").unwrap(), "value");
+
+        let error = resolver.resolve("${TEST_OPS_UNSET:?set it in .env}").unwrap_err();
+        assert_eq!(error.to_string(), "Missing required environment variable: TEST_OPS_UNSET: set it in .env");
+        assert!(resolver.resolve("${TEST_OPS_UNSET:-unterminated").is_err());
+        assert!(resolver.resolve("${TEST_OPS_SET:=x}").is_err());
+        env::remove_var("TEST_OPS_SET");
+        env::remove_var("TEST_OPS_EMPTY");
+    }
+
+    #[test]
+    fn test_single_pass_substitution() {
+        env::set_var("TEST_PASS_DOLLAR", "$TEST_PASS_OTHER");
+        env::set_var("TEST_PASS_A", "a");
+        env::set_var("TEST_PASS_AB", "ab");
+        let mut resolver = EnvironmentResolver::new();
+        assert_eq!(resolver.resolve("$TEST_PASS_DOLLAR").unwrap(), "$TEST_PASS_OTHER");
+        assert_eq!(resolver.resolve("$TEST_PASS_A-$TEST_PASS_AB-${TEST_PASS_A}").unwrap(), "a-ab-a");
+        assert_eq!(resolver.resolve("cost: $$5, $$TEST_PASS_A").unwrap(), "cost: $5, $TEST_PASS_A");
+        env::remove_var("TEST_PASS_DOLLAR");
+        env::remove_var("TEST_PASS_A");
+        env::remove_var("TEST_PASS_AB");
+    }
+
+    #[test]
+    fn test_builtins_and_filters() {
+        env::set_var("TEST_FILTER_CREDENTIALS", "user:pass");
+        let mut resolver = EnvironmentResolver::new();
+        resolver.set_variables(HashMap::from([("email".to_string(), "qa+${random_int:1:1}@example.com".to_string())]));
+
+        let body = resolver.resolve(r#"{"id":"${uuid}","day":"${now:%Y}","at":${unix_ms}}"#).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["id"].as_str().unwrap().len(), 36);
+        assert_eq!(body["day"].as_str().unwrap().len(), 4);
+        assert!(body["at"].as_u64().unwrap() > 1_700_000_000_000);
+
+        assert_eq!(resolver.resolve("Basic ${TEST_FILTER_CREDENTIALS|base64}").unwrap(), "Basic dXNlcjpwYXNz");
+        assert_eq!(resolver.resolve("${vars.email|urlencode}").unwrap(), "qa%2B1%40example.com");
+        assert_eq!(resolver.resolve("${TEST_FILTER_CREDENTIALS|base64|sha256}").unwrap().len(), 64);
+        assert!(resolver.resolve("${TEST_FILTER_CREDENTIALS|rot13}").is_err());
+        assert!(resolver.resolve("${TEST_FILTER_CREDENTIALS|}").is_err());
+
+        // Built-ins also expand in command line values, other references do not
+        let inline = resolver.resolve_inline("$TEST_FILTER_CREDENTIALS ${random_int:7:7} ${TEST_FILTER_CREDENTIALS|base64}").unwrap();
+        assert_eq!(inline, "$TEST_FILTER_CREDENTIALS 7 ${TEST_FILTER_CREDENTIALS|base64}");
+        env::remove_var("TEST_FILTER_CREDENTIALS");
+    }
+
+    #[test]
+    fn test_secret_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("api"), "from-file\n").unwrap();
+        let mut resolver = EnvironmentResolver::new();
+        let base = Some(dir.path());
+
+        assert_eq!(resolver.resolve_secret("file:api", base, false).unwrap(), "from-file");
+        // Sources are read once per invocation
+        fs::write(dir.path().join("api"), "changed").unwrap();
+        assert_eq!(resolver.resolve_secret("file:api", base, false).unwrap(), "from-file");
+
+        assert!(matches!(resolver.resolve_secret("file:missing", base, false), Err(AsteriskError::SecretSource(_))));
+        assert!(matches!(resolver.resolve_secret("cmd:echo x", base, false), Err(AsteriskError::SecretSource(_))));
+        if cfg!(unix) {
+            assert_eq!(resolver.resolve_secret("cmd:printf 'from-cmd\\n'", base, true).unwrap(), "from-cmd");
+            assert!(matches!(resolver.resolve_secret("cmd:exit 3", base, true), Err(AsteriskError::SecretSource(_))));
+            assert!(matches!(resolver.resolve_secret("cmd:true", base, true), Err(AsteriskError::SecretSource(_))));
+        }
+        assert_eq!(resolver.resolve_secret("plain", base, false).unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_profile_variables_are_namespaced() {
+        env::set_var("TEST_SHADOWED", "from_env");
+        let mut resolver = EnvironmentResolver::new();
+        resolver.set_variables(HashMap::from([
+            ("TEST_SHADOWED".to_string(), "from_profile".to_string()),
+            ("tenant".to_string(), "acme".to_string()),
+        ]));
+
+        // Profile and command line variables never replace the environment or built-ins
+        assert_eq!(resolver.resolve("${vars.tenant}/${vars.TEST_SHADOWED}/$TEST_SHADOWED").unwrap(), "acme/from_profile/from_env");
+        resolver.set_overrides(HashMap::from([("TEST_SHADOWED".to_string(), "from_cli".to_string())]));
+        assert_eq!(resolver.resolve("${vars.TEST_SHADOWED}/$TEST_SHADOWED").unwrap(), "from_cli/from_env");
+        assert!(matches!(resolver.resolve("${tenant}"), Err(AsteriskError::MissingEnvironmentVariable(_))));
+        env::remove_var("TEST_SHADOWED");
+    }
+
+    #[test]
+    fn test_profile_variable_references() {
+        env::set_var("TEST_VARS_REGION", "eu");
+        let mut resolver = EnvironmentResolver::new();
+        resolver.set_variables(HashMap::from([
+            ("tenant_id".to_string(), "acme".to_string()),
+            ("api_version".to_string(), "v2".to_string()),
+            ("base_path".to_string(), "${vars.api_version}/${vars.tenant_id}-$TEST_VARS_REGION".to_string()),
+            ("loop_a".to_string(), "${vars.loop_b}".to_string()),
+            ("loop_b".to_string(), "x${vars.loop_a}".to_string()),
+        ]));
+        resolver.set_overrides(HashMap::from([("api_version".to_string(), "$v3".to_string())]));
+
+        assert_eq!(resolver.resolve("/${vars.base_path}/users").unwrap(), "/$v3/acme-eu/users");
+        assert_eq!(resolver.resolve("${vars.missing:-none}").unwrap(), "none");
+        assert!(matches!(resolver.resolve("${vars.TEST_VARS_REGION}"), Err(AsteriskError::UndefinedVariable(_))));
+        assert_eq!(
+            resolver.resolve("${vars.loop_a}").unwrap_err().to_string(),
+            "Invalid environment variable: Variable cycle: loop_a -> loop_b -> loop_a"
+        );
+
+        // Only vars. references are expanded in command line values
+        assert_eq!(resolver.resolve_inline("items?$filter=${vars.tenant_id}&$TEST_VARS_REGION").unwrap(), "items?$filter=acme&$TEST_VARS_REGION");
+        env::remove_var("TEST_VARS_REGION");
+    }
+
     #[test]
     fn test_missing_variable() {
         let mut resolver = EnvironmentResolver::new();
@@ -208,4 +739,67 @@ mod tests {
         let mut resolver = EnvironmentResolver::new();
         assert!(resolver.resolve("${UNCLOSED").is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_dotenv() {
+        let content = r#"
+# Credentials for local development
+export DEV_API_KEY=abc123
+PLAIN = value with spaces   # trailing comment
+HASH=pass#word
+SINGLE='literal $HOME \n'
+DOUBLE="tab\there \"quoted\"" # comment
+MULTILINE="-----BEGIN KEY-----
+line two
+-----END KEY-----"
+EMPTY=
+"#;
+        let variables: HashMap<String, String> = parse_dotenv(content).unwrap().into_iter().collect();
+        assert_eq!(variables["DEV_API_KEY"], "abc123");
+        assert_eq!(variables["PLAIN"], "value with spaces");
+        assert_eq!(variables["HASH"], "pass#word");
+        assert_eq!(variables["SINGLE"], "literal $HOME \\n");
+        assert_eq!(variables["DOUBLE"], "tab\there \"quoted\"");
+        assert_eq!(variables["MULTILINE"], "-----BEGIN KEY-----\nline two\n-----END KEY-----");
+        assert_eq!(variables["EMPTY"], "");
+
+        assert!(parse_dotenv("NO_EQUALS").is_err());
+        assert!(parse_dotenv("BAD-NAME=1").is_err());
+        assert!(parse_dotenv("OPEN=\"never closed\nstill open").is_err());
+    }
+
+    #[test]
+    fn test_env_files_yield_to_process_environment() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let project = dir.path().join(".env");
+        let profile = dir.path().join("staging.env");
+        fs::write(&project, "TEST_DOTENV_KEY=project\nTEST_DOTENV_SHARED=project\nTEST_DOTENV_EXPORTED=file\n").unwrap();
+        fs::write(&profile, "TEST_DOTENV_SHARED=profile\n").unwrap();
+        env::set_var("TEST_DOTENV_EXPORTED", "process");
+
+        let mut resolver = EnvironmentResolver::new();
+        resolver.set_env_files(&[project.clone(), profile.clone()]).unwrap();
+        assert_eq!(
+            resolver.resolve("$TEST_DOTENV_KEY/$TEST_DOTENV_SHARED/$TEST_DOTENV_EXPORTED").unwrap(),
+            "project/profile/process"
+        );
+        assert_eq!(resolver.env_file_variables(), vec![
+            ("TEST_DOTENV_EXPORTED", project.as_path()),
+            ("TEST_DOTENV_KEY", project.as_path()),
+            ("TEST_DOTENV_SHARED", profile.as_path()),
+        ]);
+        assert!(resolver.set_env_files(&[dir.path().join("missing.env")]).is_err());
+        env::remove_var("TEST_DOTENV_EXPORTED");
+    }
+
+    #[test]
+    fn test_variable_reference() {
+        assert_eq!(variable_reference("$API_TOKEN"), Some("API_TOKEN"));
+        assert_eq!(variable_reference("${API_TOKEN}"), Some("API_TOKEN"));
+        assert_eq!(variable_reference("${API_TOKEN:?log in first}"), Some("API_TOKEN"));
+        assert_eq!(variable_reference("${API_TOKEN:-dev}"), None);
+        assert_eq!(variable_reference("Bearer $API_TOKEN"), None);
+        assert_eq!(variable_reference("${API_TOKEN"), None);
+        assert_eq!(variable_reference("literal"), None);
+    }
+}
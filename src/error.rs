@@ -22,7 +22,28 @@ pub enum AsteriskError {
     
     #[error("Invalid environment variable: {0}")]
     InvalidEnvironmentVariable(String),
+
+    #[error("Undefined profile variable: {0}")]
+    UndefinedVariable(String),
+
+    #[error("Secret source error: {0}")]
+    SecretSource(String),
+    
+    #[error("{request} failed with HTTP {status}")]
+    HttpStatus { request: String, status: u16 },
+
+    #[error("Expectation failed: {0}")]
+    ExpectationFailed(String),
     
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
+    #[error("GraphQL error: {0}")]
+    GraphQl(String),
+
+    #[error("{}", format_rpc_error(.code, .message))]
+    JsonRpc { code: i64, message: String },
+
     #[error("TOML parsing error: {0}")]
     TomlParsing(#[from] toml::de::Error),
 }
@@ -38,3 +59,20 @@ fn format_http_error(err: &ReqwestError) -> String {
         err.to_string()
     }
 }
+
+fn format_rpc_error(code: &i64, message: &str) -> String {
+    format!("JSON-RPC error {} ({}): {}", code, crate::jsonrpc::code_name(*code), message)
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for AsteriskError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        use tokio_tungstenite::tungstenite::Error;
+        match err {
+            Error::Http(response) => AsteriskError::WebSocket(format!(
+                "handshake rejected with HTTP {}", response.status().as_u16()
+            )),
+            Error::Io(err) => AsteriskError::WebSocket(format!("could not connect ({})", err)),
+            err => AsteriskError::WebSocket(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use crate::config::{self, Secret};
+use crate::request::PreparedRequest;
+use clap::ValueEnum;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+
+/// Output format for `--as`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// curl command line
+    Curl,
+    /// Rust using reqwest
+    Reqwest,
+    /// Python using requests
+    Python,
+    /// JavaScript using fetch
+    Fetch,
+}
+
+/// A piece of an exported string: literal text or a variable reference
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Var(String),
+}
+
+/// A request flattened into templated strings, ready to be rendered
+struct Export {
+    method: String,
+    url: Vec<Part>,
+    headers: Vec<(String, Vec<Part>)>,
+    body: Option<Vec<Part>>,
+}
+
+/// Renders a prepared request as a command line or code snippet
+/// With `mask_secrets`, credentials are replaced by `$VAR` references.
+pub fn render(format: ExportFormat, request: &PreparedRequest, mask_secrets: bool) -> String {
+    let secrets: &[Secret] = if mask_secrets { &request.secrets } else { &[] };
+    let export = Export::new(request, secrets, mask_secrets);
+
+    match format {
+        ExportFormat::Curl => render_curl(&export),
+        ExportFormat::Reqwest => render_reqwest(&export),
+        ExportFormat::Python => render_python(&export),
+        ExportFormat::Fetch => render_fetch(&export),
+    }
+}
+
+impl Export {
+    fn new(request: &PreparedRequest, secrets: &[Secret], mask_secrets: bool) -> Self {
+        let mut headers = Vec::new();
+        for (name, value) in &request.headers {
+            headers.push((name.as_str().to_string(), header_template(name, value, secrets, mask_secrets)));
+        }
+
+        // Mirrors the default applied by `HttpClient::send_request`
+        if request.body.is_some() && !request.headers.contains_key(CONTENT_TYPE) {
+            headers.push((CONTENT_TYPE.as_str().to_string(), vec![Part::Text("application/json".to_string())]));
+        }
+
+        Self {
+            method: request.method.to_uppercase(),
+            url: template(&request.url, secrets),
+            headers,
+            body: request.body.as_deref().map(|body| template(body, secrets)),
+        }
+    }
+
+    fn uses_variables(&self) -> bool {
+        let has_var = |parts: &Vec<Part>| parts.iter().any(|part| matches!(part, Part::Var(_)));
+        has_var(&self.url)
+            || self.headers.iter().any(|(_, parts)| has_var(parts))
+            || self.body.as_ref().is_some_and(has_var)
+    }
+}
+
+/// Text with every secret value replaced by its `$VAR` reference, as with `--mask-secrets`
+pub fn mask_text(text: &str, secrets: &[Secret]) -> String {
+    plain(&template(text, secrets))
+}
+
+/// Header value with its credentials replaced by `$VAR` references, as with `--mask-secrets`
+pub fn mask_header(name: &HeaderName, value: &HeaderValue, secrets: &[Secret]) -> String {
+    plain(&header_template(name, value, secrets, true))
+}
+
+fn plain(parts: &[Part]) -> String {
+    parts.iter()
+        .map(|part| match part {
+            Part::Text(text) => text.clone(),
+            Part::Var(variable) => format!("${}", variable),
+        })
+        .collect()
+}
+
+/// Splits a header value around secrets; with `mask_secrets`, credential headers that
+/// hold no known secret are named after the header
+fn header_template(name: &HeaderName, value: &HeaderValue, secrets: &[Secret], mask_secrets: bool) -> Vec<Part> {
+    let text = String::from_utf8_lossy(value.as_bytes()).to_string();
+    let parts = template(&text, secrets);
+    if !mask_secrets || !config::is_credential_header(name, value) || parts.iter().any(|part| matches!(part, Part::Var(_))) {
+        return parts;
+    }
+
+    let variable = name.as_str().to_uppercase().replace('-', "_");
+    match text.split_once(' ') {
+        Some((scheme, _)) if *name == AUTHORIZATION => vec![
+            Part::Text(format!("{} ", scheme)),
+            Part::Var(variable),
+        ],
+        _ => vec![Part::Var(variable)],
+    }
+}
+
+/// Splits text around every occurrence of a secret value
+fn template(text: &str, secrets: &[Secret]) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+
+    loop {
+        // Earliest match wins; the longest secret breaks ties
+        let next = secrets.iter()
+            .filter(|secret| !secret.value.is_empty())
+            .filter_map(|secret| rest.find(&secret.value).map(|index| (index, secret)))
+            .min_by_key(|(index, secret)| (*index, std::cmp::Reverse(secret.value.len())));
+
+        match next {
+            Some((index, secret)) => {
+                if index > 0 {
+                    parts.push(Part::Text(rest[..index].to_string()));
+                }
+                parts.push(Part::Var(secret.variable.clone()));
+                rest = &rest[index + secret.value.len()..];
+            }
+            None => {
+                if !rest.is_empty() || parts.is_empty() {
+                    parts.push(Part::Text(rest.to_string()));
+                }
+                return parts;
+            }
+        }
+    }
+}
+
+fn render_curl(export: &Export) -> String {
+    let mut lines = vec![match export.method.as_str() {
+        "GET" => format!("curl {}", shell_word(&export.url)),
+        "HEAD" => format!("curl --head {}", shell_word(&export.url)),
+        method => format!("curl -X {} {}", method, shell_word(&export.url)),
+    }];
+
+    for (name, value) in &export.headers {
+        let header = match value.split_first() {
+            Some((Part::Text(text), rest)) => [&[Part::Text(format!("{}: {}", name, text))], rest].concat(),
+            _ => [&[Part::Text(format!("{}: ", name))], value.as_slice()].concat(),
+        };
+        lines.push(format!("-H {}", shell_word(&header)));
+    }
+    if let Some(ref body) = export.body {
+        lines.push(format!("--data-raw {}", shell_word(body)));
+    }
+
+    format!("{}\n", lines.join(" \\\n  "))
+}
+
+/// Single-quotes literal text and double-quotes variable references
+fn shell_word(parts: &[Part]) -> String {
+    parts.iter()
+        .map(|part| match part {
+            Part::Text(text) => format!("'{}'", text.replace('\'', "'\\''")),
+            Part::Var(variable) => format!("\"${}\"", variable),
+        })
+        .collect()
+}
+
+fn render_reqwest(export: &Export) -> String {
+    let mut code = String::from("let client = reqwest::Client::new();\nlet response = client\n");
+    code.push_str(&format!("    .request(reqwest::Method::{}, {})\n", export.method, rust_string(&export.url)));
+    for (name, value) in &export.headers {
+        code.push_str(&format!("    .header({:?}, {})\n", name, rust_string(value)));
+    }
+    if let Some(ref body) = export.body {
+        code.push_str(&format!("    .body({})\n", rust_string(body)));
+    }
+    code.push_str("    .send()\n    .await?;\n");
+    code.push_str("println!(\"{}\", response.status());\nprintln!(\"{}\", response.text().await?);\n");
+    code
+}
+
+/// A string literal, or `format!` reading variables from the environment
+fn rust_string(parts: &[Part]) -> String {
+    if let [Part::Text(text)] = parts {
+        return format!("{:?}", text);
+    }
+
+    let mut pattern = String::new();
+    let mut arguments = Vec::new();
+    for part in parts {
+        match part {
+            Part::Text(text) => pattern.push_str(&text.replace('{', "{{").replace('}', "}}")),
+            Part::Var(variable) => {
+                pattern.push_str("{}");
+                arguments.push(format!("std::env::var({:?})?", variable));
+            }
+        }
+    }
+    format!("format!({:?}, {})", pattern, arguments.join(", "))
+}
+
+fn render_python(export: &Export) -> String {
+    let mut code = String::new();
+    if export.uses_variables() {
+        code.push_str("import os\n");
+    }
+    code.push_str("import requests\n\nresponse = requests.request(\n");
+    code.push_str(&format!("    {},\n    {},\n", json_string(&export.method), concat(&export.url, python_var)));
+    if !export.headers.is_empty() {
+        code.push_str("    headers={\n");
+        for (name, value) in &export.headers {
+            code.push_str(&format!("        {}: {},\n", json_string(name), concat(value, python_var)));
+        }
+        code.push_str("    },\n");
+    }
+    if let Some(ref body) = export.body {
+        code.push_str(&format!("    data={},\n", concat(body, python_var)));
+    }
+    code.push_str(")\nprint(response.status_code)\nprint(response.text)\n");
+    code
+}
+
+fn render_fetch(export: &Export) -> String {
+    let mut code = format!("const response = await fetch({}, {{\n", concat(&export.url, fetch_var));
+    code.push_str(&format!("  method: {},\n", json_string(&export.method)));
+    if !export.headers.is_empty() {
+        code.push_str("  headers: {\n");
+        for (name, value) in &export.headers {
+            code.push_str(&format!("    {}: {},\n", json_string(name), concat(value, fetch_var)));
+        }
+        code.push_str("  },\n");
+    }
+    if let Some(ref body) = export.body {
+        code.push_str(&format!("  body: {},\n", concat(body, fetch_var)));
+    }
+    code.push_str("});\nconsole.log(response.status);\nconsole.log(await response.text());\n");
+    code
+}
+
+fn python_var(variable: &str) -> String {
+    format!("os.environ[{}]", json_string(variable))
+}
+
+fn fetch_var(variable: &str) -> String {
+    format!("process.env[{}]", json_string(variable))
+}
+
+/// Joins parts with `+`, quoting text as a JSON string literal (valid in Python and JavaScript)
+fn concat(parts: &[Part], var: fn(&str) -> String) -> String {
+    parts.iter()
+        .map(|part| match part {
+            Part::Text(text) => json_string(text),
+            Part::Var(variable) => var(variable),
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn json_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderMap;
+
+    fn request() -> PreparedRequest {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer s3cr3t"));
+        headers.insert("x-tenant", HeaderValue::from_static("acme"));
+        PreparedRequest {
+            method: "post".to_string(),
+            url: "http://localhost:3000/users?key=k3y".to_string(),
+            headers,
+            body: Some(r#"{"name":"O'Brien"}"#.to_string()),
+            verbose: false,
+            secrets: vec![
+                Secret { value: "s3cr3t".to_string(), variable: "DEV_TOKEN".to_string() },
+                Secret { value: "k3y".to_string(), variable: "API_KEY".to_string() },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_curl() {
+        assert_eq!(render(ExportFormat::Curl, &request(), false), concat!(
+            "curl -X POST 'http://localhost:3000/users?key=k3y' \\\n",
+            "  -H 'authorization: Bearer s3cr3t' \\\n",
+            "  -H 'x-tenant: acme' \\\n",
+            "  -H 'content-type: application/json' \\\n",
+            "  --data-raw '{\"name\":\"O'\\''Brien\"}'\n",
+        ));
+    }
+
+    #[test]
+    fn test_curl_masks_secrets() {
+        let output = render(ExportFormat::Curl, &request(), true);
+        assert!(output.starts_with("curl -X POST 'http://localhost:3000/users?key='\"$API_KEY\" \\\n"));
+        assert!(output.contains("-H 'authorization: Bearer '\"$DEV_TOKEN\""));
+        assert!(!output.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn test_unknown_credentials_are_named_after_header() {
+        let mut request = request();
+        request.secrets.clear();
+        let output = render(ExportFormat::Python, &request, true);
+        assert!(output.starts_with("import os\nimport requests\n"));
+        assert!(output.contains(r#""authorization": "Bearer " + os.environ["AUTHORIZATION"],"#));
+        assert!(output.contains(r#""x-tenant": "acme","#));
+    }
+
+    #[test]
+    fn test_code_snippets() {
+        let request = request();
+
+        let rust = render(ExportFormat::Reqwest, &request, true);
+        assert!(rust.contains(r#".header("authorization", format!("Bearer {}", std::env::var("DEV_TOKEN")?))"#));
+        assert!(rust.contains(r#".body("{\"name\":\"O'Brien\"}")"#));
+
+        let fetch = render(ExportFormat::Fetch, &request, false);
+        assert!(fetch.starts_with("const response = await fetch(\"http://localhost:3000/users?key=k3y\", {\n  method: \"POST\",\n"));
+        assert!(fetch.contains(r#"  body: "{\"name\":\"O'Brien\"}","#));
+    }
+}
@@ -0,0 +1,419 @@
+use crate::error::{AsteriskError, Result};
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+
+/// Endpoint used when the profile has no `graphql_endpoint`
+pub const DEFAULT_ENDPOINT: &str = "graphql";
+
+/// Standard introspection query, covering everything needed to print SDL
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } } } }
+}"#;
+
+/// Scalars every schema has, left out of printed SDL
+const BUILTIN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+
+/// Directives every schema has, left out of printed SDL
+const BUILTIN_DIRECTIVES: &[&str] = &["include", "skip", "deprecated", "specifiedBy", "oneOf"];
+
+/// Names of the operations defined in a query document
+pub fn operation_names(document: &str) -> Vec<String> {
+    static OPERATION: OnceLock<Regex> = OnceLock::new();
+    let operation = OPERATION.get_or_init(|| {
+        Regex::new(r"(?m)^\s*(?:query|mutation|subscription)\s+([_A-Za-z][_0-9A-Za-z]*)").expect("valid operation pattern")
+    });
+    operation.captures_iter(document).map(|captures| captures[1].to_string()).collect()
+}
+
+/// Parses `--gql-var key=value` pairs; values that are valid JSON keep their type
+pub fn parse_variables(pairs: &[String]) -> Result<Map<String, Value>> {
+    let mut variables = Map::new();
+    for pair in pairs {
+        let (key, value) = pair.split_once('=').ok_or_else(|| AsteriskError::Config(
+            format!("Invalid variable '{}'. Expected 'key=value'", pair)
+        ))?;
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+        variables.insert(key.to_string(), value);
+    }
+    Ok(variables)
+}
+
+/// Reads `--variables`, given inline as a JSON object or as a path to one
+pub fn read_variables(source: &str) -> Result<Map<String, Value>> {
+    let content = if source.trim_start().starts_with('{') {
+        source.to_string()
+    } else {
+        std::fs::read_to_string(source).map_err(|e| AsteriskError::Config(
+            format!("Failed to read variables file '{}': {}", source, e)
+        ))?
+    };
+    match serde_json::from_str(&content) {
+        Ok(Value::Object(variables)) => Ok(variables),
+        _ => Err(AsteriskError::Config(format!("Variables in '{}' must be a JSON object", source))),
+    }
+}
+
+/// Builds the request body, choosing the operation to run
+/// A single named operation is selected automatically; documents with several
+/// operations need `operation`.
+pub fn payload(document: &str, variables: Map<String, Value>, operation: Option<&str>) -> Result<Value> {
+    let names = operation_names(document);
+    let operation_name = match operation {
+        Some(name) if !names.iter().any(|candidate| candidate == name) => {
+            return Err(AsteriskError::Config(format!(
+                "Operation '{}' not found in the query (found: {})", name, list_or_none(&names)
+            )));
+        }
+        Some(name) => Some(name.to_string()),
+        None if names.len() > 1 => {
+            return Err(AsteriskError::Config(format!(
+                "The query defines several operations; choose one with --operation ({})", names.join(", ")
+            )));
+        }
+        None => names.into_iter().next(),
+    };
+
+    let mut body = json!({ "query": document });
+    if let Some(name) = operation_name {
+        body["operationName"] = json!(name);
+    }
+    if !variables.is_empty() {
+        body["variables"] = Value::Object(variables);
+    }
+    Ok(body)
+}
+
+/// Formats the `errors` of a GraphQL response, one line per error
+pub fn errors(response: &Value) -> Vec<String> {
+    let Some(errors) = response.get("errors").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    errors.iter()
+        .map(|error| {
+            let mut line = error.get("message").and_then(Value::as_str).unwrap_or("(no message)").to_string();
+            if let Some(path) = error.get("path").and_then(Value::as_array) {
+                let path: Vec<String> = path.iter()
+                    .map(|segment| segment.as_str().map(String::from).unwrap_or_else(|| segment.to_string()))
+                    .collect();
+                line.push_str(&format!(" at {}", path.join(".")));
+            }
+            if let Some(location) = error.pointer("/locations/0") {
+                let number = |key: &str| location.get(key).and_then(Value::as_u64).unwrap_or(0);
+                line.push_str(&format!(" (line {}, column {})", number("line"), number("column")));
+            }
+            if let Some(code) = error.pointer("/extensions/code").and_then(Value::as_str) {
+                line.push_str(&format!(" [{}]", code));
+            }
+            line
+        })
+        .collect()
+}
+
+fn list_or_none(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// Prints an introspection result as schema definition language
+pub fn schema_to_sdl(response: &Value) -> Result<String> {
+    let schema = response.pointer("/data/__schema").ok_or_else(|| AsteriskError::Config(
+        "Introspection response has no data.__schema (is introspection disabled?)".to_string()
+    ))?;
+    let mut blocks = Vec::new();
+
+    // The schema block is only needed when root types don't use the conventional names
+    let roots: Vec<(&str, &str, &str)> = [
+        ("query", "queryType", "Query"),
+        ("mutation", "mutationType", "Mutation"),
+        ("subscription", "subscriptionType", "Subscription"),
+    ]
+        .into_iter()
+        .filter_map(|(operation, key, conventional)| Some((operation, schema.get(key)?.get("name")?.as_str()?, conventional)))
+        .collect();
+    if roots.iter().any(|(_, name, conventional)| name != conventional) {
+        let fields: Vec<String> = roots.iter().map(|(operation, name, _)| format!("  {}: {}", operation, name)).collect();
+        blocks.push(format!("schema {{\n{}\n}}", fields.join("\n")));
+    }
+
+    for directive in schema.get("directives").and_then(Value::as_array).into_iter().flatten() {
+        let name = text(directive, "name");
+        if BUILTIN_DIRECTIVES.contains(&name) {
+            continue;
+        }
+        let locations: Vec<&str> = directive.get("locations").and_then(Value::as_array).into_iter().flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        blocks.push(format!(
+            "{}directive @{}{} on {}",
+            description(directive, ""),
+            name,
+            arguments(directive),
+            locations.join(" | ")
+        ));
+    }
+
+    for kind in schema.get("types").and_then(Value::as_array).into_iter().flatten() {
+        let name = text(kind, "name");
+        if name.starts_with("__") || BUILTIN_SCALARS.contains(&name) {
+            continue;
+        }
+        let head = description(kind, "");
+        let block = match text(kind, "kind") {
+            "SCALAR" => format!("{}scalar {}", head, name),
+            "OBJECT" | "INTERFACE" => {
+                let keyword = if text(kind, "kind") == "OBJECT" { "type" } else { "interface" };
+                let interfaces: Vec<String> = kind.get("interfaces").and_then(Value::as_array).into_iter().flatten()
+                    .map(type_ref)
+                    .collect();
+                let implements = if interfaces.is_empty() { String::new() } else { format!(" implements {}", interfaces.join(" & ")) };
+                let fields: Vec<String> = kind.get("fields").and_then(Value::as_array).into_iter().flatten()
+                    .map(|field| format!(
+                        "{}  {}{}: {}{}",
+                        description(field, "  "),
+                        text(field, "name"),
+                        arguments(field),
+                        type_ref(field.get("type").unwrap_or(&Value::Null)),
+                        deprecation(field)
+                    ))
+                    .collect();
+                format!("{}{} {}{} {{\n{}\n}}", head, keyword, name, implements, fields.join("\n"))
+            }
+            "UNION" => {
+                let members: Vec<String> = kind.get("possibleTypes").and_then(Value::as_array).into_iter().flatten()
+                    .map(type_ref)
+                    .collect();
+                format!("{}union {} = {}", head, name, members.join(" | "))
+            }
+            "ENUM" => {
+                let values: Vec<String> = kind.get("enumValues").and_then(Value::as_array).into_iter().flatten()
+                    .map(|value| format!("{}  {}{}", description(value, "  "), text(value, "name"), deprecation(value)))
+                    .collect();
+                format!("{}enum {} {{\n{}\n}}", head, name, values.join("\n"))
+            }
+            "INPUT_OBJECT" => {
+                let fields: Vec<String> = kind.get("inputFields").and_then(Value::as_array).into_iter().flatten()
+                    .map(|field| format!("{}  {}", description(field, "  "), input_value(field)))
+                    .collect();
+                format!("{}input {} {{\n{}\n}}", head, name, fields.join("\n"))
+            }
+            other => return Err(AsteriskError::Config(format!("Unknown type kind '{}' for {}", other, name))),
+        };
+        blocks.push(block);
+    }
+
+    Ok(blocks.join("\n\n") + "\n")
+}
+
+/// `String!`, `[User!]` and so on from an introspection type reference
+fn type_ref(reference: &Value) -> String {
+    match text(reference, "kind") {
+        "NON_NULL" => format!("{}!", type_ref(reference.get("ofType").unwrap_or(&Value::Null))),
+        "LIST" => format!("[{}]", type_ref(reference.get("ofType").unwrap_or(&Value::Null))),
+        _ => text(reference, "name").to_string(),
+    }
+}
+
+fn input_value(value: &Value) -> String {
+    let mut printed = format!("{}: {}", text(value, "name"), type_ref(value.get("type").unwrap_or(&Value::Null)));
+    if let Some(default) = value.get("defaultValue").and_then(Value::as_str) {
+        printed.push_str(&format!(" = {}", default));
+    }
+    printed
+}
+
+fn arguments(field: &Value) -> String {
+    let args: Vec<String> = field.get("args").and_then(Value::as_array).into_iter().flatten().map(input_value).collect();
+    if args.is_empty() {
+        String::new()
+    } else {
+        format!("({})", args.join(", "))
+    }
+}
+
+fn deprecation(value: &Value) -> String {
+    if value.get("isDeprecated").and_then(Value::as_bool) != Some(true) {
+        return String::new();
+    }
+    match value.get("deprecationReason").and_then(Value::as_str) {
+        Some(reason) if reason != "No longer supported" => {
+            format!(" @deprecated(reason: {})", serde_json::to_string(reason).unwrap_or_default())
+        }
+        _ => " @deprecated".to_string(),
+    }
+}
+
+/// Description as a block string on the lines before a definition
+fn description(value: &Value, indent: &str) -> String {
+    match value.get("description").and_then(Value::as_str).filter(|text| !text.is_empty()) {
+        Some(text) if !text.contains('\n') && !text.contains('"') => format!("{}\"{}\"\n{}", indent, text, indent),
+        Some(text) => {
+            let lines: Vec<String> = text.replace("\"\"\"", "\\\"\"\"").lines().map(|line| format!("{}{}", indent, line)).collect();
+            format!("{}\"\"\"\n{}\n{}\"\"\"\n{}", indent, lines.join("\n"), indent, indent)
+        }
+        None => String::new(),
+    }
+}
+
+fn text<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_operation_selection() {
+        let document = "query GetUser($id: ID!) { user(id: $id) { name } }";
+        let variables = parse_variables(&["id=1".to_string(), "name=john".to_string()]).unwrap();
+        let body = payload(document, variables, None).unwrap();
+        assert_eq!(body["operationName"], "GetUser");
+        assert_eq!(body["variables"], json!({ "id": 1, "name": "john" }));
+
+        let document = "query A { a }\nmutation B { b }";
+        assert!(payload(document, Map::new(), None).is_err());
+        assert_eq!(payload(document, Map::new(), Some("B")).unwrap()["operationName"], "B");
+        assert!(payload(document, Map::new(), Some("C")).is_err());
+
+        let anonymous = payload("{ me { id } }", Map::new(), None).unwrap();
+        assert!(anonymous.get("operationName").is_none());
+        assert!(anonymous.get("variables").is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let response = json!({
+            "data": null,
+            "errors": [
+                { "message": "Not found", "path": ["user", 0, "name"], "locations": [{ "line": 2, "column": 3 }], "extensions": { "code": "NOT_FOUND" } },
+                { "message": "Forbidden" },
+            ]
+        });
+        assert_eq!(errors(&response), vec![
+            "Not found at user.0.name (line 2, column 3) [NOT_FOUND]".to_string(),
+            "Forbidden".to_string(),
+        ]);
+        assert!(errors(&json!({ "data": {}, "errors": [] })).is_empty());
+    }
+
+    #[test]
+    fn test_schema_to_sdl() {
+        let named = |kind: &str, name: &str| json!({ "kind": kind, "name": name, "ofType": null });
+        let non_null = |inner: Value| json!({ "kind": "NON_NULL", "name": null, "ofType": inner });
+        let response = json!({ "data": { "__schema": {
+            "queryType": { "name": "Query" },
+            "mutationType": null,
+            "subscriptionType": null,
+            "directives": [{ "name": "include", "locations": ["FIELD"], "args": [] }],
+            "types": [
+                { "kind": "OBJECT", "name": "Query", "description": "Entry points", "interfaces": [], "fields": [
+                    { "name": "user", "args": [{ "name": "id", "type": non_null(named("SCALAR", "ID")), "defaultValue": null }],
+                      "type": named("OBJECT", "User"), "isDeprecated": false },
+                    { "name": "users", "args": [{ "name": "first", "type": named("SCALAR", "Int"), "defaultValue": "10" }],
+                      "type": json!({ "kind": "LIST", "name": null, "ofType": non_null(named("OBJECT", "User")) }), "isDeprecated": false },
+                ]},
+                { "kind": "OBJECT", "name": "User", "interfaces": [named("INTERFACE", "Node")], "fields": [
+                    { "name": "id", "args": [], "type": non_null(named("SCALAR", "ID")), "isDeprecated": false },
+                    { "name": "login", "args": [], "type": named("SCALAR", "String"), "isDeprecated": true, "deprecationReason": "Use email" },
+                ]},
+                { "kind": "INTERFACE", "name": "Node", "interfaces": [], "fields": [
+                    { "name": "id", "args": [], "type": non_null(named("SCALAR", "ID")), "isDeprecated": false },
+                ]},
+                { "kind": "ENUM", "name": "Role", "enumValues": [{ "name": "ADMIN", "isDeprecated": false }, { "name": "USER", "isDeprecated": false }] },
+                { "kind": "INPUT_OBJECT", "name": "UserInput", "inputFields": [{ "name": "email", "type": non_null(named("SCALAR", "String")), "defaultValue": null }] },
+                { "kind": "UNION", "name": "SearchResult", "possibleTypes": [named("OBJECT", "User")] },
+                { "kind": "SCALAR", "name": "DateTime" },
+                { "kind": "SCALAR", "name": "String" },
+                { "kind": "OBJECT", "name": "__Type", "fields": [] },
+            ],
+        }}});
+
+        assert_eq!(schema_to_sdl(&response).unwrap(), concat!(
+            "\"Entry points\"\n",
+            "type Query {\n",
+            "  user(id: ID!): User\n",
+            "  users(first: Int = 10): [User!]\n",
+            "}\n\n",
+            "type User implements Node {\n",
+            "  id: ID!\n",
+            "  login: String @deprecated(reason: \"Use email\")\n",
+            "}\n\n",
+            "interface Node {\n",
+            "  id: ID!\n",
+            "}\n\n",
+            "enum Role {\n",
+            "  ADMIN\n",
+            "  USER\n",
+            "}\n\n",
+            "input UserInput {\n",
+            "  email: String!\n",
+            "}\n\n",
+            "union SearchResult = User\n\n",
+            "scalar DateTime\n",
+        ));
+    }
+
+    #[test]
+    fn test_schema_block_for_unconventional_roots() {
+        let response = json!({ "data": { "__schema": {
+            "queryType": { "name": "RootQuery" },
+            "mutationType": null,
+            "subscriptionType": null,
+            "directives": [],
+            "types": [],
+        }}});
+        assert_eq!(schema_to_sdl(&response).unwrap(), "schema {\n  query: RootQuery\n}\n");
+        assert!(schema_to_sdl(&json!({ "errors": [] })).is_err());
+    }
+}
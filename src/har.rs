@@ -0,0 +1,344 @@
+use crate::builtins;
+use crate::config::Secret;
+use crate::error::{AsteriskError, Result};
+use crate::export;
+use crate::http::HttpResponse;
+use crate::render::{self, BodyKind};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use url::form_urlencoded;
+
+/// Exchanges recorded for `--har`, shared by the clients of one invocation
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    entries: Arc<Mutex<Vec<Value>>>,
+    /// Credentials masked in recorded requests; `None` keeps them (`--har-keep-secrets`)
+    secrets: Option<Arc<Mutex<Vec<Secret>>>>,
+}
+
+impl Recorder {
+    /// Creates a recorder that masks credentials unless `keep_secrets` is set
+    pub fn new(keep_secrets: bool) -> Self {
+        Self {
+            entries: Arc::default(),
+            secrets: (!keep_secrets).then(Arc::default),
+        }
+    }
+
+    /// Adds resolved credentials to mask in the exchanges recorded from now on
+    /// Credential headers are masked even without a known value.
+    pub fn mask(&self, secrets: &[Secret]) {
+        if let Some(Ok(mut masked)) = self.secrets.as_ref().map(|masked| masked.lock()) {
+            for secret in secrets {
+                if !masked.contains(secret) {
+                    masked.push(secret.clone());
+                }
+            }
+        }
+    }
+
+    /// Adds an exchange to the recording
+    pub fn record(&self, response: &HttpResponse) {
+        let entry = match self.secrets {
+            Some(ref secrets) => entry(response, Some(&secrets.lock().map(|secrets| secrets.clone()).unwrap_or_default())),
+            None => entry(response, None),
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.push(entry);
+        }
+    }
+
+    /// Writes the recorded exchanges to a HAR file and returns the number of entries
+    pub fn write(&self, path: &Path) -> Result<usize> {
+        let entries = self.entries.lock().map(|mut entries| std::mem::take(&mut *entries)).unwrap_or_default();
+        let count = entries.len();
+        let content = serde_json::to_string_pretty(&document(entries))
+            .map_err(|e| AsteriskError::Config(format!("Failed to serialize HAR: {}", e)))?;
+        fs::write(path, content)?;
+        Ok(count)
+    }
+}
+
+/// Wraps entries in a HAR 1.2 log
+pub fn document(entries: Vec<Value>) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "asterisk", "version": env!("CARGO_PKG_VERSION") },
+            "entries": entries,
+        }
+    })
+}
+
+/// Converts an exchange into a HAR entry
+/// With `masked`, credentials in the request and cookies in the response are replaced by
+/// `$VAR` references as in `--mask-secrets` exports.
+pub fn entry(response: &HttpResponse, masked: Option<&[Secret]>) -> Value {
+    let request = &response.request;
+    let mask = |text: &str| match masked {
+        Some(secrets) => export::mask_text(text, secrets),
+        None => text.to_string(),
+    };
+    let query: Vec<Value> = Url::parse(&request.url)
+        .map(|url| url.query_pairs().map(|(name, value)| json!({ "name": name, "value": mask(&value) })).collect())
+        .unwrap_or_default();
+
+    // Values in the URL are percent-encoded
+    let url = match masked {
+        Some(secrets) => {
+            let encoded: Vec<Secret> = secrets.iter()
+                .map(|secret| Secret {
+                    value: form_urlencoded::byte_serialize(secret.value.as_bytes()).collect(),
+                    variable: secret.variable.clone(),
+                })
+                .chain(secrets.iter().cloned())
+                .collect();
+            export::mask_text(&request.url, &encoded)
+        }
+        None => request.url.clone(),
+    };
+
+    let mut har_request = json!({
+        "method": request.method,
+        "url": url,
+        "httpVersion": response.version,
+        "cookies": [],
+        "headers": headers(&request.headers, masked),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": request.encoded_size.or(request.body.as_ref().map(String::len)).unwrap_or(0),
+    });
+    if let Some(ref body) = request.body {
+        har_request["postData"] = json!({
+            "mimeType": header_value(&request.headers, CONTENT_TYPE.as_str()),
+            "text": mask(body),
+        });
+        // HAR has no encoding for postData, so a compressed body is kept readable and marked
+        if request.encoded_size.is_some() {
+            har_request["postData"]["comment"] = json!(format!(
+                "sent with Content-Encoding: {}; text is the body before encoding",
+                header_value(&request.headers, CONTENT_ENCODING.as_str())
+            ));
+        }
+    }
+
+    json!({
+        "startedDateTime": iso8601(request.started),
+        "time": response.phases.wait + response.phases.receive,
+        "request": har_request,
+        "response": {
+            "status": response.status,
+            "statusText": response.status_text,
+            "httpVersion": response.version,
+            "cookies": [],
+            "headers": headers(&response.headers, masked),
+            "content": content(response),
+            "redirectURL": header_value(&response.headers, "location"),
+            "headersSize": -1,
+            "bodySize": response.encoded_size.unwrap_or(response.bytes.len().max(response.body.len())),
+        },
+        "cache": {},
+        "timings": {
+            "blocked": -1,
+            "dns": -1,
+            "connect": -1,
+            "ssl": -1,
+            "send": 0,
+            "wait": response.phases.wait,
+            "receive": response.phases.receive,
+        },
+    })
+}
+
+/// Response content; binary bodies are stored base64-encoded
+fn content(response: &HttpResponse) -> Value {
+    let mime_type = header_value(&response.headers, CONTENT_TYPE.as_str());
+    if render::body_kind(Some(&mime_type), &response.bytes) == BodyKind::Binary {
+        json!({
+            "size": response.bytes.len(),
+            "mimeType": mime_type,
+            "text": BASE64.encode(&response.bytes),
+            "encoding": "base64",
+        })
+    } else {
+        let mut content = json!({
+            "size": response.body.len(),
+            "mimeType": mime_type,
+            "text": response.body,
+        });
+        if let Some(encoded) = response.encoded_size {
+            content["compression"] = json!(response.bytes.len() as i64 - encoded as i64);
+        }
+        content
+    }
+}
+
+fn headers(headers: &HeaderMap, masked: Option<&[Secret]>) -> Vec<Value> {
+    headers.iter()
+        .map(|(name, value)| json!({
+            "name": name.as_str(),
+            "value": match masked {
+                Some(secrets) => export::mask_header(name, value, secrets),
+                None => String::from_utf8_lossy(value.as_bytes()).to_string(),
+            },
+        }))
+        .collect()
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> String {
+    headers.get(name)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+        .unwrap_or_default()
+}
+
+/// Formats a time as an ISO 8601 UTC timestamp with milliseconds
+pub fn iso8601(time: SystemTime) -> String {
+    builtins::format_time(time, "%Y-%m-%dT%H:%M:%S.%3fZ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{SentRequest, TimingPhases};
+    use reqwest::header::{HeaderValue, AUTHORIZATION, COOKIE, SET_COOKIE};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(UNIX_EPOCH + Duration::from_millis(1_709_210_096_789)), "2024-02-29T12:34:56.789Z");
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        let response = HttpResponse {
+            status: 201,
+            status_text: "Created".to_string(),
+            version: "HTTP/1.1".to_string(),
+            headers: response_headers,
+            body: r#"{"id":1}"#.to_string(),
+            phases: TimingPhases { wait: 12.5, receive: 0.5 },
+            request: SentRequest {
+                method: "POST".to_string(),
+                url: "http://localhost:3000/users?notify=false".to_string(),
+                headers: request_headers,
+                body: Some(r#"{"name":"john"}"#.to_string()),
+                started: UNIX_EPOCH,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let entry = entry(&response, None);
+        assert_eq!(entry["time"], 13.0);
+        assert_eq!(entry["request"]["queryString"], json!([{ "name": "notify", "value": "false" }]));
+        assert_eq!(entry["request"]["postData"]["mimeType"], "application/json");
+        assert_eq!(entry["request"]["headers"], json!([{ "name": "content-type", "value": "application/json" }]));
+        assert_eq!(entry["response"]["content"]["text"], r#"{"id":1}"#);
+        assert_eq!(entry["timings"]["wait"], 12.5);
+    }
+
+    #[test]
+    fn test_recorder() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session.har");
+        let recorder = Recorder::new(false);
+        recorder.clone().record(&HttpResponse { status: 200, ..Default::default() });
+        recorder.record(&HttpResponse { status: 404, ..Default::default() });
+
+        assert_eq!(recorder.write(&path).unwrap(), 2);
+        let archive: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(archive["log"]["entries"][1]["response"]["status"], 404);
+    }
+
+    #[test]
+    fn test_masked_entry() {
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer t0ken"));
+        request_headers.insert(COOKIE, HeaderValue::from_static("session=abc"));
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(SET_COOKIE, HeaderValue::from_static("session=def"));
+        let response = HttpResponse {
+            headers: response_headers,
+            request: SentRequest {
+                url: "http://localhost:3000/users?api_key=k%2By".to_string(),
+                headers: request_headers,
+                body: Some(r#"{"token":"t0ken"}"#.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let recorder = Recorder::new(false);
+        recorder.mask(&[
+            Secret { value: "t0ken".to_string(), variable: "API_TOKEN".to_string() },
+            Secret { value: "k+y".to_string(), variable: "API_KEY".to_string() },
+        ]);
+        recorder.record(&response);
+        let entry = recorder.entries.lock().unwrap()[0].clone();
+        let text = entry.to_string();
+        for secret in ["t0ken", "k+y", "k%2By", "abc", "def"] {
+            assert!(!text.contains(secret), "{} in {}", secret, text);
+        }
+        assert_eq!(entry["request"]["url"], "http://localhost:3000/users?api_key=$API_KEY");
+        assert_eq!(entry["request"]["headers"][0]["value"], "Bearer $API_TOKEN");
+        assert_eq!(entry["request"]["headers"][1]["value"], "$COOKIE");
+        assert_eq!(entry["request"]["queryString"][0]["value"], "$API_KEY");
+        assert_eq!(entry["response"]["headers"][0]["value"], "$SET_COOKIE");
+
+        let kept = Recorder::new(true);
+        kept.mask(&[Secret { value: "t0ken".to_string(), variable: "API_TOKEN".to_string() }]);
+        kept.record(&response);
+        assert!(kept.entries.lock().unwrap()[0].to_string().contains("Bearer t0ken"));
+    }
+
+    #[test]
+    fn test_compressed_request() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        let response = HttpResponse {
+            request: SentRequest {
+                headers,
+                body: Some("x".repeat(100)),
+                encoded_size: Some(24),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let request = &entry(&response, None)["request"];
+        assert_eq!(request["bodySize"], 24);
+        assert_eq!(request["postData"]["text"], "x".repeat(100));
+        assert!(request["postData"]["comment"].as_str().unwrap().contains("Content-Encoding: gzip"));
+    }
+
+    #[test]
+    fn test_binary_content() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+        let response = HttpResponse {
+            status: 200,
+            headers,
+            bytes: b"\x89PNG".to_vec(),
+            body: String::from_utf8_lossy(b"\x89PNG").to_string(),
+            ..Default::default()
+        };
+
+        let content = &entry(&response, None)["response"]["content"];
+        assert_eq!(content["encoding"], "base64");
+        assert_eq!(content["text"], "iVBORw==");
+        assert_eq!(content["size"], 4);
+    }
+}
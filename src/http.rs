@@ -1,7 +1,19 @@
 use crate::error::{Result, AsteriskError};
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::{Client, Method};
-use std::time::Instant;
+use crate::compression;
+use crate::config::Secret;
+use crate::har;
+use crate::render;
+use colored::*;
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, RANGE};
+use reqwest::{Client, Method, Request, Response, StatusCode, Version};
+use std::fs::{self, File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Downloads with at least this many bytes left show a progress bar
+const PROGRESS_THRESHOLD: u64 = 1024 * 1024;
 
 #[derive(Debug)]
 pub struct RequestTiming {
@@ -40,49 +52,261 @@ impl RequestTiming {
             None => format!("Total: {}ms", total.as_millis()),
         }
     }
+
+    /// Time to first byte and body download time
+    pub fn phases(&self) -> TimingPhases {
+        let end = self.end.unwrap_or_else(Instant::now);
+        let first_byte = self.first_byte.unwrap_or(end);
+        let millis = |from: Instant, to: Instant| (to.duration_since(from).as_micros() as f64) / 1000.0;
+        TimingPhases {
+            wait: millis(self.start, first_byte),
+            receive: millis(first_byte, end),
+        }
+    }
+}
+
+/// Timing phases of an exchange in milliseconds
+#[derive(Debug, Clone, Default)]
+pub struct TimingPhases {
+    /// From sending the request to the first response byte
+    pub wait: f64,
+
+    /// Reading the response body
+    pub receive: f64,
+}
+
+/// Request as it was sent, including headers added by the client
+#[derive(Debug, Clone)]
+pub struct SentRequest {
+    /// HTTP method
+    pub method: String,
+
+    /// Request URL
+    pub url: String,
+
+    /// Request headers
+    pub headers: HeaderMap,
+
+    /// Request body, before any Content-Encoding
+    pub body: Option<String>,
+
+    /// Size of the body on the wire when it was sent compressed (`--compress-body`)
+    pub encoded_size: Option<usize>,
+
+    /// Wall clock time the request was started
+    pub started: SystemTime,
+}
+
+impl Default for SentRequest {
+    fn default() -> Self {
+        Self {
+            method: String::new(),
+            url: String::new(),
+            headers: HeaderMap::new(),
+            body: None,
+            encoded_size: None,
+            started: SystemTime::UNIX_EPOCH,
+        }
+    }
+}
+
+/// Response returned by `HttpClient::send_request`
+#[derive(Debug, Default)]
+pub struct HttpResponse {
+    /// HTTP status code
+    pub status: u16,
+
+    /// Canonical reason phrase for the status
+    pub status_text: String,
+
+    /// Protocol version, e.g. `HTTP/1.1`
+    pub version: String,
+
+    /// Response headers
+    pub headers: HeaderMap,
+
+    /// Response body decoded as text
+    pub body: String,
+
+    /// Raw response body, after undoing any Content-Encoding
+    pub bytes: Vec<u8>,
+
+    /// Size of the body on the wire when it was decoded from a Content-Encoding
+    pub encoded_size: Option<usize>,
+
+    /// Formatted timing information
+    pub timing: String,
+
+    /// Timing phases for HAR export
+    pub phases: TimingPhases,
+
+    /// The request that produced this response
+    pub request: SentRequest,
+
+    /// File the body was saved to with `-o`; `body` and `bytes` are then empty
+    pub saved_to: Option<PathBuf>,
+}
+
+impl HttpResponse {
+    /// Builds the response of a finished exchange from its body
+    fn new(
+        status: StatusCode,
+        version: Version,
+        headers: HeaderMap,
+        bytes: Vec<u8>,
+        encoded_size: Option<usize>,
+        timing: &RequestTiming,
+        request: SentRequest,
+    ) -> Self {
+        Self {
+            status: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or("").to_string(),
+            version: format!("{:?}", version),
+            body: decode_body(&bytes, &headers),
+            headers,
+            bytes,
+            encoded_size,
+            timing: timing.format(),
+            phases: timing.phases(),
+            request,
+            saved_to: None,
+        }
+    }
+
+    /// Media type from the Content-Type header, without parameters
+    pub fn content_type(&self) -> Option<String> {
+        self.headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|media_type| media_type.trim().to_lowercase())
+    }
+
+    /// Whether the body is still compressed (`--raw-encoding`)
+    pub fn is_encoded(&self) -> bool {
+        self.encoded_size.is_none()
+            && !self.bytes.is_empty()
+            && self.headers
+                .get(CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|coding| !coding.trim().eq_ignore_ascii_case("identity"))
+    }
+}
+
+/// Decodes a body using the Content-Type charset, defaulting to UTF-8
+fn decode_body(bytes: &[u8], headers: &HeaderMap) -> String {
+    let encoding = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').find_map(|param| param.trim().strip_prefix("charset=")))
+        .and_then(|charset| Encoding::for_label(charset.trim_matches('"').as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Response body saved to a file with `-o`
+#[derive(Debug)]
+pub struct Download {
+    /// File the body was written to
+    pub path: PathBuf,
+
+    /// Bytes written by this request
+    pub written: u64,
+
+    /// Size of the partial file a resumed download continued from
+    pub resumed_from: u64,
+}
+
+/// Response of `HttpClient::open`, read chunk by chunk
+/// The body received so far is added to the HAR recording when the response is dropped,
+/// however the stream ended.
+pub struct StreamedResponse {
+    response: Response,
+    received: Vec<u8>,
+    timing: RequestTiming,
+    request: SentRequest,
+    recorder: Option<har::Recorder>,
+}
+
+impl StreamedResponse {
+    pub fn status(&self) -> StatusCode {
+        self.response.status()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.response.headers()
+    }
+
+    /// Next chunk of the body; `None` once the server has closed it
+    pub async fn chunk(&mut self) -> reqwest::Result<Option<Vec<u8>>> {
+        let chunk = self.response.chunk().await?.map(|chunk| chunk.to_vec());
+        if let (Some(ref chunk), Some(_)) = (&chunk, &self.recorder) {
+            self.received.extend_from_slice(chunk);
+        }
+        Ok(chunk)
+    }
+
+    /// The rest of the body as text
+    pub async fn text(&mut self) -> reqwest::Result<String> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = self.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(decode_body(&bytes, self.headers()))
+    }
+}
+
+impl Drop for StreamedResponse {
+    fn drop(&mut self) {
+        let Some(ref recorder) = self.recorder else {
+            return;
+        };
+        self.timing.set_end();
+        recorder.record(&HttpResponse::new(
+            self.response.status(),
+            self.response.version(),
+            self.response.headers().clone(),
+            std::mem::take(&mut self.received),
+            None,
+            &self.timing,
+            std::mem::take(&mut self.request),
+        ));
+    }
 }
 
 pub struct HttpClient {
     client: Client,
+    /// Recording every exchange is added to for `--har`
+    recorder: Option<har::Recorder>,
 }
 
 impl HttpClient {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            recorder: None,
         }
     }
 
-    pub fn parse_headers(headers_str: &str, token: Option<&str>) -> Result<HeaderMap> {
-        let mut headers = HeaderMap::new();
-        
-        // Add Bearer token if provided
-        if let Some(token) = token {
-            headers.insert(
-                HeaderName::from_static("authorization"),
-                HeaderValue::from_str(&format!("Bearer {}", token))
-                    .map_err(|_| AsteriskError::InvalidHeaders)?,
-            );
-        }
-
-        // Parse custom headers
-        if !headers_str.is_empty() {
-            for header in headers_str.split(',') {
-                let parts: Vec<&str> = header.split(':').collect();
-                if parts.len() != 2 {
-                    return Err(AsteriskError::InvalidHeaders);
-                }
-
-                let name = HeaderName::from_bytes(parts[0].trim().as_bytes())
-                    .map_err(|_| AsteriskError::InvalidHeaders)?;
-                let value = HeaderValue::from_str(parts[1].trim())
-                    .map_err(|_| AsteriskError::InvalidHeaders)?;
+    /// Adds every exchange sent by this client to `recorder`
+    pub fn with_recorder(mut self, recorder: Option<har::Recorder>) -> Self {
+        self.recorder = recorder;
+        self
+    }
 
-                headers.insert(name, value);
-            }
+    /// Masks these resolved credentials in the HAR recording
+    pub fn mask_secrets(&self, secrets: &[Secret]) {
+        if let Some(ref recorder) = self.recorder {
+            recorder.mask(secrets);
         }
+    }
 
-        Ok(headers)
+    /// Creates a client that skips TLS certificate verification, recording to the same HAR
+    pub fn insecure(&self) -> Result<Self> {
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()?;
+        Ok(Self { client, recorder: self.recorder.clone() })
     }
 
     pub async fn send_request(
@@ -91,9 +315,148 @@ impl HttpClient {
         method: &str,
         headers: HeaderMap,
         body: Option<String>,
-    ) -> Result<(u16, String, String)> {
+    ) -> Result<HttpResponse> {
         let mut timing = RequestTiming::new();
-        
+        let (request, sent) = self.build_request(url, method, headers, body)?;
+
+        let response = self.client.execute(request).await?;
+        timing.set_first_byte();
+
+        let (status, version, headers) = (response.status(), response.version(), response.headers().clone());
+        let bytes = response.bytes().await?.to_vec();
+        timing.set_end();
+
+        self.complete(status, version, headers, bytes, &timing, sent)
+    }
+
+    /// Sends a request and streams a successful body into `path`
+    /// With `resume`, an existing file is continued with a Range request; servers that
+    /// ignore the range get the file rewritten. Error responses are returned without
+    /// touching the file and with `None` as the download.
+    pub async fn download(
+        &self,
+        url: &str,
+        method: &str,
+        mut headers: HeaderMap,
+        body: Option<String>,
+        path: &Path,
+        resume: bool,
+    ) -> Result<(HttpResponse, Option<Download>)> {
+        let existing = if resume { fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0) } else { 0 };
+        if existing > 0 {
+            let range = HeaderValue::from_str(&format!("bytes={}-", existing)).map_err(|_| AsteriskError::InvalidHeaders)?;
+            headers.insert(RANGE, range);
+        }
+
+        // Saved files hold the body as served so resumed ranges line up
+        if !compression::options().raw && !headers.contains_key(ACCEPT_ENCODING) {
+            headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+        }
+
+        let mut timing = RequestTiming::new();
+        let (request, sent) = self.build_request(url, method, headers, body)?;
+
+        let mut response = self.client.execute(request).await?;
+        timing.set_first_byte();
+        let (status, version, response_headers) = (response.status(), response.version(), response.headers().clone());
+
+        // Nothing after the end of the file: the previous download finished
+        if status == StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+            timing.set_end();
+            let download = Download { path: path.to_path_buf(), written: 0, resumed_from: existing };
+            let mut response = self.complete(status, version, response_headers, Vec::new(), &timing, sent)?;
+            response.saved_to = Some(path.to_path_buf());
+            return Ok((response, Some(download)));
+        }
+
+        if !status.is_success() {
+            let bytes = response.bytes().await?.to_vec();
+            timing.set_end();
+            return Ok((self.complete(status, version, response_headers, bytes, &timing, sent)?, None));
+        }
+
+        let resumed_from = if status == StatusCode::PARTIAL_CONTENT { existing } else { 0 };
+        let mut file = if resumed_from > 0 {
+            OpenOptions::new().append(true).open(path)?
+        } else {
+            File::create(path)?
+        };
+
+        let mut progress = Progress::new(response.content_length().map(|length| length + resumed_from), resumed_from);
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk)?;
+            written += chunk.len() as u64;
+            progress.update(resumed_from + written);
+        }
+        progress.finish();
+        timing.set_end();
+
+        let download = Download { path: path.to_path_buf(), written, resumed_from };
+        let mut response = self.complete(status, version, response_headers, Vec::new(), &timing, sent)?;
+        response.saved_to = Some(path.to_path_buf());
+        Ok((response, Some(download)))
+    }
+
+    /// Sends a request and returns the response with its body unread, for `--stream`
+    /// Streams ask for `identity` since compressed chunks can't be shown as they arrive.
+    pub async fn open(&self, url: &str, method: &str, mut headers: HeaderMap, body: Option<String>) -> Result<StreamedResponse> {
+        if !headers.contains_key(ACCEPT_ENCODING) {
+            headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+        }
+        let mut timing = RequestTiming::new();
+        let (request, sent) = self.build_request(url, method, headers, body)?;
+        let response = self.client.execute(request).await?;
+        timing.set_first_byte();
+        Ok(StreamedResponse { response, received: Vec::new(), timing, request: sent, recorder: self.recorder.clone() })
+    }
+
+    /// Assembles the response of a finished exchange and adds it to the HAR recording
+    /// The body is decoded from its Content-Encoding unless `--raw-encoding` is set; a body
+    /// that fails to decode is kept as received, with a warning.
+    fn complete(
+        &self,
+        status: StatusCode,
+        version: Version,
+        headers: HeaderMap,
+        wire: Vec<u8>,
+        timing: &RequestTiming,
+        request: SentRequest,
+    ) -> Result<HttpResponse> {
+        let content_encoding = headers
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .filter(|_| !compression::options().raw && !wire.is_empty());
+        let (bytes, encoded_size) = match content_encoding.map(|coding| compression::decode(&wire, coding)) {
+            Some(Ok(bytes)) => (bytes, Some(wire.len())),
+            Some(Err(e)) => {
+                eprintln!("{} {}; showing the body as received", "Warning:".bold().yellow(), e);
+                (wire, None)
+            }
+            None => (wire, None),
+        };
+
+        let response = HttpResponse::new(status, version, headers, bytes, encoded_size, timing, request);
+        if let Some(ref recorder) = self.recorder {
+            recorder.record(&response);
+        }
+        Ok(response)
+    }
+
+    /// Builds the request, defaulting bodies to JSON, and records it as sent
+    /// Compression is advertised unless the caller set `Accept-Encoding`.
+    fn build_request(
+        &self,
+        url: &str,
+        method: &str,
+        mut headers: HeaderMap,
+        body: Option<String>,
+    ) -> Result<(Request, SentRequest)> {
+        let request_has_content_type = headers.contains_key(CONTENT_TYPE);
+        if !headers.contains_key(ACCEPT_ENCODING) {
+            headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(compression::ACCEPT_ENCODING));
+        }
+
         // Parse HTTP method
         let method = match method.to_uppercase().as_str() {
             "GET" => Method::GET,
@@ -101,34 +464,94 @@ impl HttpClient {
             "PUT" => Method::PUT,
             "DELETE" => Method::DELETE,
             "PATCH" => Method::PATCH,
+            "HEAD" => Method::HEAD,
+            "OPTIONS" => Method::OPTIONS,
             _ => return Err(AsteriskError::InvalidHeaders),
         };
 
         let mut request = self.client.request(method, url);
         request = request.headers(headers);
 
-        // Add body for POST/PUT/PATCH
-        if let Some(body) = body {
-            request = request
-                .header(HeaderName::from_static("content-type"), "application/json")
-                .body(body);
+        // Default to JSON bodies unless the caller set a content type
+        let mut encoded_size = None;
+        if let Some(ref body) = body {
+            if !request_has_content_type {
+                request = request.header(CONTENT_TYPE, "application/json");
+            }
+            match compression::options().compress_body {
+                Some(coding) => {
+                    let encoded = compression::compress(body.as_bytes(), coding)?;
+                    encoded_size = Some(encoded.len());
+                    request = request.header(CONTENT_ENCODING, coding.token()).body(encoded);
+                }
+                None => request = request.body(body.clone()),
+            }
         }
 
-        let response = request
-            .send()
-            .await?;
+        let request = request.build()?;
+        let sent = SentRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: request.headers().clone(),
+            body,
+            encoded_size,
+            started: SystemTime::now(),
+        };
+        Ok((request, sent))
+    }
+}
 
-        timing.set_first_byte();
-        
-        let status = response.status();
-        let body = response.text().await?;
-        
-        timing.set_end();
+/// Progress of a large download, drawn on stderr when it is a terminal
+struct Progress {
+    total: Option<u64>,
+    started: Instant,
+    offset: u64,
+    visible: bool,
+    last_draw: Option<Instant>,
+}
 
-        Ok((
-            status.as_u16(),
-            body,
-            timing.format(),
-        ))
+impl Progress {
+    fn new(total: Option<u64>, offset: u64) -> Self {
+        let large = total.is_none_or(|total| total.saturating_sub(offset) >= PROGRESS_THRESHOLD);
+        Self {
+            total,
+            started: Instant::now(),
+            offset,
+            visible: large && std::io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    fn update(&mut self, received: u64) {
+        if !self.visible || self.last_draw.is_some_and(|last| last.elapsed() < Duration::from_millis(100)) {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+
+        let seconds = self.started.elapsed().as_secs_f64().max(0.001);
+        let rate = format!("{}/s", render::format_size(((received - self.offset) as f64 / seconds) as u64));
+        let line = match self.total {
+            Some(total) if total > 0 => {
+                let fraction = (received as f64 / total as f64).min(1.0);
+                let filled = (fraction * 30.0) as usize;
+                format!(
+                    "{:>3}% [{}{}] {} / {}  {}",
+                    (fraction * 100.0) as u64,
+                    "#".repeat(filled),
+                    "-".repeat(30 - filled),
+                    render::format_size(received),
+                    render::format_size(total),
+                    rate,
+                )
+            }
+            _ => format!("{}  {}", render::format_size(received), rate),
+        };
+        eprint!("\r{:<72}", line);
+    }
+
+    fn finish(&mut self) {
+        if self.visible && self.last_draw.is_some() {
+            eprintln!();
+        }
     }
 }
@@ -0,0 +1,607 @@
+use crate::builtins;
+use crate::config::{ConfigManager, ResolvedConfig};
+use crate::error::{AsteriskError, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// HTTP methods recognised at the start of a request line
+const METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+
+/// JetBrains HTTP Client environment files, keyed by environment (profile) name
+const ENV_FILES: &[&str] = &["http-client.env.json", "http-client.private.env.json"];
+
+/// Maximum nesting depth when interpolating variables that reference other variables
+const MAX_INTERPOLATION_DEPTH: usize = 10;
+
+/// Parsed .http / .rest file
+#[derive(Debug, Clone)]
+pub struct HttpFile {
+    /// File-level `@name = value` declarations in declaration order
+    pub variables: Vec<(String, String)>,
+
+    /// Requests in file order
+    pub requests: Vec<HttpFileRequest>,
+
+    /// Directory of the file, used for `< ./body.json` references and env files
+    pub base_dir: PathBuf,
+}
+
+/// A single request block from a .http file
+#[derive(Debug, Clone, Default)]
+pub struct HttpFileRequest {
+    /// Name from a `# @name` directive
+    pub name: Option<String>,
+
+    /// Text following the `###` separator
+    pub title: Option<String>,
+
+    /// First line of the block (1-based)
+    pub start_line: usize,
+
+    /// Last line of the block (1-based)
+    pub end_line: usize,
+
+    /// HTTP method (defaults to GET)
+    pub method: String,
+
+    /// Request URL, absolute or relative to the profile URL
+    pub url: String,
+
+    /// Headers in file order
+    pub headers: Vec<(String, String)>,
+
+    /// Request body lines, including `< file` references
+    pub body: Option<String>,
+
+    /// Response handler scripts (`> {% ... %}` or `> handler.js`)
+    pub handlers: Vec<String>,
+
+    /// Number of file variables declared above the request line
+    pub declared_variables: usize,
+}
+
+impl HttpFileRequest {
+    /// Label used when listing or reporting the request
+    pub fn label(&self) -> String {
+        match (&self.name, &self.title) {
+            (Some(name), _) => name.clone(),
+            (None, Some(title)) if !title.is_empty() => title.clone(),
+            _ => format!("line {}", self.start_line),
+        }
+    }
+}
+
+impl HttpFile {
+    /// Reads and parses a .http file from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+            format!("Failed to read request file {}: {}", path.display(), e)
+        ))?;
+
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::parse(&content, base_dir)
+    }
+
+    /// Parses .http file content
+    pub fn parse(content: &str, base_dir: PathBuf) -> Result<Self> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut variables = Vec::new();
+        let mut requests = Vec::new();
+
+        // Split into blocks at `###` separators
+        let mut blocks: Vec<(usize, usize, Option<String>)> = Vec::new();
+        let mut block_start = 0;
+        let mut block_title = None;
+        for (index, line) in lines.iter().enumerate() {
+            if let Some(title) = line.trim_start().strip_prefix("###") {
+                if index > block_start || block_title.is_some() {
+                    blocks.push((block_start, index, block_title.take()));
+                }
+                block_start = index + 1;
+                block_title = Some(title.trim().to_string());
+            }
+        }
+        blocks.push((block_start, lines.len(), block_title));
+
+        for (start, end, title) in blocks {
+            if let Some(mut request) = parse_block(&lines[start..end], start, &mut variables)? {
+                request.title = title;
+                request.start_line = start.max(1);
+                requests.push(request);
+            }
+        }
+
+        Ok(Self {
+            variables,
+            requests,
+            base_dir,
+        })
+    }
+
+    /// Selects requests by `# @name` or by a line number inside the request block
+    /// Returns every request when neither is given
+    pub fn select(&self, name: Option<&str>, line: Option<usize>) -> Result<Vec<&HttpFileRequest>> {
+        if let Some(name) = name {
+            return self.requests.iter()
+                .find(|r| r.name.as_deref() == Some(name))
+                .map(|r| vec![r])
+                .ok_or_else(|| AsteriskError::Config(format!("No request named '{}' in file", name)));
+        }
+
+        if let Some(line) = line {
+            return self.requests.iter()
+                .find(|r| line >= r.start_line && line <= r.end_line)
+                .map(|r| vec![r])
+                .ok_or_else(|| AsteriskError::Config(format!("No request found at line {}", line)));
+        }
+
+        Ok(self.requests.iter().collect())
+    }
+
+    /// Loads JetBrains environment variables for the given profile name
+    fn environment_variables(&self, profile: Option<&str>) -> Result<HashMap<String, String>> {
+        let mut variables = HashMap::new();
+        let profile = match profile {
+            Some(profile) => profile,
+            None => return Ok(variables),
+        };
+
+        for env_file in ENV_FILES {
+            let path = self.base_dir.join(env_file);
+            if !path.exists() {
+                continue;
+            }
+
+            let content = fs::read_to_string(&path)?;
+            let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| AsteriskError::Config(
+                format!("Failed to parse {}: {}", path.display(), e)
+            ))?;
+
+            if let Some(env) = json.get(profile).and_then(|v| v.as_object()) {
+                for (key, value) in env {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    variables.insert(key.clone(), value);
+                }
+            }
+        }
+
+        Ok(variables)
+    }
+}
+
+/// Parses one `###`-delimited block; returns None for blocks without a request line
+fn parse_block(
+    lines: &[&str],
+    offset: usize,
+    variables: &mut Vec<(String, String)>,
+) -> Result<Option<HttpFileRequest>> {
+    let mut request = HttpFileRequest::default();
+    let mut index = 0;
+
+    // Preamble: comments, directives and variable declarations
+    while index < lines.len() {
+        let line = lines[index].trim();
+        if line.is_empty() {
+            index += 1;
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#').or_else(|| line.strip_prefix("//")) {
+            if let Some(name) = comment.trim().strip_prefix("@name") {
+                let name = name.trim().trim_start_matches('=').trim();
+                if !name.is_empty() {
+                    request.name = Some(name.to_string());
+                }
+            }
+            index += 1;
+            continue;
+        }
+
+        if let Some(declaration) = line.strip_prefix('@') {
+            let (name, value) = declaration.split_once('=').ok_or_else(|| AsteriskError::Config(
+                format!("Invalid variable declaration on line {}: '{}'", offset + index + 1, line)
+            ))?;
+            variables.push((name.trim().to_string(), value.trim().to_string()));
+            index += 1;
+            continue;
+        }
+
+        break;
+    }
+
+    if index == lines.len() {
+        return Ok(None);
+    }
+    request.declared_variables = variables.len();
+
+    // Request line: `METHOD URL [HTTP/1.1]` or a bare URL
+    let request_line = lines[index].trim();
+    let mut parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() > 1 && parts[parts.len() - 1].starts_with("HTTP/") {
+        parts.pop();
+    }
+    let (method, url) = match parts.split_first() {
+        Some((first, rest)) if METHODS.contains(&first.to_uppercase().as_str()) && !rest.is_empty() => {
+            (first.to_uppercase(), rest.join(" "))
+        }
+        _ => ("GET".to_string(), parts.join(" ")),
+    };
+    request.method = method;
+    request.url = url;
+    index += 1;
+
+    // Multi-line query strings continue the URL
+    while index < lines.len() {
+        let line = lines[index].trim();
+        if line.starts_with('?') || line.starts_with('&') {
+            request.url.push_str(line);
+            index += 1;
+        } else {
+            break;
+        }
+    }
+
+    // Headers until the first blank line
+    while index < lines.len() {
+        let line = lines[index].trim();
+        index += 1;
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let (name, value) = line.split_once(':').ok_or_else(|| AsteriskError::Config(
+            format!("Invalid header on line {}: '{}'. Expected 'Name: value'", offset + index, line)
+        ))?;
+        request.headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    // Body, then response handlers and response references after it
+    let handlers_start = trailing_section(lines, index);
+    let mut body_lines: Vec<&str> = lines[index..handlers_start].to_vec();
+    index = handlers_start;
+    while index < lines.len() {
+        if let Some(handler) = handler_script(lines[index]) {
+            let mut handler = handler.to_string();
+            if handler.starts_with("{%") {
+                while !handler.ends_with("%}") && index + 1 < lines.len() {
+                    index += 1;
+                    handler.push('\n');
+                    handler.push_str(lines[index]);
+                    handler = handler.trim_end().to_string();
+                }
+            }
+            request.handlers.push(handler);
+        }
+        index += 1;
+    }
+
+    while body_lines.last().is_some_and(|l| l.trim().is_empty()) {
+        body_lines.pop();
+    }
+    if !body_lines.is_empty() {
+        request.body = Some(body_lines.join("\n"));
+    }
+
+    request.end_line = offset + lines.len();
+    Ok(Some(request))
+}
+
+/// Returns the script of a `> {% ... %}` or `> handler.js` line
+fn handler_script(line: &str) -> Option<&str> {
+    let script = line.trim().strip_prefix('>')?.trim();
+    (script.starts_with("{%") || script.ends_with(".js")).then_some(script)
+}
+
+/// Finds where the trailing handlers and `<>` response references start,
+/// so `>` lines inside the body stay part of it
+fn trailing_section(lines: &[&str], body_start: usize) -> usize {
+    let mut start = lines.len();
+    while start > body_start {
+        let line = lines[start - 1].trim();
+        if line.is_empty() || line.starts_with("<>") || handler_script(line).is_some() {
+            start -= 1;
+        } else if line.ends_with("%}") {
+            // Last line of a multi-line `> {% ... %}` script
+            let opening = (body_start..start - 1).rev()
+                .find(|&i| handler_script(lines[i]).is_some_and(|script| script.starts_with("{%")));
+            match opening {
+                Some(opening) => start = opening,
+                None => break,
+            }
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+/// Expands `{{var}}` references for requests in a .http file
+///
+/// Lookup order: file variables declared above the request, JetBrains environment for the active profile,
+/// profile values (`baseUrl`, `url`, `token`), named responses, then environment
+/// variables through the `EnvironmentResolver`.
+pub struct Interpolator<'a> {
+    file: &'a HttpFile,
+    environment: HashMap<String, String>,
+    profile: &'a ResolvedConfig,
+    responses: HashMap<String, serde_json::Value>,
+}
+
+impl<'a> Interpolator<'a> {
+    /// Creates an interpolator for a file and resolved profile
+    pub fn new(file: &'a HttpFile, profile: &'a ResolvedConfig) -> Result<Self> {
+        Ok(Self {
+            file,
+            environment: file.environment_variables(profile.profile.as_deref())?,
+            profile,
+            responses: HashMap::new(),
+        })
+    }
+
+    /// Records the response of a named request for `{{name.response.body.*}}` references
+    pub fn record_response(&mut self, name: &str, body: &str) {
+        let value = serde_json::from_str(body)
+            .unwrap_or_else(|_| serde_json::Value::String(body.to_string()));
+        self.responses.insert(name.to_string(), value);
+    }
+
+    /// Expands every `{{...}}` reference in text belonging to the request
+    pub fn interpolate(&self, text: &str, request: &HttpFileRequest, config_manager: &mut ConfigManager) -> Result<String> {
+        let variables = &self.file.variables[..request.declared_variables];
+        self.interpolate_with_depth(text, variables, config_manager, 0)
+    }
+
+    /// Resolves the request body, reading `< path` file references
+    pub fn body(&self, request: &HttpFileRequest, config_manager: &mut ConfigManager) -> Result<Option<String>> {
+        let body = match request.body {
+            Some(ref body) => body,
+            None => return Ok(None),
+        };
+
+        let mut resolved = Vec::new();
+        for line in body.lines() {
+            match line.trim().strip_prefix('<') {
+                Some(path) if line.trim_start().starts_with("< ") => {
+                    let path = self.file.base_dir.join(self.interpolate(path.trim(), request, config_manager)?);
+                    let content = fs::read_to_string(&path).map_err(|e| AsteriskError::Config(
+                        format!("Failed to read body file {}: {}", path.display(), e)
+                    ))?;
+                    resolved.push(content);
+                }
+                _ => resolved.push(self.interpolate(line, request, config_manager)?),
+            }
+        }
+
+        Ok(Some(resolved.join("\n")))
+    }
+
+    fn interpolate_with_depth(
+        &self,
+        text: &str,
+        variables: &[(String, String)],
+        config_manager: &mut ConfigManager,
+        depth: usize,
+    ) -> Result<String> {
+        if depth > MAX_INTERPOLATION_DEPTH {
+            return Err(AsteriskError::Config(
+                format!("Variable nesting too deep while expanding '{}'", text)
+            ));
+        }
+
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| AsteriskError::Config(
+                format!("Unterminated '{{{{' in '{}'", text)
+            ))?;
+
+            let value = self.lookup(after[..end].trim(), variables, config_manager, depth)?;
+            result.push_str(&value);
+            rest = &after[end + 2..];
+        }
+        result.push_str(rest);
+
+        Ok(result)
+    }
+
+    fn lookup(
+        &self,
+        name: &str,
+        variables: &[(String, String)],
+        config_manager: &mut ConfigManager,
+        depth: usize,
+    ) -> Result<String> {
+        // System variables: {{$processEnv NAME}}, {{$dotenv NAME}}, {{$guid}},
+        // {{$timestamp}} and {{$randomInt min max}} (max exclusive)
+        if let Some(system) = name.strip_prefix('$') {
+            let mut parts = system.split_whitespace();
+            return match (parts.next(), parts.next(), parts.next()) {
+                (Some("processEnv") | Some("dotenv"), Some(var), None) => {
+                    config_manager.resolve_value(&format!("${{{}}}", var.trim_start_matches('%')))
+                }
+                (Some("guid"), None, None) => builtins::evaluate("uuid", None),
+                (Some("timestamp"), None, None) => builtins::evaluate("unix", None),
+                (Some("randomInt"), Some(min), Some(max)) => {
+                    let max: i64 = max.parse().map_err(|_| AsteriskError::Config(
+                        format!("Invalid maximum in '{{{{{}}}}}'", name)
+                    ))?;
+                    builtins::evaluate("random_int", Some(&format!("{}:{}", min, max - 1)))
+                }
+                _ => Err(AsteriskError::Config(format!("Unsupported system variable '{{{{{}}}}}'", name))),
+            };
+        }
+
+        if let Some((_, value)) = variables.iter().rev().find(|(n, _)| n == name) {
+            return self.interpolate_with_depth(value, variables, config_manager, depth + 1);
+        }
+
+        if let Some(value) = self.environment.get(name) {
+            return self.interpolate_with_depth(&value.clone(), variables, config_manager, depth + 1);
+        }
+
+        match name {
+            "baseUrl" | "base_url" | "url" => return Ok(self.profile.url.clone()),
+            "token" => {
+                if let Some(ref token) = self.profile.token {
+                    return Ok(token.clone());
+                }
+            }
+            _ => {}
+        }
+
+        if let Some((request_name, path)) = name.split_once(".response.body") {
+            let response = self.responses.get(request_name).ok_or_else(|| AsteriskError::Config(
+                format!("Request '{}' has not been executed yet", request_name)
+            ))?;
+            return json_path(response, path).ok_or_else(|| AsteriskError::Config(
+                format!("Path '{}' not found in response of '{}'", path, request_name)
+            ));
+        }
+
+        config_manager.resolve_value(&format!("${{{}}}", name))
+    }
+}
+
+/// Evaluates a simple JSONPath (`.*`, `.$.a.b[0]`) against a response body
+fn json_path(value: &serde_json::Value, path: &str) -> Option<String> {
+    let path = path.trim_start_matches('.');
+    if path == "*" || path.is_empty() {
+        return Some(match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+    }
+
+    let mut current = value;
+    for segment in path.trim_start_matches('$').split('.').filter(|s| !s.is_empty()) {
+        let (key, indexes) = match segment.find('[') {
+            Some(pos) => (&segment[..pos], &segment[pos..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            current = current.get(index.trim_end_matches(']').parse::<usize>().ok()?)?;
+        }
+    }
+
+    Some(match current {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"@host = http://localhost:3000
+@user = john
+
+### Login
+# @name login
+POST {{host}}/login HTTP/1.1
+Content-Type: application/json
+
+{"user": "{{user}}"}
+
+> {% client.global.set("token", response.body.token); %}
+
+###
+// @name getUsers
+GET /users
+    ?page=2
+    &limit=10
+Authorization: Bearer {{login.response.body.$.token}}
+
+### Health
+{{host}}/health
+"#;
+
+    #[test]
+    fn test_parse_requests() {
+        let file = HttpFile::parse(SAMPLE, PathBuf::new()).unwrap();
+        assert_eq!(file.variables.len(), 2);
+        assert_eq!(file.requests.len(), 3);
+
+        let login = &file.requests[0];
+        assert_eq!(login.name.as_deref(), Some("login"));
+        assert_eq!(login.title.as_deref(), Some("Login"));
+        assert_eq!(login.method, "POST");
+        assert_eq!(login.url, "{{host}}/login");
+        assert_eq!(login.headers, vec![("Content-Type".to_string(), "application/json".to_string())]);
+        assert_eq!(login.body.as_deref(), Some(r#"{"user": "{{user}}"}"#));
+        assert_eq!(login.handlers.len(), 1);
+
+        let users = &file.requests[1];
+        assert_eq!(users.name.as_deref(), Some("getUsers"));
+        assert_eq!(users.url, "/users?page=2&limit=10");
+        assert!(users.body.is_none());
+
+        let health = &file.requests[2];
+        assert_eq!(health.method, "GET");
+        assert_eq!(health.url, "{{host}}/health");
+        assert_eq!(health.label(), "Health");
+    }
+
+    #[test]
+    fn test_select_by_name_and_line() {
+        let file = HttpFile::parse(SAMPLE, PathBuf::new()).unwrap();
+        assert_eq!(file.select(Some("getUsers"), None).unwrap()[0].url, "/users?page=2&limit=10");
+        assert_eq!(file.select(None, Some(6)).unwrap()[0].name.as_deref(), Some("login"));
+        assert_eq!(file.select(None, Some(21)).unwrap()[0].label(), "Health");
+        assert_eq!(file.select(None, None).unwrap().len(), 3);
+        assert!(file.select(Some("missing"), None).is_err());
+    }
+
+    #[test]
+    fn test_body_lines_starting_with_handler_marker() {
+        let content = "POST /notes\nContent-Type: text/markdown\n\n> quoted line\n> {% not a handler %}\nlast line\n\n> {%\n    client.log(response.status);\n%}\n> after.js\n<> previous.json\n";
+        let file = HttpFile::parse(content, PathBuf::new()).unwrap();
+        let request = &file.requests[0];
+        assert_eq!(request.body.as_deref(), Some("> quoted line\n> {% not a handler %}\nlast line"));
+        assert_eq!(request.handlers.len(), 2);
+        assert!(request.handlers[0].starts_with("{%") && request.handlers[0].ends_with("%}"));
+        assert_eq!(request.handlers[1], "after.js");
+    }
+
+    #[test]
+    fn test_variables_scoped_to_request_line() {
+        let content = "@host = http://first\nGET {{host}}/a\n\n###\n@host = http://second\nGET {{host}}/b\n";
+        let file = HttpFile::parse(content, PathBuf::new()).unwrap();
+        let profile = ResolvedConfig {
+            profile: None,
+            url: String::new(),
+            token: None,
+            headers: Default::default(),
+            query: Vec::new(),
+            verbose: false,
+            secrets: Vec::new(),
+            graphql_endpoint: None,
+        };
+        let interpolator = Interpolator::new(&file, &profile).unwrap();
+        let mut config_manager = ConfigManager::new();
+        let urls: Vec<String> = file.requests.iter()
+            .map(|r| interpolator.interpolate(&r.url, r, &mut config_manager).unwrap())
+            .collect();
+        assert_eq!(urls, vec!["http://first/a", "http://second/b"]);
+    }
+
+    #[test]
+    fn test_json_path() {
+        let value: serde_json::Value = serde_json::from_str(r#"{"token":"abc","items":[{"id":7}]}"#).unwrap();
+        assert_eq!(json_path(&value, ".$.token").as_deref(), Some("abc"));
+        assert_eq!(json_path(&value, ".$.items[0].id").as_deref(), Some("7"));
+        assert!(json_path(&value, ".$.missing").is_none());
+    }
+}
@@ -0,0 +1,399 @@
+use super::{escape_dollars, relative_endpoint, url_origin, url_profile, Imported};
+use crate::config::{RawConfig, RawRequest};
+use crate::error::{AsteriskError, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::collections::HashMap;
+use std::fs;
+
+/// Boundary used for `-F` multipart bodies
+const FORM_BOUNDARY: &str = "------------------------asterisk-form-boundary";
+
+/// curl options that take a value but do not change the request
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-o", "--output", "-m", "--max-time", "--connect-timeout", "-w", "--write-out", "--retry",
+];
+
+/// curl options without a value that do not change the request
+/// (`--compressed` is implied: compression is advertised and responses are decoded)
+const IGNORED_FLAGS: &[&str] = &[
+    "-L", "--location", "-s", "--silent", "-S", "--show-error", "-v", "--verbose", "-i", "--include",
+    "-f", "--fail", "-N", "--no-buffer", "-g", "--globoff", "--compressed", "--http1.1", "--http2",
+];
+
+/// Request parsed from a curl command line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CurlRequest {
+    /// Uppercase HTTP method
+    pub method: String,
+
+    /// Absolute URL
+    pub url: String,
+
+    /// Headers in command line order
+    pub headers: Vec<(String, String)>,
+
+    /// Request body
+    pub body: Option<String>,
+
+    /// Skip TLS certificate verification (`-k`)
+    pub insecure: bool,
+
+    /// Options that were recognised but could not be honoured
+    pub notes: Vec<String>,
+}
+
+impl CurlRequest {
+    /// Looks up a header value by case-insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Path and query of the URL relative to `base_url`, or to the origin
+    pub fn endpoint(&self, base_url: Option<&str>) -> String {
+        relative_endpoint(&self.url, base_url)
+    }
+}
+
+/// Splits a pasted command line into arguments
+/// Handles shell quoting and backslash line continuations.
+pub fn split_command(command: &str) -> Result<Vec<String>> {
+    let joined = command.replace("\\\r\n", " ").replace("\\\n", " ");
+    shlex::split(&joined).ok_or_else(|| AsteriskError::Config(
+        "Could not parse curl command: unbalanced quotes".to_string()
+    ))
+}
+
+/// Parses curl arguments (with or without the leading `curl`)
+pub fn parse(args: &[String]) -> Result<CurlRequest> {
+    let mut request = CurlRequest::default();
+    let mut method = None;
+    let mut data: Vec<String> = Vec::new();
+    let mut form: Vec<String> = Vec::new();
+    let mut get = false;
+    let mut head = false;
+
+    let mut args = expand_short_flags(args.iter().skip_while(|arg| *arg == "curl")).into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| AsteriskError::Config(
+            format!("curl option '{}' requires a value", name)
+        ));
+
+        match arg.as_str() {
+            "-X" | "--request" => method = Some(value(&arg)?.to_uppercase()),
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (name, header_value) = header.split_once(':').ok_or_else(|| AsteriskError::Config(
+                    format!("Invalid curl header '{}'", header)
+                ))?;
+                request.headers.push((name.trim().to_string(), header_value.trim().to_string()));
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => data.push(read_data(value(&arg)?)?),
+            "--data-raw" => data.push(value(&arg)?),
+            "--data-urlencode" => data.push(url_encode_data(&value(&arg)?)),
+            "-F" | "--form" => form.push(value(&arg)?),
+            "-u" | "--user" => {
+                let credentials = value(&arg)?;
+                request.headers.push(("Authorization".to_string(), format!("Basic {}", BASE64.encode(credentials))));
+            }
+            "-A" | "--user-agent" => request.headers.push(("User-Agent".to_string(), value(&arg)?)),
+            "-e" | "--referer" => request.headers.push(("Referer".to_string(), value(&arg)?)),
+            "-b" | "--cookie" => request.headers.push(("Cookie".to_string(), value(&arg)?)),
+            "--url" => request.url = value(&arg)?,
+            "-k" | "--insecure" => request.insecure = true,
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => head = true,
+            option if IGNORED_WITH_VALUE.contains(&option) => {
+                value(option)?;
+            }
+            option if IGNORED_FLAGS.contains(&option) => {}
+            option if option.starts_with('-') && option.len() > 1 => {
+                request.notes.push(format!("Option '{}' is not supported and was ignored", option));
+            }
+            url if request.url.is_empty() => request.url = url.to_string(),
+            extra => request.notes.push(format!("Extra argument '{}' was ignored", extra)),
+        }
+    }
+
+    if request.url.is_empty() {
+        return Err(AsteriskError::Config("No URL found in curl command".to_string()));
+    }
+    if !request.url.contains("://") {
+        request.url = format!("http://{}", request.url);
+    }
+    if !data.is_empty() && !form.is_empty() {
+        return Err(AsteriskError::Config("curl options -d and -F cannot be combined".to_string()));
+    }
+
+    if !data.is_empty() {
+        let data = data.join("&");
+        if get {
+            let separator = if request.url.contains('?') { '&' } else { '?' };
+            request.url = format!("{}{}{}", request.url, separator, data);
+        } else {
+            if request.header("Content-Type").is_none() {
+                request.headers.push(("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()));
+            }
+            request.body = Some(data);
+        }
+    }
+    if !form.is_empty() {
+        if request.header("Content-Type").is_none() {
+            request.headers.push(("Content-Type".to_string(), format!("multipart/form-data; boundary={}", FORM_BOUNDARY)));
+        }
+        request.body = Some(multipart_body(&form)?);
+    }
+
+    request.method = match method {
+        Some(method) => method,
+        None if head => "HEAD".to_string(),
+        None if request.body.is_some() => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+
+    Ok(request)
+}
+
+/// Converts a parsed command into a saved request
+/// With a matching profile URL the endpoint becomes profile-relative; without a
+/// config file a `default` profile is created for the URL's origin. Credentials are
+/// left to the profile rather than written to the config file.
+pub fn convert(request: &CurlRequest, name: &str, profile_url: Option<&str>, has_config: bool) -> Imported {
+    let mut unsupported = request.notes.clone();
+    let mut profiles = HashMap::new();
+
+    let endpoint = match profile_url {
+        Some(base_url) => request.endpoint(Some(base_url)),
+        None if !has_config => {
+            profiles.insert("default".to_string(), url_profile(url_origin(&request.url)));
+            request.endpoint(None)
+        }
+        None => {
+            unsupported.push(format!("No profile matches {}; the request keeps its absolute URL", url_origin(&request.url)));
+            request.url.clone()
+        }
+    };
+
+    let mut headers = Vec::new();
+    for (header, value) in &request.headers {
+        if header.eq_ignore_ascii_case("authorization") || header.eq_ignore_ascii_case("cookie") {
+            unsupported.push(format!("{} header was not saved; configure credentials on the profile", header));
+        } else {
+            headers.push(format!("{}:{}", header, escape_dollars(value)));
+        }
+    }
+    if request.insecure {
+        unsupported.push("-k is not saved; saved requests always verify certificates".to_string());
+    }
+
+    let saved = RawRequest {
+        description: Some(format!("Imported from curl: {} {}", request.method, request.url)),
+        method: Some(request.method.clone()),
+        endpoint: escape_dollars(&endpoint),
+        headers: if headers.is_empty() { None } else { Some(headers) },
+        body: request.body.as_deref().map(escape_dollars),
+        ..Default::default()
+    };
+
+    Imported {
+        config: RawConfig {
+            default_profile: if profiles.is_empty() { None } else { Some("default".to_string()) },
+            openapi: None,
+            defaults: None,
+            profiles,
+            requests: HashMap::from([(name.to_string(), saved)]),
+        },
+        unsupported,
+    }
+}
+
+/// Splits bundled short options (`-sSL`, `-XPOST`) into separate arguments
+fn expand_short_flags<'a>(args: impl Iterator<Item = &'a String>) -> Vec<String> {
+    const WITH_VALUE: &str = "XHdFuAebmow";
+    let mut expanded = Vec::new();
+    let mut after_value_option = false;
+
+    for arg in args {
+        let bundled = !after_value_option && arg.len() > 2 && arg.starts_with('-') && !arg.starts_with("--");
+        after_value_option = false;
+        if !bundled {
+            after_value_option = (arg.len() == 2 && WITH_VALUE.contains(&arg[1..]))
+                || IGNORED_WITH_VALUE.contains(&arg.as_str())
+                || (arg.starts_with("--") && is_long_with_value(arg));
+            expanded.push(arg.clone());
+            continue;
+        }
+
+        for (index, flag) in arg[1..].char_indices() {
+            expanded.push(format!("-{}", flag));
+            if WITH_VALUE.contains(flag) {
+                let rest = &arg[1 + index + flag.len_utf8()..];
+                if rest.is_empty() {
+                    after_value_option = true;
+                } else {
+                    expanded.push(rest.to_string());
+                }
+                break;
+            }
+        }
+    }
+    expanded
+}
+
+fn is_long_with_value(arg: &str) -> bool {
+    matches!(arg,
+        "--request" | "--header" | "--data" | "--data-ascii" | "--data-binary" | "--data-raw"
+        | "--data-urlencode" | "--form" | "--user" | "--user-agent" | "--referer" | "--cookie" | "--url")
+}
+
+/// Reads `@file` data arguments
+fn read_data(value: String) -> Result<String> {
+    match value.strip_prefix('@') {
+        Some(path) => fs::read_to_string(path)
+            .map(|content| content.replace(['\r', '\n'], ""))
+            .map_err(|e| AsteriskError::Config(format!("Failed to read curl data file {}: {}", path, e))),
+        None => Ok(value),
+    }
+}
+
+/// Encodes a `--data-urlencode` argument (`content` or `name=content`)
+fn url_encode_data(value: &str) -> String {
+    match value.split_once('=') {
+        Some((name, content)) if !name.is_empty() => format!("{}={}", name, form_encode(content)),
+        Some((_, content)) => form_encode(content),
+        None => form_encode(value),
+    }
+}
+
+fn form_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => (byte as char).to_string(),
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Builds a multipart body from `-F name=value` and `-F name=@file;type=...` fields
+fn multipart_body(fields: &[String]) -> Result<String> {
+    let mut body = String::new();
+    for field in fields {
+        let (name, value) = field.split_once('=').ok_or_else(|| AsteriskError::Config(
+            format!("Invalid curl form field '{}'. Expected 'name=value'", field)
+        ))?;
+        body.push_str(&format!("--{}\r\n", FORM_BOUNDARY));
+
+        match value.strip_prefix('@').or_else(|| value.strip_prefix('<')) {
+            Some(file) => {
+                let (path, content_type) = match file.split_once(";type=") {
+                    Some((path, content_type)) => (path, content_type),
+                    None => (file, "application/octet-stream"),
+                };
+                let content = fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+                    format!("Failed to read form file {}: {}", path, e)
+                ))?;
+                let filename = std::path::Path::new(path).file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if value.starts_with('@') {
+                    body.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        name, filename, content_type
+                    ));
+                } else {
+                    body.push_str(&format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name));
+                }
+                body.push_str(&content);
+            }
+            None => {
+                body.push_str(&format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name));
+                body.push_str(value);
+            }
+        }
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", FORM_BOUNDARY));
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    fn parse_command(command: &str) -> CurlRequest {
+        parse(&split_command(command).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_devtools_command() {
+        let request = parse_command(r#"curl 'https://api.example.com/v1/users?page=2' \
+  -H 'accept: application/json' \
+  -H 'authorization: Bearer abc' \
+  --data-raw '{"name":"john"}' \
+  --compressed -sSk"#);
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.url, "https://api.example.com/v1/users?page=2");
+        assert_eq!(request.header("Accept"), Some("application/json"));
+        assert_eq!(request.header("content-type"), Some("application/x-www-form-urlencoded"));
+        assert_eq!(request.body.as_deref(), Some(r#"{"name":"john"}"#));
+        assert!(request.insecure);
+        assert!(request.notes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let request = parse_command("curl -XPUT -u user:pass -d a=1 -d b=2 --data-urlencode 'q=a b&c' http://localhost:3000/x");
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.header("Authorization"), Some("Basic dXNlcjpwYXNz"));
+        assert_eq!(request.body.as_deref(), Some("a=1&b=2&q=a+b%26c"));
+
+        let request = parse_command("curl -G -d page=2 localhost:3000/users --proxy http://p");
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "http://localhost:3000/users?page=2");
+        assert_eq!(request.notes.len(), 2);
+
+        let request = parse_command("curl -F name=john -F 'bio=hi' http://localhost:3000/upload");
+        assert_eq!(request.method, "POST");
+        assert!(request.header("Content-Type").unwrap().starts_with("multipart/form-data; boundary="));
+        assert!(request.body.unwrap().contains("Content-Disposition: form-data; name=\"name\"\r\n\r\njohn\r\n"));
+
+        assert!(parse(&split_command("curl -H 'X: 1'").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_convert_matches_profile() {
+        let request = parse_command("curl -H 'Authorization: Bearer x' -H 'X-Tenant: acme' https://api.example.com/v1/users/5");
+
+        let imported = convert(&request, "get_user", Some("https://api.example.com/v1"), true);
+        let saved = &imported.config.requests["get_user"];
+        assert_eq!(saved.endpoint, "/users/5");
+        assert_eq!(saved.headers, Some(vec!["X-Tenant:acme".to_string()]));
+        assert!(imported.config.profiles.is_empty());
+        assert_eq!(imported.unsupported.len(), 1);
+
+        let imported = convert(&request, "get_user", None, false);
+        assert_eq!(imported.config.profiles["default"].url, "https://api.example.com");
+        assert_eq!(imported.config.requests["get_user"].endpoint, "/v1/users/5");
+
+        let imported = convert(&request, "get_user", None, true);
+        assert_eq!(imported.config.requests["get_user"].endpoint, "https://api.example.com/v1/users/5");
+    }
+
+    #[test]
+    fn test_convert_escapes_dollars() {
+        let request = parse_command(r#"curl -H 'X-Price: $5' -d '{"note":"$ 5 or $HOME"}' 'https://api.example.com/v1/$batch'"#);
+        let imported = convert(&request, "batch", Some("https://api.example.com/v1"), true);
+        let saved = &imported.config.requests["batch"];
+
+        let mut config_manager = ConfigManager::new();
+        assert_eq!(config_manager.resolve_value(&saved.endpoint).unwrap(), "/$batch");
+        assert_eq!(config_manager.resolve_value(&saved.headers.as_ref().unwrap()[0]).unwrap(), "X-Price:$5");
+        assert_eq!(config_manager.resolve_value(saved.body.as_deref().unwrap()).unwrap(), r#"{"note":"$ 5 or $HOME"}"#);
+        assert!(imported.unsupported.is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use super::{escape_dollars, relative_endpoint, slugify, unique_name, url_origin, url_profile, Imported};
+use crate::config::{RawConfig, RawExpectation, RawRequest};
+use crate::error::{AsteriskError, Result};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Request headers added by browsers and HTTP clients on their own
+const SKIPPED_HEADERS: &[&str] = &[
+    "host", "content-length", "connection", "keep-alive", "te", "accept-encoding", "accept-language",
+    "user-agent", "origin", "referer", "cache-control", "pragma", "priority", "dnt", "upgrade-insecure-requests",
+];
+
+/// Chrome `_resourceType` values that are API calls rather than page assets
+const API_RESOURCE_TYPES: &[&str] = &["xhr", "fetch"];
+
+/// Converts the entries of an HTTP Archive into saved requests
+/// Browser captures are narrowed to XHR/fetch calls and repeated calls are saved once.
+/// URLs under a profile (as reported by `profile_url`) become profile-relative; without
+/// a config file a `default` profile is created for the most frequent origin.
+pub fn convert(archive: &Value, profile_url: impl Fn(&str) -> Option<String>, has_config: bool) -> Result<Imported> {
+    let entries = archive.pointer("/log/entries").and_then(Value::as_array).ok_or_else(|| AsteriskError::Config(
+        "Not a HAR file (missing log.entries)".to_string()
+    ))?;
+
+    let mut unsupported = Vec::new();
+
+    // Browser captures tag every entry; keep only API calls
+    let browser_capture = entries.iter().any(|entry| entry.get("_resourceType").is_some());
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    let mut skipped_assets = 0;
+    for entry in entries {
+        let resource_type = entry.get("_resourceType").and_then(Value::as_str).unwrap_or("");
+        if browser_capture && !API_RESOURCE_TYPES.contains(&resource_type) {
+            skipped_assets += 1;
+            continue;
+        }
+        let request = entry.get("request").unwrap_or(&Value::Null);
+        let key = (text(request, "method"), text(request, "url"), request.pointer("/postData/text").cloned());
+        if seen.insert(key) {
+            kept.push(entry);
+        }
+    }
+    if skipped_assets > 0 {
+        unsupported.push(format!("Skipped {} page asset entries (documents, scripts, styles, images)", skipped_assets));
+    }
+    if kept.len() < entries.len() - skipped_assets {
+        unsupported.push(format!("Skipped {} repeated requests", entries.len() - skipped_assets - kept.len()));
+    }
+
+    let mut profiles = HashMap::new();
+    let default_origin = if has_config { None } else { most_frequent_origin(&kept) };
+    if let Some(ref origin) = default_origin {
+        profiles.insert("default".to_string(), url_profile(origin.clone()));
+    }
+
+    let mut requests = HashMap::new();
+    let mut dropped_credentials = BTreeSet::new();
+    let mut unmatched_origins = BTreeSet::new();
+    for entry in kept {
+        let request = entry.get("request").unwrap_or(&Value::Null);
+        let method = text(request, "method").to_uppercase();
+        let url = text(request, "url");
+
+        let endpoint = match profile_url(&url) {
+            Some(base_url) => relative_endpoint(&url, Some(&base_url)),
+            None if default_origin.as_deref() == Some(url_origin(&url).as_str()) => relative_endpoint(&url, None),
+            None => {
+                unmatched_origins.insert(url_origin(&url));
+                url.clone()
+            }
+        };
+
+        let mut headers = Vec::new();
+        for header in request.get("headers").and_then(Value::as_array).into_iter().flatten() {
+            let name = text(header, "name");
+            let lowercase = name.to_lowercase();
+            if lowercase.starts_with(':') || lowercase.starts_with("sec-") || SKIPPED_HEADERS.contains(&lowercase.as_str()) {
+                continue;
+            }
+            if lowercase == "authorization" || lowercase == "cookie" {
+                dropped_credentials.insert(name);
+                continue;
+            }
+            headers.push(format!("{}:{}", name, escape_dollars(&text(header, "value"))));
+        }
+
+        let body = request.pointer("/postData/text").and_then(Value::as_str).map(escape_dollars);
+        let label = format!("{} {}", method, url);
+        if body.is_none() && request.pointer("/postData/params").is_some() {
+            unsupported.push(format!("{}: multipart form parameters are not imported", label));
+        }
+
+        let status = entry.pointer("/response/status").and_then(Value::as_u64)
+            .and_then(|status| u16::try_from(status).ok())
+            .filter(|status| (200..300).contains(status));
+
+        let path = Url::parse(&url).map(|parsed| parsed.path().to_string()).unwrap_or_default();
+        let name = unique_name(slugify(&format!("{} {}", method.to_lowercase(), path)), |candidate| requests.contains_key(candidate));
+        requests.insert(name, RawRequest {
+            description: Some(format!("Imported from HAR: {}", label)),
+            method: Some(method),
+            endpoint: escape_dollars(&endpoint),
+            headers: if headers.is_empty() { None } else { Some(headers) },
+            body,
+            expect: status.map(|status| RawExpectation { status: Some(status), ..Default::default() }),
+            ..Default::default()
+        });
+    }
+
+    if !dropped_credentials.is_empty() {
+        let names: Vec<String> = dropped_credentials.into_iter().collect();
+        unsupported.push(format!("{} headers were not saved; configure credentials on the profile", names.join("/")));
+    }
+    for origin in unmatched_origins {
+        unsupported.push(format!("No profile matches {}; its requests keep absolute URLs", origin));
+    }
+
+    Ok(Imported {
+        config: RawConfig {
+            default_profile: default_origin.map(|_| "default".to_string()),
+            openapi: None,
+            defaults: None,
+            profiles,
+            requests,
+        },
+        unsupported,
+    })
+}
+
+/// Origin used by the most entries (the first one seen wins ties)
+fn most_frequent_origin(entries: &[&Value]) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for entry in entries {
+        let origin = url_origin(&text(entry.get("request").unwrap_or(&Value::Null), "url"));
+        match counts.iter_mut().find(|(existing, _)| *existing == origin) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((origin, 1)),
+        }
+    }
+    let max = counts.iter().map(|(_, count)| *count).max()?;
+    counts.into_iter().find(|(_, count)| *count == max).map(|(origin, _)| origin)
+}
+
+fn text(value: &Value, key: &str) -> String {
+    value.get(key).and_then(Value::as_str).unwrap_or("").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+    use serde_json::json;
+
+    fn archive() -> Value {
+        let entry = |resource_type: &str, method: &str, url: &str, status: u16| json!({
+            "_resourceType": resource_type,
+            "request": {
+                "method": method,
+                "url": url,
+                "headers": [
+                    { "name": ":authority", "value": "api.example.com" },
+                    { "name": "accept", "value": "application/json" },
+                    { "name": "authorization", "value": "Bearer secret" },
+                    { "name": "sec-fetch-mode", "value": "cors" },
+                ],
+            },
+            "response": { "status": status },
+        });
+        let mut create = entry("fetch", "POST", "https://api.example.com/v1/users", 201);
+        create["request"]["postData"] = json!({ "mimeType": "application/json", "text": "{\"name\":\"john\",\"cost\":\"$ 5\"}" });
+
+        json!({ "log": { "version": "1.2", "entries": [
+            entry("document", "GET", "https://app.example.com/", 200),
+            entry("xhr", "GET", "https://api.example.com/v1/users?page=2", 200),
+            entry("xhr", "GET", "https://api.example.com/v1/users?page=2", 200),
+            create,
+            entry("fetch", "GET", "https://cdn.example.com/config.json", 404),
+        ]}})
+    }
+
+    #[test]
+    fn test_convert_browser_capture() {
+        let imported = convert(&archive(), |_| None, false).unwrap();
+        let config = imported.config;
+
+        assert_eq!(config.profiles["default"].url, "https://api.example.com");
+        assert_eq!(config.requests.len(), 3);
+
+        let list = &config.requests["get_v1_users"];
+        assert_eq!(list.endpoint, "/v1/users?page=2");
+        assert_eq!(list.headers, Some(vec!["accept:application/json".to_string()]));
+        assert_eq!(list.expect.as_ref().unwrap().status, Some(200));
+
+        let create = &config.requests["post_v1_users"];
+        let body = ConfigManager::new().resolve_value(create.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body, "{\"name\":\"john\",\"cost\":\"$ 5\"}");
+
+        let config_json = &config.requests["get_config_json"];
+        assert_eq!(config_json.endpoint, "https://cdn.example.com/config.json");
+        assert!(config_json.expect.is_none());
+
+        assert_eq!(imported.unsupported, vec![
+            "Skipped 1 page asset entries (documents, scripts, styles, images)".to_string(),
+            "Skipped 1 repeated requests".to_string(),
+            "authorization headers were not saved; configure credentials on the profile".to_string(),
+            "No profile matches https://cdn.example.com; its requests keep absolute URLs".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_convert_matches_profiles() {
+        let profile_url = |url: &str| url.starts_with("https://api.example.com/v1").then(|| "https://api.example.com/v1".to_string());
+        let imported = convert(&archive(), profile_url, true).unwrap();
+        assert!(imported.config.profiles.is_empty());
+        assert_eq!(imported.config.requests["get_v1_users"].endpoint, "/users?page=2");
+    }
+
+    #[test]
+    fn test_rejects_non_har() {
+        assert!(convert(&json!({ "info": {} }), |_| None, false).is_err());
+    }
+}
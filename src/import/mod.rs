@@ -0,0 +1,151 @@
+pub mod curl;
+pub mod har;
+pub mod openapi;
+pub mod postman;
+
+use crate::config::{RawConfig, RawProfile};
+use reqwest::Url;
+
+/// Result of converting an external collection format into asterisk configuration
+#[derive(Debug)]
+pub struct Imported {
+    /// Profiles and saved requests to merge into asterisk.config
+    pub config: RawConfig,
+
+    /// Human readable notes for everything that could not be converted
+    pub unsupported: Vec<String>,
+}
+
+/// Converts a display name into a config key (lowercase, `_` separated)
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for ch in name.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.ends_with('_') && !slug.is_empty() {
+            slug.push('_');
+        }
+    }
+    let slug = slug.trim_end_matches('_').to_string();
+    if slug.is_empty() { "unnamed".to_string() } else { slug }
+}
+
+/// Makes a variable name usable in `${vars.name}` references
+pub fn sanitize_variable(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Escapes literal `$` as `$$`, so imported text is not read as variable references
+pub fn escape_dollars(text: &str) -> String {
+    text.replace('$', "$$")
+}
+
+/// Built-in equivalents of Postman dynamic variables
+const DYNAMIC_VARIABLES: &[(&str, &str)] = &[
+    ("$guid", "${uuid}"),
+    ("$randomUUID", "${uuid}"),
+    ("$timestamp", "${unix}"),
+    ("$isoTimestamp", "${now:%Y-%m-%dT%H:%M:%S.%3fZ}"),
+    ("$randomInt", "${random_int:0:1000}"),
+];
+
+/// Rewrites `{{var}}` references as `${vars.var}` and escapes literal `$` as `$$`
+/// Dynamic variables with a built-in equivalent (`{{$guid}}` becomes `${uuid}`) are converted;
+/// others are kept as literal text and returned separately
+pub fn convert_template(text: &str, dynamic: &mut Vec<String>) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&escape_dollars(&rest[..start]));
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                if let Some((_, builtin)) = DYNAMIC_VARIABLES.iter().find(|(dynamic, _)| *dynamic == name) {
+                    result.push_str(builtin);
+                } else if name.starts_with('$') {
+                    dynamic.push(name.to_string());
+                    result.push_str(&escape_dollars(&rest[start..start + 2 + end + 2]));
+                } else {
+                    result.push_str(&format!("${{vars.{}}}", sanitize_variable(name)));
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push_str(&escape_dollars(&rest[start..]));
+                rest = "";
+            }
+        }
+    }
+    result.push_str(&escape_dollars(rest));
+    result
+}
+
+/// Picks a name not already present in `taken`, appending `_2`, `_3`, ...
+pub fn unique_name(base: String, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{}_{}", base, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(base)
+}
+
+/// Scheme, host and port of a URL
+pub fn url_origin(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(parsed) => parsed.origin().ascii_serialization(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Path and query of `url` relative to `base_url`, or to the URL's origin
+pub fn relative_endpoint(url: &str, base_url: Option<&str>) -> String {
+    let base = base_url.map(String::from).unwrap_or_else(|| url_origin(url));
+    match url.strip_prefix(base.trim_end_matches('/')) {
+        Some("") => "/".to_string(),
+        Some(rest) => rest.to_string(),
+        None => url.to_string(),
+    }
+}
+
+/// A profile with only a base URL
+pub fn url_profile(url: String) -> RawProfile {
+    RawProfile {
+        extends: None,
+        url,
+        token: None,
+        headers: None,
+        verbose: None,
+        auth: None,
+        vars: None,
+        graphql_endpoint: None,
+        env_file: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Create User"), "create_user");
+        assert_eq!(slugify("  Users / Get by ID "), "users_get_by_id");
+        assert_eq!(slugify("!!!"), "unnamed");
+    }
+
+    #[test]
+    fn test_convert_template() {
+        let mut dynamic = Vec::new();
+        assert_eq!(
+            convert_template("{{baseUrl}}/users/{{user-id}}?t={{$timestamp}}&n={{$randomFirstName}}&cost=$5", &mut dynamic),
+            "${vars.baseUrl}/users/${vars.user_id}?t=${unix}&n={{$$randomFirstName}}&cost=$$5"
+        );
+        assert_eq!(dynamic, vec!["$randomFirstName".to_string()]);
+    }
+}
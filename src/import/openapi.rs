@@ -0,0 +1,276 @@
+use super::{escape_dollars, sanitize_variable, slugify, unique_name, Imported};
+use crate::config::{RawAuth, RawConfig, RawExpectation, RawProfile, RawRequest};
+use crate::openapi::{OpenApiSpec, Operation};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Profile URL used when the spec declares no absolute server
+const DEFAULT_URL: &str = "http://localhost:3000";
+
+/// Converts an OpenAPI 3 document into profiles and saved requests
+/// Servers become profiles, the first applicable security scheme becomes profile
+/// auth and every operation becomes a request named after its operationId.
+pub fn convert(spec: &OpenApiSpec, spec_path: &str) -> Imported {
+    let mut unsupported = Vec::new();
+    let auth = convert_security(spec, &mut unsupported);
+
+    let mut profiles = HashMap::new();
+    for server in spec.document.get("servers").and_then(Value::as_array).into_iter().flatten() {
+        let url = server_url(server);
+        let url = if url.starts_with('/') {
+            unsupported.push(format!("Relative server URL '{}' was prefixed with {}", url, DEFAULT_URL));
+            format!("{}{}", DEFAULT_URL, url)
+        } else {
+            url
+        };
+        let base = server.get("description").and_then(Value::as_str).map(slugify)
+            .unwrap_or_else(|| "server".to_string());
+        let name = unique_name(base, |candidate| profiles.contains_key(candidate));
+        profiles.insert(name, profile(url, auth.clone()));
+    }
+    if profiles.is_empty() {
+        profiles.insert("default".to_string(), profile(DEFAULT_URL.to_string(), auth));
+    }
+
+    let mut requests = HashMap::new();
+    for operation in spec.operations() {
+        let name = unique_name(operation.name(), |candidate| requests.contains_key(candidate));
+        let request = convert_operation(spec, &operation, &mut unsupported);
+        requests.insert(name, request);
+    }
+
+    let default_profile = {
+        let mut names: Vec<&String> = profiles.keys().collect();
+        names.sort();
+        names.first().map(|n| n.to_string())
+    };
+
+    Imported {
+        config: RawConfig {
+            default_profile,
+            openapi: Some(spec_path.to_string()),
+            defaults: None,
+            profiles,
+            requests,
+        },
+        unsupported,
+    }
+}
+
+fn profile(url: String, auth: Option<RawAuth>) -> RawProfile {
+    RawProfile {
+        extends: None,
+        url,
+        token: None,
+        headers: None,
+        verbose: None,
+        auth,
+        vars: None,
+        graphql_endpoint: None,
+        env_file: None,
+    }
+}
+
+/// Substitutes server variables with their defaults
+fn server_url(server: &Value) -> String {
+    let mut url = server.get("url").and_then(Value::as_str).unwrap_or("/").to_string();
+    if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+        for (name, variable) in variables {
+            let default = variable.get("default").and_then(Value::as_str).unwrap_or("");
+            url = url.replace(&format!("{{{}}}", name), default);
+        }
+    }
+    url.trim_end_matches('/').to_string()
+}
+
+/// Maps the first globally required (or first declared) security scheme to profile auth
+fn convert_security(spec: &OpenApiSpec, unsupported: &mut Vec<String>) -> Option<RawAuth> {
+    let schemes = spec.document.pointer("/components/securitySchemes").and_then(Value::as_object)?;
+
+    let required = spec.document.get("security").and_then(Value::as_array)
+        .and_then(|requirements| requirements.first())
+        .and_then(Value::as_object)
+        .and_then(|requirement| requirement.keys().next().cloned());
+    let name = required.or_else(|| schemes.keys().next().cloned())?;
+    let scheme = spec.resolve(schemes.get(&name)?);
+
+    for other in schemes.keys().filter(|other| **other != name) {
+        unsupported.push(format!("Security scheme '{}' was not applied (only '{}' is used)", other, name));
+    }
+
+    let scheme_type = scheme.get("type").and_then(Value::as_str).unwrap_or("");
+    match (scheme_type, scheme.get("scheme").and_then(Value::as_str).map(str::to_lowercase)) {
+        ("http", Some(ref http)) if http == "bearer" => Some(RawAuth::Bearer { token: "$API_TOKEN".to_string() }),
+        ("http", Some(ref http)) if http == "basic" => Some(RawAuth::Basic {
+            username: "$API_USERNAME".to_string(),
+            password: Some("$API_PASSWORD".to_string()),
+        }),
+        ("apiKey", _) => {
+            let location = scheme.get("in").and_then(Value::as_str).unwrap_or("header");
+            if location == "cookie" {
+                unsupported.push(format!("Security scheme '{}': cookie API keys are not supported", name));
+                return None;
+            }
+            Some(RawAuth::ApiKey {
+                key: scheme.get("name").and_then(Value::as_str).unwrap_or("X-API-Key").to_string(),
+                value: "$API_KEY".to_string(),
+                location: if location == "query" { Some("query".to_string()) } else { None },
+            })
+        }
+        ("oauth2", _) | ("openIdConnect", _) => {
+            unsupported.push(format!(
+                "Security scheme '{}': {} flows are not run; supply an access token in $API_TOKEN", name, scheme_type
+            ));
+            Some(RawAuth::Bearer { token: "$API_TOKEN".to_string() })
+        }
+        _ => {
+            unsupported.push(format!("Security scheme '{}' of type '{}' is not supported", name, scheme_type));
+            None
+        }
+    }
+}
+
+fn convert_operation(spec: &OpenApiSpec, operation: &Operation, unsupported: &mut Vec<String>) -> RawRequest {
+    let label = format!("{} {}", operation.method, operation.path);
+
+    // Path parameters become `${vars.name}` placeholders supplied as `name=value` at run time
+    let mut endpoint = escape_dollars(&operation.path);
+    let mut query = HashMap::new();
+    let mut headers = Vec::new();
+    for parameter in &operation.parameters {
+        let name = parameter.get("name").and_then(Value::as_str).unwrap_or("");
+        let required = parameter.get("required").and_then(Value::as_bool).unwrap_or(false);
+        let placeholder = format!("${{vars.{}}}", sanitize_variable(name));
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => endpoint = endpoint.replace(&format!("{{{}}}", name), &placeholder),
+            Some("query") if required => {
+                query.insert(name.to_string(), placeholder);
+            }
+            Some("header") if required => headers.push(format!("{}:{}", name, placeholder)),
+            Some("cookie") if required => {
+                unsupported.push(format!("{}: cookie parameter '{}' is not supported", label, name));
+            }
+            _ => {}
+        }
+    }
+
+    let mut body = None;
+    if let Some(request_body) = operation.definition.get("requestBody") {
+        let content = spec.resolve(request_body).get("content").and_then(Value::as_object);
+        let json_media = content.and_then(|c| {
+            c.iter().find(|(media_type, _)| media_type.contains("json")).map(|(_, m)| m)
+        });
+        match (json_media, content) {
+            (Some(media), _) => {
+                let example = media.get("example").cloned()
+                    .or_else(|| media.get("examples").and_then(Value::as_object)
+                        .and_then(|examples| examples.values().next())
+                        .and_then(|example| spec.resolve(example).get("value").cloned()))
+                    .or_else(|| media.get("schema").map(|schema| spec.example_for_schema(schema)));
+                if let Some(example) = example {
+                    let rendered = serde_json::to_string_pretty(&example).unwrap_or_default();
+                    body = Some(escape_dollars(&rendered));
+                }
+            }
+            (None, Some(content)) => {
+                if let Some(media_type) = content.keys().next() {
+                    unsupported.push(format!("{}: '{}' request bodies are not generated", label, media_type));
+                    headers.push(format!("Content-Type:{}", media_type));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // The first declared 2xx response is the expected status
+    let status = operation.definition.get("responses").and_then(Value::as_object)
+        .and_then(|responses| {
+            let mut codes: Vec<u16> = responses.keys().filter_map(|code| code.parse().ok()).collect();
+            codes.sort();
+            codes.into_iter().find(|code| (200..300).contains(code))
+        });
+
+    RawRequest {
+        description: operation.summary.clone().or_else(|| Some(label.clone())),
+        method: Some(operation.method.clone()),
+        endpoint,
+        headers: if headers.is_empty() { None } else { Some(headers) },
+        body,
+        body_file: None,
+        query: if query.is_empty() { None } else { Some(query) },
+        expect: status.map(|status| RawExpectation {
+            status: Some(status),
+            ..Default::default()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info: { title: Users, version: "1.0" }
+servers:
+  - url: https://{env}.example.com/v1
+    description: Staging
+    variables:
+      env: { default: staging }
+  - url: http://localhost:8080
+security:
+  - bearerAuth: []
+paths:
+  /users/{id}:
+    get:
+      operationId: getUserById
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+        - { name: expand, in: query, required: true, schema: { type: string } }
+      responses:
+        "404": { description: Missing }
+        "200": { description: OK }
+  /users:
+    post:
+      operationId: createUser
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: "#/components/schemas/NewUser" }
+      responses:
+        "201": { description: Created }
+components:
+  securitySchemes:
+    bearerAuth: { type: http, scheme: bearer }
+  schemas:
+    NewUser:
+      type: object
+      properties:
+        name: { type: string, example: John }
+        plan: { type: string, example: "$5 $TIER" }
+"##;
+
+    #[test]
+    fn test_convert_spec() {
+        let spec = OpenApiSpec::parse(SPEC).unwrap();
+        let imported = convert(&spec, "openapi.yaml");
+        let config = imported.config;
+
+        assert_eq!(config.openapi.as_deref(), Some("openapi.yaml"));
+        assert_eq!(config.profiles["staging"].url, "https://staging.example.com/v1");
+        assert_eq!(config.profiles["server"].url, "http://localhost:8080");
+        assert!(matches!(config.profiles["staging"].auth, Some(RawAuth::Bearer { .. })));
+
+        let get_user = &config.requests["getUserById"];
+        assert_eq!(get_user.endpoint, "/users/${vars.id}");
+        assert_eq!(get_user.query.as_ref().unwrap()["expand"], "${vars.expand}");
+        assert_eq!(get_user.expect.as_ref().unwrap().status, Some(200));
+
+        let create_user = &config.requests["createUser"];
+        let body = ConfigManager::new().resolve_value(create_user.body.as_deref().unwrap()).unwrap();
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body, serde_json::json!({ "name": "John", "plan": "$5 $TIER" }));
+        assert!(imported.unsupported.is_empty());
+    }
+}
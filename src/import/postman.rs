@@ -0,0 +1,536 @@
+use super::{convert_template, escape_dollars, sanitize_variable, slugify, unique_name, Imported};
+use crate::config::{RawAuth, RawConfig, RawProfile, RawRequest};
+use crate::error::{AsteriskError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Variable names treated as the collection base URL
+const BASE_URL_VARIABLES: &[&str] = &["baseurl", "base_url", "url", "host", "apiurl", "api_url", "server"];
+
+/// Fallback profile URL when the collection gives no hint
+const DEFAULT_URL: &str = "http://localhost:3000";
+
+/// Converts a Postman v2.1 collection and optional environments
+/// Each environment becomes a profile; without environments a single profile is
+/// named after the collection.
+pub fn convert(collection: &Value, environments: &[Value]) -> Result<Imported> {
+    let schema = collection.pointer("/info/schema").and_then(Value::as_str).unwrap_or("");
+    if collection.get("item").is_none() || (!schema.is_empty() && !schema.contains("v2.")) {
+        return Err(AsteriskError::Config(
+            "Not a Postman v2.1 collection (missing 'item' or unsupported schema)".to_string()
+        ));
+    }
+
+    let mut converter = Converter::default();
+
+    // Collection variables are shared by every profile
+    let mut collection_vars = HashMap::new();
+    for variable in array(collection.get("variable")) {
+        if let Some((key, value)) = variable_entry(variable, "disabled", true) {
+            collection_vars.insert(sanitize_variable(&key), value);
+        }
+    }
+
+    let mut env_vars: Vec<(String, HashMap<String, String>)> = Vec::new();
+    for environment in environments {
+        let name = environment.get("name").and_then(Value::as_str).unwrap_or("environment");
+        let mut vars = collection_vars.clone();
+        for variable in array(environment.get("values")) {
+            if let Some((key, value)) = variable_entry(variable, "enabled", false) {
+                vars.insert(sanitize_variable(&key), value);
+            }
+        }
+        env_vars.push((slugify(name), vars));
+    }
+    if env_vars.is_empty() {
+        let name = collection.pointer("/info/name").and_then(Value::as_str).unwrap_or("postman");
+        env_vars.push((slugify(name), collection_vars.clone()));
+    }
+
+    // Requests are relative to the base URL variable or a common origin
+    converter.base_variable = env_vars.iter()
+        .flat_map(|(_, vars)| vars.keys())
+        .find(|key| BASE_URL_VARIABLES.contains(&key.to_lowercase().as_str()))
+        .cloned();
+    if converter.base_variable.is_none() {
+        converter.origin = first_origin(collection);
+    }
+
+    let auth = match collection.get("auth") {
+        Some(auth) => converter.convert_auth(auth, "collection"),
+        None => None,
+    };
+    converter.report_events(collection, "collection");
+
+    for item in array(collection.get("item")) {
+        converter.convert_item(item, &[]);
+    }
+
+    let profile_url = match (&converter.base_variable, &converter.origin) {
+        (Some(variable), _) => format!("${{vars.{}}}", variable),
+        (None, Some(origin)) => escape_dollars(origin),
+        (None, None) => {
+            converter.unsupported.push(format!(
+                "No base URL variable or absolute request URL found; profiles use {}", DEFAULT_URL
+            ));
+            DEFAULT_URL.to_string()
+        }
+    };
+
+    let mut profiles = HashMap::new();
+    for (name, mut vars) in env_vars {
+        for (key, value) in &converter.path_defaults {
+            vars.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        let mut dynamic = Vec::new();
+        let vars: HashMap<String, String> = vars.into_iter()
+            .map(|(k, v)| (k, convert_template(&v, &mut dynamic)))
+            .collect();
+        for variable in dynamic {
+            converter.unsupported.push(format!("{}: dynamic variable {{{{{}}}}} is not converted", name, variable));
+        }
+
+        let name = unique_name(name, |candidate| profiles.contains_key(candidate));
+        profiles.insert(name, RawProfile {
+            extends: None,
+            url: profile_url.clone(),
+            token: None,
+            headers: None,
+            verbose: None,
+            auth: auth.clone(),
+            vars: if vars.is_empty() { None } else { Some(vars) },
+            graphql_endpoint: None,
+            env_file: None,
+        });
+    }
+
+    let default_profile = {
+        let mut names: Vec<&String> = profiles.keys().collect();
+        names.sort();
+        names.first().map(|n| n.to_string())
+    };
+
+    Ok(Imported {
+        config: RawConfig {
+            default_profile,
+            openapi: None,
+            defaults: None,
+            profiles,
+            requests: converter.requests,
+        },
+        unsupported: converter.unsupported,
+    })
+}
+
+#[derive(Default)]
+struct Converter {
+    base_variable: Option<String>,
+    origin: Option<String>,
+    requests: HashMap<String, RawRequest>,
+    path_defaults: HashMap<String, String>,
+    unsupported: Vec<String>,
+}
+
+impl Converter {
+    /// Converts a folder (recursively) or a request item
+    fn convert_item(&mut self, item: &Value, folders: &[String]) {
+        let name = item.get("name").and_then(Value::as_str).unwrap_or("request").to_string();
+        let mut path = folders.to_vec();
+        path.push(name);
+        let label = path.join(" / ");
+
+        self.report_events(item, &label);
+
+        if let Some(children) = item.get("item").and_then(Value::as_array) {
+            if item.get("auth").is_some() {
+                self.unsupported.push(format!("{}: folder-level auth is not converted", label));
+            }
+            for child in children {
+                self.convert_item(child, &path);
+            }
+            return;
+        }
+
+        if let Some(request) = item.get("request") {
+            let key = unique_name(slugify(&path.join("_")), |candidate| self.requests.contains_key(candidate));
+            if let Some(converted) = self.convert_request(request, &label) {
+                self.requests.insert(key, converted);
+            }
+        }
+    }
+
+    fn convert_request(&mut self, request: &Value, label: &str) -> Option<RawRequest> {
+        let mut dynamic = Vec::new();
+
+        // A request may be given as a bare URL string
+        let (method, url) = match request {
+            Value::String(url) => ("GET".to_string(), Value::String(url.clone())),
+            _ => (
+                request.get("method").and_then(Value::as_str).unwrap_or("GET").to_uppercase(),
+                request.get("url").cloned().unwrap_or(Value::Null),
+            ),
+        };
+
+        let raw_url = match &url {
+            Value::String(raw) => raw.clone(),
+            Value::Object(_) => url.get("raw").and_then(Value::as_str).unwrap_or("").to_string(),
+            _ => String::new(),
+        };
+        if raw_url.is_empty() {
+            self.unsupported.push(format!("{}: request has no URL and was skipped", label));
+            return None;
+        }
+
+        // Query parameters from the structured URL, falling back to the raw string
+        let (raw_path, raw_query) = match raw_url.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (raw_url.clone(), None),
+        };
+        let mut query = HashMap::new();
+        match url.get("query").and_then(Value::as_array) {
+            Some(params) => {
+                for param in params {
+                    if let Some((key, value)) = variable_entry(param, "disabled", true) {
+                        query.insert(key, convert_template(&value, &mut dynamic));
+                    }
+                }
+            }
+            None => {
+                for pair in raw_query.iter().flat_map(|q| q.split('&')).filter(|p| !p.is_empty()) {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    query.insert(key.to_string(), convert_template(value, &mut dynamic));
+                }
+            }
+        }
+
+        // Path variables (`:id`) become `${vars.id}` with their example value as a profile default
+        for variable in array(url.get("variable")) {
+            if let Some((key, value)) = variable_entry(variable, "disabled", true) {
+                if !value.is_empty() {
+                    self.path_defaults.entry(sanitize_variable(&key)).or_insert(value);
+                }
+            }
+        }
+        let endpoint = self.relative_endpoint(&raw_path);
+        let endpoint = endpoint
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) if !name.is_empty() => format!("{{{{{}}}}}", name),
+                _ => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let endpoint = convert_template(&endpoint, &mut dynamic);
+
+        let mut headers = Vec::new();
+        for header in array(request.get("header")) {
+            if let Some((key, value)) = variable_entry(header, "disabled", true) {
+                headers.push(format!("{}:{}", key, convert_template(&value, &mut dynamic)));
+            }
+        }
+
+        if let Some(auth) = request.get("auth") {
+            self.convert_request_auth(auth, label, &mut headers);
+        }
+
+        let body = request.get("body").and_then(|body| self.convert_body(body, label, &mut headers));
+        let body = body.map(|b| convert_template(&b, &mut dynamic));
+
+        for name in dynamic {
+            self.unsupported.push(format!("{}: dynamic variable {{{{{}}}}} is not converted", label, name));
+        }
+
+        let description = match request.get("description") {
+            Some(Value::String(text)) => Some(text.clone()),
+            Some(other) => other.get("content").and_then(Value::as_str).map(String::from),
+            None => None,
+        };
+
+        Some(RawRequest {
+            description: description
+                .and_then(|d| d.lines().next().map(|l| l.trim().to_string()))
+                .filter(|d| !d.is_empty())
+                .or_else(|| Some(format!("Imported from Postman: {}", label))),
+            method: Some(method),
+            endpoint: if endpoint.is_empty() { "/".to_string() } else { endpoint },
+            headers: if headers.is_empty() { None } else { Some(headers) },
+            body,
+            body_file: None,
+            query: if query.is_empty() { None } else { Some(query) },
+            expect: None,
+        })
+    }
+
+    /// Strips the base URL variable or common origin from a raw URL
+    fn relative_endpoint(&self, raw_path: &str) -> String {
+        if let Some(ref variable) = self.base_variable {
+            if let Some(rest) = raw_path.strip_prefix(&format!("{{{{{}}}}}", variable)) {
+                return rest.to_string();
+            }
+        }
+        if let Some(ref origin) = self.origin {
+            if let Some(rest) = raw_path.strip_prefix(origin.as_str()) {
+                return rest.to_string();
+            }
+        }
+        raw_path.to_string()
+    }
+
+    fn convert_body(&mut self, body: &Value, label: &str, headers: &mut Vec<String>) -> Option<String> {
+        let has_content_type = headers.iter().any(|h| h.to_lowercase().starts_with("content-type:"));
+        match body.get("mode").and_then(Value::as_str) {
+            Some("raw") => {
+                let raw = body.get("raw").and_then(Value::as_str).unwrap_or("").to_string();
+                let language = body.pointer("/options/raw/language").and_then(Value::as_str);
+                let content_type = match language {
+                    Some("xml") => Some("application/xml"),
+                    Some("text") => Some("text/plain"),
+                    Some("html") => Some("text/html"),
+                    _ => None,
+                };
+                if let (Some(content_type), false) = (content_type, has_content_type) {
+                    headers.push(format!("Content-Type:{}", content_type));
+                }
+                if raw.is_empty() { None } else { Some(raw) }
+            }
+            Some("urlencoded") => {
+                let pairs: Vec<String> = array(body.get("urlencoded"))
+                    .filter_map(|p| variable_entry(p, "disabled", true))
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                if !has_content_type {
+                    headers.push("Content-Type:application/x-www-form-urlencoded".to_string());
+                }
+                Some(pairs.join("&"))
+            }
+            Some("graphql") => {
+                let query = body.pointer("/graphql/query").and_then(Value::as_str).unwrap_or("");
+                let variables = body.pointer("/graphql/variables").and_then(Value::as_str)
+                    .and_then(|v| serde_json::from_str::<Value>(v).ok())
+                    .unwrap_or(Value::Null);
+                Some(serde_json::json!({ "query": query, "variables": variables }).to_string())
+            }
+            Some(mode @ ("formdata" | "file")) => {
+                self.unsupported.push(format!("{}: '{}' body is not supported and was dropped", label, mode));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts collection auth into profile auth
+    fn convert_auth(&mut self, auth: &Value, label: &str) -> Option<RawAuth> {
+        let auth_type = auth.get("type").and_then(Value::as_str).unwrap_or("noauth");
+        let params = auth_params(auth, auth_type);
+        let param = |key: &str| params.get(key).cloned().unwrap_or_default();
+        let mut dynamic = Vec::new();
+
+        let converted = match auth_type {
+            "noauth" => None,
+            "bearer" => Some(RawAuth::Bearer {
+                token: convert_template(&param("token"), &mut dynamic),
+            }),
+            "basic" => Some(RawAuth::Basic {
+                username: convert_template(&param("username"), &mut dynamic),
+                password: Some(convert_template(&param("password"), &mut dynamic)),
+            }),
+            "apikey" => Some(RawAuth::ApiKey {
+                key: param("key"),
+                value: convert_template(&param("value"), &mut dynamic),
+                location: match param("in").as_str() {
+                    "query" => Some("query".to_string()),
+                    _ => None,
+                },
+            }),
+            other => {
+                self.unsupported.push(format!("{}: '{}' auth is not supported", label, other));
+                None
+            }
+        };
+
+        for name in dynamic {
+            self.unsupported.push(format!("{}: dynamic variable {{{{{}}}}} is not converted", label, name));
+        }
+        converted
+    }
+
+    /// Request-level auth can only be expressed as headers
+    fn convert_request_auth(&mut self, auth: &Value, label: &str, headers: &mut Vec<String>) {
+        let auth_type = auth.get("type").and_then(Value::as_str).unwrap_or("noauth");
+        let params = auth_params(auth, auth_type);
+        let mut dynamic = Vec::new();
+
+        match auth_type {
+            "bearer" => headers.push(format!(
+                "Authorization:Bearer {}",
+                convert_template(params.get("token").map(String::as_str).unwrap_or(""), &mut dynamic)
+            )),
+            "apikey" if params.get("in").map(String::as_str) != Some("query") => headers.push(format!(
+                "{}:{}",
+                params.get("key").cloned().unwrap_or_default(),
+                convert_template(params.get("value").map(String::as_str).unwrap_or(""), &mut dynamic)
+            )),
+            "noauth" => self.unsupported.push(format!("{}: 'noauth' cannot disable profile auth", label)),
+            other => self.unsupported.push(format!("{}: request-level '{}' auth is not supported", label, other)),
+        }
+    }
+
+    /// Scripts cannot be executed, so every pre-request and test script is reported
+    fn report_events(&mut self, item: &Value, label: &str) {
+        for event in array(item.get("event")) {
+            let has_script = array(event.pointer("/script/exec"))
+                .any(|line| line.as_str().is_some_and(|l| !l.trim().is_empty()));
+            if has_script {
+                let listen = event.get("listen").and_then(Value::as_str).unwrap_or("script");
+                self.unsupported.push(format!("{}: {} script is not supported", label, listen));
+            }
+        }
+    }
+}
+
+fn array(value: Option<&Value>) -> impl Iterator<Item = &Value> {
+    value.and_then(Value::as_array).into_iter().flatten()
+}
+
+/// Reads a `{key, value}` entry, honouring `disabled: true` or `enabled: false`
+fn variable_entry(entry: &Value, flag: &str, skip_when: bool) -> Option<(String, String)> {
+    if entry.get(flag).and_then(Value::as_bool) == Some(skip_when) {
+        return None;
+    }
+    let key = entry.get("key").and_then(Value::as_str)?;
+    let value = match entry.get("value") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    Some((key.to_string(), value))
+}
+
+/// Auth parameters are stored as `[{key, value}]` under the auth type name
+fn auth_params(auth: &Value, auth_type: &str) -> HashMap<String, String> {
+    array(auth.get(auth_type))
+        .filter_map(|p| {
+            let key = p.get("key").and_then(Value::as_str)?;
+            let value = match p.get("value") {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Finds the origin (`scheme://host[:port]`) of the first absolute request URL
+fn first_origin(collection: &Value) -> Option<String> {
+    fn walk(items: &Value) -> Option<String> {
+        for item in items.as_array()? {
+            if let Some(children) = item.get("item") {
+                if let Some(origin) = walk(children) {
+                    return Some(origin);
+                }
+                continue;
+            }
+            let raw = match item.get("request") {
+                Some(Value::String(url)) => url.as_str(),
+                Some(request) => request.pointer("/url/raw").and_then(Value::as_str)
+                    .or_else(|| request.get("url").and_then(Value::as_str))
+                    .unwrap_or(""),
+                None => "",
+            };
+            if let Some((scheme, rest)) = raw.split_once("://") {
+                if !scheme.contains("{{") {
+                    let host = rest.split(['/', '?']).next().unwrap_or("");
+                    return Some(format!("{}://{}", scheme, host));
+                }
+            }
+        }
+        None
+    }
+    walk(collection.get("item")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    fn collection() -> Value {
+        serde_json::json!({
+            "info": {
+                "name": "Users API",
+                "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+            },
+            "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "{{apiToken}}" }] },
+            "variable": [{ "key": "baseUrl", "value": "http://localhost:3000" }],
+            "item": [{
+                "name": "Users",
+                "item": [{
+                    "name": "Get User",
+                    "event": [{ "listen": "test", "script": { "exec": ["pm.test('ok')"] } }],
+                    "request": {
+                        "method": "GET",
+                        "header": [{ "key": "X-Tenant", "value": "{{tenant}}" }],
+                        "url": {
+                            "raw": "{{baseUrl}}/users/:id?expand=true",
+                            "query": [{ "key": "expand", "value": "true" }],
+                            "variable": [{ "key": "id", "value": "42" }]
+                        }
+                    }
+                }, {
+                    "name": "Create User",
+                    "request": {
+                        "method": "POST",
+                        "url": "{{baseUrl}}/users?ref=$5",
+                        "body": { "mode": "raw", "raw": "{\"name\":\"{{name}}\",\"cost\":\"$ 5\"}" }
+                    }
+                }]
+            }]
+        })
+    }
+
+    #[test]
+    fn test_convert_collection() {
+        let environment = serde_json::json!({
+            "name": "Staging",
+            "values": [
+                { "key": "baseUrl", "value": "https://staging.example.com", "enabled": true },
+                { "key": "apiToken", "value": "", "enabled": true }
+            ]
+        });
+
+        let imported = convert(&collection(), &[environment]).unwrap();
+        let config = imported.config;
+
+        let profile = &config.profiles["staging"];
+        assert_eq!(profile.url, "${vars.baseUrl}");
+        let vars = profile.vars.as_ref().unwrap();
+        assert_eq!(vars["baseUrl"], "https://staging.example.com");
+        assert_eq!(vars["id"], "42");
+        assert!(matches!(profile.auth, Some(RawAuth::Bearer { ref token }) if token == "${vars.apiToken}"));
+
+        let get_user = &config.requests["users_get_user"];
+        assert_eq!(get_user.endpoint, "/users/${vars.id}");
+        assert_eq!(get_user.query.as_ref().unwrap()["expand"], "true");
+        assert_eq!(get_user.headers.as_ref().unwrap(), &vec!["X-Tenant:${vars.tenant}".to_string()]);
+
+        let create_user = &config.requests["users_create_user"];
+        assert_eq!(create_user.method.as_deref(), Some("POST"));
+        assert_eq!(create_user.body.as_deref(), Some("{\"name\":\"${vars.name}\",\"cost\":\"$$ 5\"}"));
+        assert_eq!(ConfigManager::new().resolve_value(&create_user.query.as_ref().unwrap()["ref"]).unwrap(), "$5");
+
+        assert_eq!(imported.unsupported, vec!["Users / Get User: test script is not supported".to_string()]);
+    }
+
+    #[test]
+    fn test_profile_from_collection_without_environment() {
+        let imported = convert(&collection(), &[]).unwrap();
+        assert_eq!(imported.config.default_profile.as_deref(), Some("users_api"));
+        assert!(imported.config.profiles.contains_key("users_api"));
+    }
+
+    #[test]
+    fn test_rejects_non_collection() {
+        assert!(convert(&serde_json::json!({ "info": {} }), &[]).is_err());
+    }
+}
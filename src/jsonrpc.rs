@@ -0,0 +1,244 @@
+use crate::error::{AsteriskError, Result};
+use serde_json::{json, Map, Value};
+
+/// Hands out request ids, starting at 1 for each invocation
+#[derive(Debug, Default)]
+pub struct Ids(u64);
+
+impl Ids {
+    pub fn next(&mut self) -> u64 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+/// An error object from a JSON-RPC response
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    /// Method of the call that failed, when it could be matched by id
+    pub method: Option<String>,
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl From<RpcError> for AsteriskError {
+    fn from(error: RpcError) -> Self {
+        AsteriskError::JsonRpc { code: error.code, message: error.message }
+    }
+}
+
+/// Name of a code reserved by the specification
+pub fn code_name(code: i64) -> &'static str {
+    match code {
+        -32700 => "parse error",
+        -32600 => "invalid request",
+        -32601 => "method not found",
+        -32602 => "invalid params",
+        -32603 => "internal error",
+        -32099..=-32000 => "server error",
+        _ => "application error",
+    }
+}
+
+/// Parses call parameters: `key=value` pairs give named params, bare values positional ones
+/// Values that are valid JSON keep their type. Returns None when there are no parameters.
+pub fn parse_params(args: &[String]) -> Result<Option<Value>> {
+    if args.is_empty() {
+        return Ok(None);
+    }
+    let typed = |value: &str| serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+
+    // Anything that parses as JSON on its own (`'{"a":1}'`, `2`, `"x=y"`) is positional
+    let is_named = |arg: &String| serde_json::from_str::<Value>(arg).is_err() && arg.contains('=');
+    if args.iter().all(is_named) {
+        let mut params = Map::new();
+        for arg in args {
+            let (key, value) = arg.split_once('=').unwrap_or((arg, ""));
+            params.insert(key.to_string(), typed(value));
+        }
+        Ok(Some(Value::Object(params)))
+    } else if args.iter().any(is_named) {
+        Err(AsteriskError::Config(
+            "Parameters must be all key=value (named) or all plain values (positional)".to_string()
+        ))
+    } else {
+        Ok(Some(Value::Array(args.iter().map(|arg| typed(arg)).collect())))
+    }
+}
+
+/// Builds a request envelope
+pub fn envelope(method: &str, params: Option<Value>, id: u64) -> Value {
+    let mut request = json!({ "jsonrpc": "2.0", "method": method });
+    if let Some(params) = params {
+        request["params"] = params;
+    }
+    request["id"] = json!(id);
+    request
+}
+
+/// Builds a batch from a file holding an array of `{"method", "params"}` calls
+/// Calls without an `id` are numbered; an explicit `"id": null` sends a notification.
+pub fn batch(content: &str, ids: &mut Ids) -> Result<Value> {
+    let calls = match serde_json::from_str(content) {
+        Ok(Value::Array(calls)) if !calls.is_empty() => calls,
+        Ok(Value::Object(call)) => vec![Value::Object(call)],
+        Ok(_) => return Err(AsteriskError::Config("Batch file must contain a non-empty array of calls".to_string())),
+        Err(e) => return Err(AsteriskError::Config(format!("Invalid batch file: {}", e))),
+    };
+
+    let requests = calls.into_iter().enumerate()
+        .map(|(index, call)| {
+            let Value::Object(mut call) = call else {
+                return Err(AsteriskError::Config(format!("Batch call {} is not an object", index + 1)));
+            };
+            let method = call.get("method").and_then(Value::as_str).map(String::from).ok_or_else(|| AsteriskError::Config(
+                format!("Batch call {} has no method", index + 1)
+            ))?;
+            let mut request = json!({ "jsonrpc": "2.0", "method": method });
+            if let Some(params) = call.remove("params") {
+                request["params"] = params;
+            }
+            match call.remove("id") {
+                Some(Value::Null) => {}
+                Some(id) => request["id"] = id,
+                None => request["id"] = json!(ids.next()),
+            }
+            Ok(request)
+        })
+        .collect::<Result<Vec<Value>>>()?;
+    Ok(Value::Array(requests))
+}
+
+/// Collects the errors of an HTTP response to `request`
+/// Servers often send JSON-RPC errors with an HTTP error status, so the body is read first;
+/// the status is only reported when the body is not a JSON-RPC response.
+pub fn response_errors(request: &Value, status: u16, body: &str) -> Result<Vec<RpcError>> {
+    let http_error = || AsteriskError::HttpStatus { request: "JSON-RPC request".to_string(), status };
+    // A batch of notifications gets no response body at all
+    if body.trim().is_empty() {
+        return if status < 300 { Ok(Vec::new()) } else { Err(http_error()) };
+    }
+    let errors = serde_json::from_str(body)
+        .map_err(|_| AsteriskError::Config("Response is not a JSON-RPC response".to_string()))
+        .and_then(|response| errors(request, &response));
+    match errors {
+        Ok(errors) if errors.is_empty() && status >= 300 => Err(http_error()),
+        Err(_) if status >= 300 => Err(http_error()),
+        errors => errors,
+    }
+}
+
+/// Collects the error objects of a response to `request` (a call or a batch)
+/// Fails when the response is not a JSON-RPC response at all.
+pub fn errors(request: &Value, response: &Value) -> Result<Vec<RpcError>> {
+    let responses = match response {
+        Value::Array(responses) => responses.iter().collect(),
+        Value::Object(_) => vec![response],
+        _ => return Err(AsteriskError::Config("Response is not a JSON-RPC response".to_string())),
+    };
+    let calls: Vec<&Value> = match request {
+        Value::Array(calls) => calls.iter().collect(),
+        call => vec![call],
+    };
+    let method_for = |id: &Value| {
+        calls.iter()
+            .find(|call| call.get("id") == Some(id))
+            .and_then(|call| call.get("method"))
+            .and_then(Value::as_str)
+            .map(String::from)
+    };
+
+    let mut errors = Vec::new();
+    for response in responses {
+        if response.get("jsonrpc").and_then(Value::as_str) != Some("2.0")
+            || (response.get("result").is_none() && response.get("error").is_none())
+        {
+            return Err(AsteriskError::Config("Response is not a JSON-RPC 2.0 response".to_string()));
+        }
+        let Some(error) = response.get("error") else {
+            continue;
+        };
+        errors.push(RpcError {
+            method: response.get("id").and_then(method_for),
+            code: error.get("code").and_then(Value::as_i64).unwrap_or(0),
+            message: error.get("message").and_then(Value::as_str).unwrap_or("(no message)").to_string(),
+            data: error.get("data").cloned(),
+        });
+    }
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_params() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_params(&[]).unwrap(), None);
+        assert_eq!(
+            parse_params(&args(&["name=john", "age=30", "tags=[\"a\"]"])).unwrap(),
+            Some(json!({ "name": "john", "age": 30, "tags": ["a"] }))
+        );
+        assert_eq!(parse_params(&args(&["1", "two", "{\"a\":\"b=c\"}"])).unwrap(), Some(json!([1, "two", { "a": "b=c" }])));
+        assert!(parse_params(&args(&["1", "name=john"])).is_err());
+    }
+
+    #[test]
+    fn test_envelopes_and_batch() {
+        let mut ids = Ids::default();
+        assert_eq!(
+            envelope("eth_blockNumber", None, ids.next()),
+            json!({ "jsonrpc": "2.0", "method": "eth_blockNumber", "id": 1 })
+        );
+
+        let batch = batch(r#"[
+            { "method": "sum", "params": [1, 2] },
+            { "method": "notify_hello", "params": [7], "id": null },
+            { "method": "get_data", "id": "custom" },
+            { "method": "subtract", "params": { "a": 5 } }
+        ]"#, &mut ids).unwrap();
+        assert_eq!(batch, json!([
+            { "jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 2 },
+            { "jsonrpc": "2.0", "method": "notify_hello", "params": [7] },
+            { "jsonrpc": "2.0", "method": "get_data", "id": "custom" },
+            { "jsonrpc": "2.0", "method": "subtract", "params": { "a": 5 }, "id": 3 },
+        ]));
+        assert!(super::batch("[]", &mut ids).is_err());
+        assert!(super::batch("[{ \"params\": [] }]", &mut ids).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let request = json!([
+            { "jsonrpc": "2.0", "method": "sum", "id": 1 },
+            { "jsonrpc": "2.0", "method": "foo.get", "id": 2 },
+        ]);
+        let response = json!([
+            { "jsonrpc": "2.0", "error": { "code": -32601, "message": "Method not found" }, "id": 2 },
+            { "jsonrpc": "2.0", "result": 7, "id": 1 },
+        ]);
+        assert_eq!(errors(&request, &response).unwrap(), vec![RpcError {
+            method: Some("foo.get".to_string()),
+            code: -32601,
+            message: "Method not found".to_string(),
+            data: None,
+        }]);
+        assert!(errors(&request, &json!({ "result": 1 })).is_err());
+        assert_eq!(code_name(-32001), "server error");
+    }
+
+    #[test]
+    fn test_response_errors() {
+        let request = json!({ "jsonrpc": "2.0", "method": "sum", "id": 1 });
+        let error = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params"},"id":1}"#;
+        let errors = response_errors(&request, 500, error).unwrap();
+        assert_eq!((errors[0].code, errors[0].method.as_deref()), (-32602, Some("sum")));
+
+        // Only a body that is not JSON-RPC falls back to the status
+        assert!(matches!(response_errors(&request, 502, "<html>Bad Gateway</html>"), Err(AsteriskError::HttpStatus { status: 502, .. })));
+        assert!(matches!(response_errors(&request, 200, "<html>"), Err(AsteriskError::Config(_))));
+        assert!(response_errors(&request, 204, "").unwrap().is_empty());
+    }
+}
@@ -1,4 +1,13 @@
+use crate::contract::ContractResult;
+use crate::http::{Download, HttpResponse};
+use crate::har;
+use crate::jsonrpc::{self, RpcError};
+use crate::render;
+use crate::stream::SseEvent;
 use colored::*;
+use reqwest::header::CONTENT_ENCODING;
+use std::time::{Duration, SystemTime};
+use tokio_tungstenite::tungstenite::Message;
 
 pub struct Logger {
     verbose: bool,
@@ -9,28 +18,267 @@ impl Logger {
         Logger { verbose }
     }
 
-    pub fn response(&self, status: u16, timing: &str, body: &str) {
-        let status_color = match status {
-            200..=299 => status.to_string().green(),
-            300..=399 => status.to_string().yellow(),
-            _ => status.to_string().red(),
+    pub fn response(&self, response: &HttpResponse) {
+        let status = response.status;
+        let status_color = self.status_color(status);
+        let content_type = response.content_type();
+        let body = if response.is_encoded() {
+            render::hex_dump(&response.bytes, &render::options())
+        } else {
+            render::render_body(content_type.as_deref(), &response.bytes, &response.body, &render::options())
         };
 
+        let mut output = String::new();
         if self.verbose {
-            println!("\n{}", "Response Details:".bold());
-            println!("{} {}", "Status Code:".bold(), status_color);
-            println!("{} {}", "Status Text:".bold(), self.status_text(status));
-            println!("{} {}", "Performance:".bold(), timing);
-            println!("\n{}", "Response Body:".bold());
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
-                println!("{}", serde_json::to_string_pretty(&json).unwrap_or_else(|_| body.to_string()));
+            output.push_str(&format!("\n{}\n", "Response Details:".bold()));
+            output.push_str(&format!("{} {}\n", "Status Code:".bold(), status_color));
+            output.push_str(&format!("{} {}\n", "Status Text:".bold(), self.status_text(status)));
+            output.push_str(&format!("{} {}\n", "Performance:".bold(), response.timing));
+            if let Some(ref content_type) = content_type {
+                output.push_str(&format!("{} {}\n", "Content-Type:".bold(), content_type));
+            }
+            if let Some(encoding) = self.encoding(response) {
+                output.push_str(&format!("{} {}\n", "Content-Encoding:".bold(), encoding));
+            }
+            output.push_str(&format!("\n{}\n{}\n", "Response Body:".bold(), body));
+        } else {
+            output.push_str(&format!("\n{} {}\n", "Status:".bold(), status_color));
+            output.push_str(&format!("{} {}\n", "Timing:".bold(), response.timing));
+            if body.contains('\n') {
+                output.push_str(&format!("{}\n{}\n", "Response:".bold(), body));
             } else {
-                println!("{}", body);
+                output.push_str(&format!("{} {}\n", "Response:".bold(), body));
+            }
+        }
+        render::print_paged(&output);
+    }
+
+    pub fn download(&self, response: &HttpResponse, download: &Download) {
+        let size = if download.written == 0 && download.resumed_from > 0 {
+            format!("already complete, {}", render::format_size(download.resumed_from))
+        } else if download.resumed_from > 0 {
+            format!(
+                "{} written, resumed at {}",
+                render::format_size(download.written),
+                render::format_size(download.resumed_from)
+            )
+        } else {
+            format!("{} written", render::format_size(download.written))
+        };
+
+        println!("\n{} {}", "Status:".bold(), self.status_color(response.status));
+        println!("{} {}", "Timing:".bold(), response.timing);
+        if self.verbose {
+            if let Some(content_type) = response.content_type() {
+                println!("{} {}", "Content-Type:".bold(), content_type);
+            }
+        }
+        println!("{} {} ({})", "Saved:".bold(), download.path.display(), size);
+    }
+
+    pub fn stream_opened(&self, status: u16, content_type: &str) {
+        println!("\n{} {} {}", "Status:".bold(), self.status_color(status), content_type.dimmed());
+        if self.verbose {
+            println!("{}", "Streaming response (Ctrl-C to stop)".italic());
+        }
+        println!();
+    }
+
+    pub fn event(&self, event: &SseEvent) {
+        let mut label = event.event.clone().unwrap_or_else(|| "message".to_string()).cyan().to_string();
+        if let Some(ref id) = event.id {
+            label.push_str(&format!(" {}", format!("id={}", id).dimmed()));
+        }
+        self.stream_item(&label, &event.data);
+    }
+
+    pub fn chunk(&self, text: &str) {
+        self.stream_item("", text);
+    }
+
+    /// Prints a streamed item inline, or rendered by content in verbose mode
+    fn stream_item(&self, label: &str, data: &str) {
+        let mut prefix = self.timestamp();
+        if !label.is_empty() {
+            prefix = format!("{} {}", prefix, label);
+        }
+        prefix.push(' ');
+        let data = if self.verbose {
+            render::render_body(None, data.as_bytes(), data, &render::options())
+        } else {
+            data.trim_end().to_string()
+        };
+        if data.contains('\n') {
+            println!("{}\n{}", prefix.trim_end(), data);
+        } else {
+            println!("{}{}", prefix, data);
+        }
+    }
+
+    pub fn reconnecting(&self, reason: &str, delay: Duration, last_event_id: Option<&str>) {
+        let resume = last_event_id.map(|id| format!(" from id {}", id)).unwrap_or_default();
+        println!("{} {}", self.timestamp(), format!("{}, reconnecting in {}ms{}", reason, delay.as_millis(), resume).yellow());
+    }
+
+    pub fn stream_closed(&self, received: usize, reason: &str) {
+        println!("\n{} {} received, {}", "Stream:".bold(), received, reason);
+    }
+
+    fn timestamp(&self) -> String {
+        // HH:MM:SS.mmm of the UTC timestamp
+        format!("[{}]", &har::iso8601(SystemTime::now())[11..23]).dimmed().to_string()
+    }
+
+    pub fn ws_connected(&self, url: &str, status: u16) {
+        println!("{} {} ({})", "Connected:".bold(), url, status.to_string().green());
+    }
+
+    pub fn ws_prompt_help(&self) {
+        println!("{}", "Type a message and press Enter to send. /ping [text], /close [code] [reason], /quit".italic());
+    }
+
+    pub fn ws_sent(&self, text: &str) {
+        println!("{} {} {}", self.timestamp(), "→".green(), text);
+    }
+
+    pub fn ws_sent_control(&self, kind: &str, payload: &str) {
+        println!("{} {} {} {}", self.timestamp(), "→".green(), kind.magenta(), payload.trim());
+    }
+
+    pub fn ws_received(&self, message: &Message) {
+        let arrow = "←".cyan();
+        match message {
+            Message::Text(text) => {
+                let rendered = render::render_body(None, text.as_bytes(), text, &render::options());
+                if rendered.contains('\n') {
+                    println!("{} {}\n{}", self.timestamp(), arrow, rendered);
+                } else {
+                    println!("{} {} {}", self.timestamp(), arrow, rendered);
+                }
+            }
+            Message::Binary(bytes) => {
+                let kind = render::describe_magic(bytes).map(|kind| format!(" ({})", kind)).unwrap_or_default();
+                let label = format!("binary {}{}", render::format_size(bytes.len() as u64), kind);
+                println!("{} {} {}", self.timestamp(), arrow, label.magenta());
+                if self.verbose {
+                    println!("{}", render::hex_dump(bytes, &render::options()));
+                }
+            }
+            Message::Ping(payload) => {
+                println!("{} {} {} {}", self.timestamp(), arrow, "ping".magenta(), String::from_utf8_lossy(payload));
             }
+            Message::Pong(payload) => {
+                println!("{} {} {} {}", self.timestamp(), arrow, "pong".magenta(), String::from_utf8_lossy(payload));
+            }
+            Message::Close(_) | Message::Frame(_) => {}
+        }
+    }
+
+    pub fn ws_closed(&self, code: u16, reason: &str) {
+        let description = match code {
+            1000 => "normal closure",
+            1001 => "going away",
+            1002 => "protocol error",
+            1003 => "unsupported data",
+            1005 => "no status code",
+            1006 => "abnormal closure",
+            1007 => "invalid payload",
+            1008 => "policy violation",
+            1009 => "message too big",
+            1010 => "missing extension",
+            1011 => "internal server error",
+            1015 => "TLS handshake failure",
+            4000..=4999 => "application defined",
+            _ => "unknown",
+        };
+        let code_color = match code {
+            1000 | 1001 | 1005 => code.to_string().green(),
+            _ => code.to_string().red(),
+        };
+        let reason = if reason.is_empty() { String::new() } else { format!(": {}", reason) };
+        println!("{} {} {}{}", "Closed:".bold(), code_color, description, reason);
+    }
+
+    pub fn expectations(&self, failures: &[String]) {
+        if failures.is_empty() {
+            println!("{} {}", "Expectations:".bold(), "passed".green());
         } else {
-            println!("\n{} {}", "Status:".bold(), status_color);
-            println!("{} {}", "Timing:".bold(), timing);
-            println!("{} {}", "Response:".bold(), body);
+            println!("{} {}", "Expectations:".bold(), "failed".red());
+            for failure in failures {
+                println!("  {} {}", "✗".red(), failure);
+            }
+        }
+    }
+
+    pub fn graphql_errors(&self, errors: &[String]) {
+        println!("{} {}", "GraphQL errors:".bold(), errors.len().to_string().red());
+        for error in errors {
+            println!("  {} {}", "✗".red(), error.red());
+        }
+    }
+
+    pub fn rpc_errors(&self, errors: &[RpcError]) {
+        if errors.is_empty() {
+            return;
+        }
+        println!("{} {}", "RPC errors:".bold(), errors.len().to_string().red());
+        for error in errors {
+            let method = error.method.as_ref().map(|method| format!("{} ", method.bold())).unwrap_or_default();
+            println!(
+                "  {} {}{} {}: {}",
+                "✗".red(),
+                method,
+                error.code.to_string().red(),
+                jsonrpc::code_name(error.code),
+                error.message.red()
+            );
+            if let Some(ref data) = error.data {
+                println!("    {}", data);
+            }
+        }
+    }
+
+    pub fn contract(&self, result: &ContractResult) {
+        match result {
+            ContractResult::Unmatched => {
+                if self.verbose {
+                    println!("{} {}", "Contract:".bold(), "no matching operation".italic());
+                }
+            }
+            ContractResult::Valid { operation } => {
+                println!("{} {} {}", "Contract:".bold(), operation, "valid".green());
+            }
+            ContractResult::Invalid { operation, violations } => {
+                println!("{} {} {}", "Contract:".bold(), operation, format!("{} violation(s)", violations.len()).red());
+                for violation in violations {
+                    println!("  {} {} {}", "✗".red(), violation.pointer.bold(), violation.message);
+                }
+            }
+            ContractResult::SpecError(error) => {
+                println!("{} {} {}", "Contract:".bold(), "not checked:".yellow(), error);
+            }
+        }
+    }
+
+    /// Describes the Content-Encoding with the compressed and decompressed sizes
+    fn encoding(&self, response: &HttpResponse) -> Option<String> {
+        let coding = response.headers.get(CONTENT_ENCODING)?.to_str().ok()?;
+        Some(match response.encoded_size {
+            Some(encoded) => format!(
+                "{} ({} on the wire, {} decoded)",
+                coding,
+                render::format_size(encoded as u64),
+                render::format_size(response.bytes.len() as u64)
+            ),
+            None => format!("{} ({}, not decoded)", coding, render::format_size(response.bytes.len() as u64)),
+        })
+    }
+
+    fn status_color(&self, status: u16) -> ColoredString {
+        match status {
+            200..=299 => status.to_string().green(),
+            300..=399 => status.to_string().yellow(),
+            _ => status.to_string().red(),
         }
     }
 
@@ -1,25 +1,70 @@
+mod builtins;
 mod cli;
+mod compression;
 mod config;
+mod contract;
 mod environment;
 mod error;
+mod export;
+mod graphql;
+mod har;
 mod http;
+mod httpfile;
+mod import;
+mod jsonrpc;
 mod logger;
+mod openapi;
+mod render;
+mod request;
+mod secrets;
+mod stream;
+mod websocket;
 
-use cli::{Cli, Commands, ConfigAction};
+use cli::{Cli, Commands, ConfigAction, ImportSource, RequestOverrides, SecretAction};
 use config::ConfigManager;
+use contract::{ContractResult, ContractValidator};
 use error::AsteriskError;
 use http::HttpClient;
+use httpfile::{HttpFile, Interpolator};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use logger::Logger;
+use request::PreparedRequest;
 use colored::*;
 
 #[tokio::main]
 async fn main() -> Result<(), AsteriskError> {
     let cli = Cli::new();
     let mut config_manager = ConfigManager::new();
+    config_manager.set_location(config::ConfigLocation::detect(cli.config.as_deref()));
+    render::configure(render::RenderOptions::detect(cli.sort_keys, cli.no_pager));
+    compression::configure(compression::EncodingOptions {
+        raw: cli.raw_encoding,
+        compress_body: cli.compress_body,
+    });
 
+    let har_path = cli.har.clone();
+    let recorder = har_path.as_ref().map(|_| har::Recorder::new(cli.har_keep_secrets));
+    let http_client = HttpClient::new().with_recorder(recorder.clone());
+
+    let result = run(cli, &mut config_manager, &http_client).await;
+
+    // The archive is written even when a request or expectation failed, whose error wins
+    if let (Some(path), Some(recorder)) = (har_path, recorder) {
+        match recorder.write(std::path::Path::new(&path)) {
+            Ok(count) => println!("{} {} ({} entries)", "HAR:".bold(), path, count),
+            Err(e) if result.is_err() => eprintln!("{} failed to write {}: {}", "HAR:".bold(), path, e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    result
+}
+
+/// Runs a subcommand or a single request
+async fn run(cli: Cli, config_manager: &mut ConfigManager, http_client: &HttpClient) -> Result<(), AsteriskError> {
     // Handle subcommands first
     if let Some(command) = cli.command {
-        return handle_command(command, &mut config_manager).await;
+        return handle_command(command, config_manager, http_client).await;
     }
 
     // Ensure we have endpoint and method for HTTP requests
@@ -32,7 +77,8 @@ async fn main() -> Result<(), AsteriskError> {
     })?;
 
     // Load and resolve configuration
-    let resolved_config = config_manager.load_resolved_config(
+    config_manager.set_variable_overrides(parse_key_values(&cli.vars)?);
+    let mut resolved_config = config_manager.load_resolved_config(
         cli.profile.as_deref(),
         cli.url.as_deref(),
         cli.token.as_deref(),
@@ -40,37 +86,117 @@ async fn main() -> Result<(), AsteriskError> {
         cli.verbose,
     )?;
 
+    // Build final URL
+    let endpoint = config_manager.resolve_inline(&endpoint)?;
+    let url = request::target_url(&mut resolved_config, &endpoint)?;
+
+    let prepared = PreparedRequest {
+        method,
+        url,
+        headers: resolved_config.headers,
+        body: cli.body.map(|body| config_manager.resolve_inline(&body)).transpose()?,
+        verbose: resolved_config.verbose,
+        secrets: resolved_config.secrets,
+    };
+    http_client.mask_secrets(&prepared.secrets);
+
+    if let Some(format) = cli.export_as {
+        print!("{}", export::render(format, &prepared, cli.mask_secrets));
+        return Ok(());
+    }
+
     // Initialize components
-    let logger = Logger::new(resolved_config.verbose);
-    let http_client = HttpClient::new();
+    let logger = Logger::new(prepared.verbose);
 
-    // Build final URL
-    let url = build_request_url(&resolved_config.url, &endpoint);
+    if cli.streaming.stream {
+        return stream::run(http_client, &prepared, &(&cli.streaming).into(), &logger).await;
+    }
 
     // Send request
-    let (status, body, timing) = http_client
-        .send_request(&url, &method, resolved_config.headers, cli.body)
-        .await?;
+    let (method, url) = (prepared.method.clone(), prepared.url.clone());
+    let response = send_prepared(http_client, prepared, cli.output.as_deref(), cli.resume, &logger).await?;
 
-    logger.response(status, &timing, &body);
+    if let Some(contract) = check_contract(contract_validator(config_manager).as_ref(), &method, &url, &response) {
+        logger.contract(&contract);
+    }
 
     Ok(())
 }
 
+/// Loads the configured OpenAPI spec for contract checks, once a response is in
+/// A spec that fails to load is kept as its error, so the check fails rather than the request.
+fn contract_validator(config_manager: &ConfigManager) -> Option<Result<ContractValidator, String>> {
+    config_manager.openapi_spec().transpose()
+        .map(|spec| spec.map(ContractValidator::new).map_err(|e| e.to_string()))
+}
+
+/// Checks a response against the spec from `contract_validator`; `None` without a spec
+fn check_contract(
+    validator: Option<&Result<ContractValidator, String>>,
+    method: &str,
+    url: &str,
+    response: &http::HttpResponse,
+) -> Option<ContractResult> {
+    Some(match validator? {
+        Ok(validator) => validator.validate(method, url, response),
+        Err(error) => ContractResult::SpecError(error.clone()),
+    })
+}
+
+/// Sends a prepared request and logs the response
+/// With `-o` a successful body is saved instead, and the response has its status and
+/// headers but no body (`saved_to` is set).
+async fn send_prepared(
+    http_client: &HttpClient,
+    prepared: PreparedRequest,
+    output: Option<&str>,
+    resume: bool,
+    logger: &Logger,
+) -> Result<http::HttpResponse, AsteriskError> {
+    let Some(output) = output else {
+        let response = http_client
+            .send_request(&prepared.url, &prepared.method, prepared.headers, prepared.body)
+            .await?;
+        logger.response(&response);
+        return Ok(response);
+    };
+
+    let (response, download) = http_client
+        .download(&prepared.url, &prepared.method, prepared.headers, prepared.body, std::path::Path::new(output), resume)
+        .await?;
+    match download {
+        Some(download) => logger.download(&response, &download),
+        None => {
+            println!("{} {} was not written (HTTP {})", "Output:".bold(), output, response.status);
+            logger.response(&response);
+        }
+    }
+    Ok(response)
+}
+
 /// Handles configuration subcommands
-async fn handle_command(command: Commands, config_manager: &mut ConfigManager) -> Result<(), AsteriskError> {
+async fn handle_command(
+    command: Commands,
+    config_manager: &mut ConfigManager,
+    http_client: &HttpClient,
+) -> Result<(), AsteriskError> {
     match command {
         Commands::Config { action } => {
             match action {
-                ConfigAction::Show { profile } => {
-                    let config_display = config_manager.format_current_config(profile.as_deref())?;
+                ConfigAction::Show { profile, origin, vars } => {
+                    config_manager.set_variable_overrides(parse_key_values(&vars)?);
+                    let config_display = config_manager.format_current_config(profile.as_deref(), origin)?;
                     
                     println!("{}", "Current Configuration:".bold().bright_cyan());
-                    if let Some(config_path) = config_manager.find_config_file() {
-                        println!("{} {}", "Config file:".bold(), config_path.display());
-                    } else {
+                    let sources = config_manager.config_sources();
+                    if sources.is_empty() {
                         println!("{}", "Config file: None (using defaults)".italic());
                     }
+                    // Listed from lowest to highest precedence
+                    for source in sources {
+                        println!("{} {} ({})", "Config file:".bold(), source.path.display(), source.layer.name());
+                    }
+                    println!("{} {}", "Located:".bold(), config_manager.location().describe());
                     println!();
                     print!("{}", config_display);
                 }
@@ -78,6 +204,15 @@ async fn handle_command(command: Commands, config_manager: &mut ConfigManager) -
                     let config_path = config_manager.create_default_config()?;
                     println!("{}", "✅ Configuration initialized!".bright_green());
                     println!("{} {}", "Created:".bold(), config_path.display());
+                    if let Some(gitignore) = config_manager.local_config_gitignore()? {
+                        if confirm(&format!("Add asterisk.local.config to {}?", gitignore.display()))? {
+                            config_manager.ignore_local_config()?;
+                            println!("{} asterisk.local.config to {}", "Ignored:".bold(), gitignore.display());
+                        } else {
+                            println!("{} add asterisk.local.config to {} to keep personal settings out of git",
+                                "Note:".bold().yellow(), gitignore.display());
+                        }
+                    }
                     println!();
                     println!("{}", "Next steps:".bold());
                     println!("1. Edit {} to configure your API endpoints", config_path.display());
@@ -86,17 +221,539 @@ async fn handle_command(command: Commands, config_manager: &mut ConfigManager) -
                 }
             }
         }
+        Commands::Secret { action } => {
+            match action {
+                SecretAction::Set { setting, value } => {
+                    let value = match value {
+                        Some(value) => value,
+                        None => read_secret_value(&setting)?,
+                    };
+                    let update = config_manager.set_secret(&setting, &value)?;
+                    println!("{}", "✅ Secret encrypted!".bright_green());
+                    println!("{} {} in {}", "Updated:".bold(), setting, update.path.display());
+                    if let Some(key_file) = update.created_key {
+                        println!("{} {} (share it out of band, never commit it)", "Created key:".bold(), key_file.display());
+                    }
+                    if let Some(gitignore) = update.ignored_in {
+                        println!("{} asterisk.key* to {}", "Ignored:".bold(), gitignore.display());
+                    }
+                }
+                SecretAction::RotateKey => {
+                    let rotation = config_manager.rotate_secret_key()?;
+                    println!("{}", "✅ Key rotated!".bright_green());
+                    for (path, count) in &rotation.files {
+                        println!("{} {} value(s) in {}", "Re-encrypted:".bold(), count, path.display());
+                    }
+                    for path in &rotation.skipped {
+                        println!("{} {} uses the key file next to it and was not rotated", "Note:".bold().yellow(), path.display());
+                    }
+                    match rotation.key_file {
+                        Some(key_file) => println!(
+                            "{} {} (the previous key is kept as {}.old)", "New key:".bold(), key_file.display(), key_file.display()
+                        ),
+                        None => println!("{} ASTERISK_SECRET_PASSPHRASE to the new passphrase", "Next:".bold()),
+                    }
+                }
+            }
+        }
+        Commands::Run { name, params, list, overrides } => {
+            if list {
+                return list_saved_requests(config_manager);
+            }
+
+            // Positional variables win over --var
+            let mut variables = parse_key_values(&overrides.vars)?;
+            variables.extend(parse_key_values(&params)?);
+            config_manager.set_variable_overrides(variables);
+
+            let name = name.ok_or_else(|| {
+                AsteriskError::Config("NAME is required to run a saved request".to_string())
+            })?;
+            return run_saved_request(&name, &overrides, config_manager, http_client).await;
+        }
+        Commands::File { path, name, line, list, profile, vars, url, token, verbose } => {
+            let file = HttpFile::load(std::path::Path::new(&path))?;
+
+            if list {
+                list_http_file_requests(&file);
+                return Ok(());
+            }
+
+            config_manager.set_variable_overrides(parse_key_values(&vars)?);
+            let resolved_config = config_manager.load_resolved_config(
+                profile.as_deref(),
+                url.as_deref(),
+                token.as_deref(),
+                None,
+                verbose,
+            )?;
+            return run_http_file(&file, name.as_deref(), line, &resolved_config, config_manager, http_client).await;
+        }
+        Commands::Ops { spec } => {
+            let spec = match spec {
+                Some(path) => openapi::OpenApiSpec::load(std::path::Path::new(&path))?,
+                None => config_manager.openapi_spec()?.ok_or_else(|| AsteriskError::Config(
+                    "No OpenAPI spec configured. Run 'asterisk import openapi <spec>' or pass --spec".to_string()
+                ))?,
+            };
+            list_operations(&spec);
+        }
+        Commands::Import { source } => {
+            let (imported, source_name, dry_run) = match source {
+                ImportSource::Postman { collection, env, dry_run } => {
+                    let collection_json = read_json_file(&collection)?;
+                    let environments = env.iter()
+                        .map(|path| read_json_file(path))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    (import::postman::convert(&collection_json, &environments)?, collection, dry_run)
+                }
+                ImportSource::Har { file, dry_run } => {
+                    let archive = read_json_file(&file)?;
+                    let has_config = config_manager.find_config_file().is_some();
+                    let profile_url = |url: &str| config_manager.match_profile(url).ok().flatten().map(|(_, base)| base);
+                    (import::har::convert(&archive, profile_url, has_config)?, file, dry_run)
+                }
+                ImportSource::Openapi { spec, dry_run } => {
+                    let parsed = openapi::OpenApiSpec::load(std::path::Path::new(&spec))?;
+                    (import::openapi::convert(&parsed, &spec), spec, dry_run)
+                }
+            };
+            return write_import(imported, &source_name, dry_run, config_manager);
+        }
+        Commands::Ws { endpoint, message, file, wait, profile, vars, url, token, headers, verbose } => {
+            config_manager.set_variable_overrides(parse_key_values(&vars)?);
+            let mut resolved_config = config_manager.load_resolved_config(
+                profile.as_deref(),
+                url.as_deref(),
+                token.as_deref(),
+                headers.as_deref(),
+                verbose,
+            )?;
+            let endpoint = config_manager.resolve_inline(&endpoint)?;
+            let url = request::target_url(&mut resolved_config, &endpoint)?;
+
+            let mut messages = message;
+            if let Some(ref path) = file {
+                messages.extend(websocket::read_messages(path)?);
+            }
+            let input = if file.is_none() && messages.is_empty() && websocket::stdin_is_interactive() {
+                websocket::Input::Interactive
+            } else {
+                if file.is_none() && messages.is_empty() {
+                    messages = websocket::read_messages("-")?;
+                }
+                websocket::Input::Scripted { messages, wait: std::time::Duration::from_secs_f64(wait) }
+            };
+
+            let logger = Logger::new(resolved_config.verbose);
+            return websocket::run(&websocket::websocket_url(&url), &resolved_config.headers, input, &logger).await;
+        }
+        Commands::Gql { query, gql_vars, variables, operation, introspect, endpoint, profile, vars, url, token, headers, verbose } => {
+            config_manager.set_variable_overrides(parse_key_values(&vars)?);
+            let mut resolved_config = config_manager.load_resolved_config(
+                profile.as_deref(),
+                url.as_deref(),
+                token.as_deref(),
+                headers.as_deref(),
+                verbose,
+            )?;
+            let endpoint = match endpoint {
+                Some(ref endpoint) => config_manager.resolve_inline(endpoint)?,
+                None => resolved_config.graphql_endpoint.clone().unwrap_or_else(|| graphql::DEFAULT_ENDPOINT.to_string()),
+            };
+            let url = request::target_url(&mut resolved_config, &endpoint)?;
+
+            let payload = if introspect {
+                serde_json::json!({ "query": graphql::INTROSPECTION_QUERY, "operationName": "IntrospectionQuery" })
+            } else {
+                let path = query.unwrap_or_default();
+                let document = if path == "-" {
+                    std::io::read_to_string(std::io::stdin())?
+                } else {
+                    std::fs::read_to_string(&path).map_err(|e| AsteriskError::Config(
+                        format!("Failed to read query file '{}': {}", path, e)
+                    ))?
+                };
+                let mut merged = match variables {
+                    Some(ref source) => graphql::read_variables(source)?,
+                    None => serde_json::Map::new(),
+                };
+                merged.extend(graphql::parse_variables(&gql_vars)?);
+                graphql::payload(&document, merged, operation.as_deref())?
+            };
+
+            http_client.mask_secrets(&resolved_config.secrets);
+            let logger = Logger::new(resolved_config.verbose);
+            let response = http_client
+                .send_request(&url, "POST", resolved_config.headers.clone(), Some(payload.to_string()))
+                .await?;
+            let parsed: Option<serde_json::Value> = serde_json::from_str(&response.body).ok();
+            let errors = parsed.as_ref().map(graphql::errors).unwrap_or_default();
+
+            match parsed {
+                Some(ref parsed) if introspect && errors.is_empty() && response.status < 300 => {
+                    print!("{}", graphql::schema_to_sdl(parsed)?);
+                    return Ok(());
+                }
+                _ => logger.response(&response),
+            }
+            if !errors.is_empty() {
+                logger.graphql_errors(&errors);
+                return Err(AsteriskError::GraphQl(format!("{} error(s) in response", errors.len())));
+            }
+            if response.status >= 300 {
+                return Err(AsteriskError::GraphQl(format!("request failed with HTTP {}", response.status)));
+            }
+            return Ok(());
+        }
+        Commands::Rpc { method, params, file, endpoint, profile, vars, url, token, headers, verbose } => {
+            config_manager.set_variable_overrides(parse_key_values(&vars)?);
+            let mut resolved_config = config_manager.load_resolved_config(
+                profile.as_deref(),
+                url.as_deref(),
+                token.as_deref(),
+                headers.as_deref(),
+                verbose,
+            )?;
+            let endpoint = match endpoint {
+                Some(ref endpoint) => config_manager.resolve_inline(endpoint)?,
+                None => resolved_config.url.clone(),
+            };
+            let url = request::target_url(&mut resolved_config, &endpoint)?;
+
+            let mut ids = jsonrpc::Ids::default();
+            let payload = match file {
+                Some(ref path) => {
+                    let content = if path == "-" {
+                        std::io::read_to_string(std::io::stdin())?
+                    } else {
+                        std::fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+                            format!("Failed to read batch file '{}': {}", path, e)
+                        ))?
+                    };
+                    jsonrpc::batch(&content, &mut ids)?
+                }
+                None => jsonrpc::envelope(&method.unwrap_or_default(), jsonrpc::parse_params(&params)?, ids.next()),
+            };
+
+            http_client.mask_secrets(&resolved_config.secrets);
+            let logger = Logger::new(resolved_config.verbose);
+            let response = http_client
+                .send_request(&url, "POST", resolved_config.headers.clone(), Some(payload.to_string()))
+                .await?;
+            logger.response(&response);
+
+            let errors = jsonrpc::response_errors(&payload, response.status, &response.body)?;
+            logger.rpc_errors(&errors);
+            if let Some(error) = errors.into_iter().next() {
+                return Err(error.into());
+            }
+            return Ok(());
+        }
+        Commands::FromCurl { command, save, dry_run, profile, verbose } => {
+            let args = match command.as_slice() {
+                [single] => import::curl::split_command(single)?,
+                _ => command,
+            };
+            let curl = import::curl::parse(&args)?;
+            let profile_url = config_manager.match_profile(&curl.url)?.map(|(_, url)| url);
+
+            if let Some(name) = save {
+                let has_config = config_manager.find_config_file().is_some();
+                let imported = import::curl::convert(&curl, &name, profile_url.as_deref(), has_config);
+                return write_import(imported, "curl command", dry_run, config_manager);
+            }
+            return run_curl(&curl, profile_url.as_deref(), profile.as_deref(), verbose, config_manager, http_client).await;
+        }
     }
     Ok(())
 }
 
-/// Builds the final request URL from base URL and endpoint
-fn build_request_url(base_url: &str, endpoint: &str) -> String {
-    let trimmed_endpoint = endpoint.trim_start_matches('/');
-    
-    if base_url.ends_with('/') {
-        format!("{}{}", base_url, trimmed_endpoint)
+/// Executes a saved request and checks its expectations
+async fn run_saved_request(
+    name: &str,
+    overrides: &RequestOverrides,
+    config_manager: &mut ConfigManager,
+    http_client: &HttpClient,
+) -> Result<(), AsteriskError> {
+    let saved_request = config_manager.saved_request(name)?;
+    let prepared = request::prepare_saved_request(config_manager, &saved_request, overrides)?;
+    http_client.mask_secrets(&prepared.secrets);
+
+    if let Some(format) = overrides.export_as {
+        print!("{}", export::render(format, &prepared, overrides.mask_secrets));
+        return Ok(());
+    }
+
+    let logger = Logger::new(prepared.verbose);
+
+    if overrides.streaming.stream {
+        return stream::run(http_client, &prepared, &(&overrides.streaming).into(), &logger).await;
+    }
+
+    let (method, url) = (prepared.method.clone(), prepared.url.clone());
+    let response = send_prepared(http_client, prepared, overrides.output.as_deref(), overrides.resume, &logger).await?;
+
+    let contract = check_contract(contract_validator(config_manager).as_ref(), &method, &url, &response);
+    if let Some(ref contract) = contract {
+        logger.contract(contract);
+    }
+
+    if let Some(ref expect) = saved_request.expect {
+        let failures = request::check_expectations(expect, &response, contract.as_ref());
+        logger.expectations(&failures);
+        if !failures.is_empty() {
+            return Err(AsteriskError::ExpectationFailed(failures.join("; ")));
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes the selected requests of a .http file in order
+async fn run_http_file(
+    file: &HttpFile,
+    name: Option<&str>,
+    line: Option<usize>,
+    resolved_config: &config::ResolvedConfig,
+    config_manager: &mut ConfigManager,
+    http_client: &HttpClient,
+) -> Result<(), AsteriskError> {
+    let requests = file.select(name, line)?;
+    let mut interpolator = Interpolator::new(file, resolved_config)?;
+    http_client.mask_secrets(&resolved_config.secrets);
+    let logger = Logger::new(resolved_config.verbose);
+    let mut validator = None;
+
+    for request in requests {
+        println!("\n{} {}", "###".bright_cyan(), request.label().bold());
+
+        // Relative URLs are resolved against the profile URL
+        let url = interpolator.interpolate(&request.url, request, config_manager)?;
+        let mut profile = resolved_config.clone();
+        let url = request::target_url(&mut profile, &url)?;
+
+        // Profile headers first, file headers replace matching names
+        let mut headers: HeaderMap = profile.headers;
+        for (header_name, header_value) in &request.headers {
+            let header_value = interpolator.interpolate(header_value, request, config_manager)?;
+            headers.insert(
+                HeaderName::from_bytes(header_name.as_bytes()).map_err(|_| AsteriskError::InvalidHeaders)?,
+                HeaderValue::from_str(&header_value).map_err(|_| AsteriskError::InvalidHeaders)?,
+            );
+        }
+
+        let body = interpolator.body(request, config_manager)?;
+
+        let response = http_client
+            .send_request(&url, &request.method, headers, body)
+            .await?;
+
+        logger.response(&response);
+
+        let validator = validator.get_or_insert_with(|| contract_validator(config_manager));
+        if let Some(contract) = check_contract(validator.as_ref(), &request.method, &url, &response) {
+            logger.contract(&contract);
+        }
+
+        if !request.handlers.is_empty() && resolved_config.verbose {
+            println!("{} {} response handler(s) skipped (scripts are not executed)",
+                "Note:".bold(), request.handlers.len());
+        }
+
+        if let Some(ref request_name) = request.name {
+            interpolator.record_response(request_name, &response.body);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends a request parsed from a curl command
+/// With `profile`, the request is sent to that profile's URL with its headers and
+/// credentials; headers from the command replace the rest.
+async fn run_curl(
+    curl: &import::curl::CurlRequest,
+    profile_url: Option<&str>,
+    profile: Option<&str>,
+    verbose: bool,
+    config_manager: &mut ConfigManager,
+    http_client: &HttpClient,
+) -> Result<(), AsteriskError> {
+    for note in &curl.notes {
+        println!("{} {}", "Note:".bold().yellow(), note);
+    }
+
+    let (url, mut headers, verbose) = match profile {
+        Some(profile) => {
+            let mut resolved_config = config_manager.load_resolved_config(Some(profile), None, None, None, verbose)?;
+            let url = request::target_url(&mut resolved_config, &curl.endpoint(profile_url))?;
+            http_client.mask_secrets(&resolved_config.secrets);
+            (url, resolved_config.headers, resolved_config.verbose)
+        }
+        None => (curl.url.clone(), HeaderMap::new(), verbose),
+    };
+
+    for (header_name, header_value) in &curl.headers {
+        let header_name = HeaderName::from_bytes(header_name.as_bytes()).map_err(|_| AsteriskError::InvalidHeaders)?;
+        if header_name == AUTHORIZATION && headers.contains_key(AUTHORIZATION) {
+            continue;
+        }
+        headers.insert(header_name, HeaderValue::from_str(header_value).map_err(|_| AsteriskError::InvalidHeaders)?);
+    }
+
+    let logger = Logger::new(verbose);
+    let insecure;
+    let http_client = if curl.insecure {
+        insecure = http_client.insecure()?;
+        &insecure
+    } else {
+        http_client
+    };
+
+    let response = http_client
+        .send_request(&url, &curl.method, headers, curl.body.clone())
+        .await?;
+
+    logger.response(&response);
+
+    if let Some(contract) = check_contract(contract_validator(config_manager).as_ref(), &curl.method, &url, &response) {
+        logger.contract(&contract);
+    }
+
+    Ok(())
+}
+
+/// Prints the requests of a .http file with their line ranges
+fn list_http_file_requests(file: &HttpFile) {
+    if file.requests.is_empty() {
+        println!("{}", "No requests found".italic());
+        return;
+    }
+
+    println!("{}", "Requests:".bold().bright_cyan());
+    for request in &file.requests {
+        println!("  {} {} {} {}",
+            request.label().bright_green(),
+            request.method.bright_blue(),
+            request.url,
+            format!("(lines {}-{})", request.start_line, request.end_line).italic());
+    }
+}
+
+/// Writes an import through the config manager and prints the report
+fn write_import(
+    imported: import::Imported,
+    source: &str,
+    dry_run: bool,
+    config_manager: &ConfigManager,
+) -> Result<(), AsteriskError> {
+    if dry_run {
+        print!("{}", ConfigManager::serialize_config(&imported.config)?);
     } else {
-        format!("{}/{}", base_url, trimmed_endpoint)
+        let outcome = config_manager.import_config(imported.config, source)?;
+        let action = if outcome.created { "Created:" } else { "Updated:" };
+        println!("{}", "✅ Import complete!".bright_green());
+        println!("{} {}", action.bold(), outcome.path.display());
+        println!("{} {}", "Profiles added:".bold(), outcome.added_profiles.join(", "));
+        println!("{} {}", "Requests added:".bold(), outcome.added_requests.len());
+        if !outcome.skipped.is_empty() {
+            println!("{} {}", "Skipped (already defined):".bold(), outcome.skipped.join(", "));
+        }
+    }
+
+    if !imported.unsupported.is_empty() {
+        println!();
+        println!("{}", "Unsupported features:".bold().yellow());
+        for note in &imported.unsupported {
+            println!("  {} {}", "•".yellow(), note);
+        }
+    }
+
+    Ok(())
+}
+
+/// Asks a yes/no question on the terminal, defaulting to yes; `false` without a terminal
+fn confirm(question: &str) -> Result<bool, AsteriskError> {
+    if !websocket::stdin_is_interactive() {
+        return Ok(false);
+    }
+    eprint!("{} [Y/n] ", question);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes"))
+}
+
+/// Reads the value for `secret set` from stdin, prompting when it is a terminal
+fn read_secret_value(setting: &str) -> Result<String, AsteriskError> {
+    if websocket::stdin_is_interactive() {
+        eprint!("Value for {}: ", setting);
     }
+    let mut value = String::new();
+    std::io::stdin().read_line(&mut value)?;
+    let value = value.trim_end_matches(['\r', '\n']);
+    if value.is_empty() {
+        return Err(AsteriskError::Config(format!("No value given for {}", setting)));
+    }
+    Ok(value.to_string())
+}
+
+/// Prints OpenAPI operations with their method, path and summary
+fn list_operations(spec: &openapi::OpenApiSpec) {
+    let operations = spec.operations();
+    if operations.is_empty() {
+        println!("{}", "No operations found".italic());
+        return;
+    }
+
+    println!("{}", "Operations:".bold().bright_cyan());
+    for operation in operations {
+        println!("  {} {} {}", operation.name().bright_green(), operation.method.bright_blue(), operation.path);
+        if let Some(ref summary) = operation.summary {
+            println!("      {}", summary.italic());
+        }
+    }
+}
+
+/// Parses `key=value` arguments into a map
+fn parse_key_values(params: &[String]) -> Result<std::collections::HashMap<String, String>, AsteriskError> {
+    params
+        .iter()
+        .map(|param| {
+            param.split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| AsteriskError::Config(format!("Invalid parameter '{}'. Expected 'key=value'", param)))
+        })
+        .collect()
+}
+
+/// Reads and parses a JSON file
+fn read_json_file(path: &str) -> Result<serde_json::Value, AsteriskError> {
+    let content = std::fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+        format!("Failed to read {}: {}", path, e)
+    ))?;
+    serde_json::from_str(&content).map_err(|e| AsteriskError::Config(
+        format!("Failed to parse {}: {}", path, e)
+    ))
+}
+
+/// Prints saved requests with their descriptions
+fn list_saved_requests(config_manager: &ConfigManager) -> Result<(), AsteriskError> {
+    let requests = config_manager.saved_requests()?;
+
+    if requests.is_empty() {
+        println!("{}", "No saved requests found".italic());
+        return Ok(());
+    }
+
+    println!("{}", "Saved Requests:".bold().bright_cyan());
+    for (name, request) in requests {
+        let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
+        println!("  {} {} {}", name.bright_green(), method.bright_blue(), request.endpoint);
+        if let Some(ref description) = request.description {
+            println!("      {}", description.italic());
+        }
+    }
+
+    Ok(())
 }
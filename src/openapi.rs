@@ -0,0 +1,267 @@
+use crate::error::{AsteriskError, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// HTTP methods that may appear as operations in an OpenAPI path item
+const OPERATION_METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Maximum schema nesting followed when generating examples
+const MAX_EXAMPLE_DEPTH: usize = 8;
+
+/// Parsed OpenAPI 3 document
+#[derive(Debug, Clone)]
+pub struct OpenApiSpec {
+    /// Raw document (YAML specs are converted to JSON values)
+    pub document: Value,
+}
+
+/// A single operation (method + path) from the spec
+#[derive(Debug, Clone)]
+pub struct Operation {
+    /// Declared operationId
+    pub operation_id: Option<String>,
+
+    /// Uppercase HTTP method
+    pub method: String,
+
+    /// Path template, e.g. `/users/{id}`
+    pub path: String,
+
+    /// Short summary
+    pub summary: Option<String>,
+
+    /// Path-level and operation-level parameters with `$ref`s resolved
+    pub parameters: Vec<Value>,
+
+    /// Operation object
+    pub definition: Value,
+}
+
+impl Operation {
+    /// operationId, or a name derived from method and path
+    pub fn name(&self) -> String {
+        match self.operation_id {
+            Some(ref id) => id.clone(),
+            None => crate::import::slugify(&format!("{} {}", self.method.to_lowercase(), self.path)),
+        }
+    }
+}
+
+impl OpenApiSpec {
+    /// Loads a YAML or JSON OpenAPI document
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+            format!("Failed to read OpenAPI spec {}: {}", path.display(), e)
+        ))?;
+        Self::parse(&content).map_err(|e| match e {
+            AsteriskError::Config(message) => AsteriskError::Config(format!("{}: {}", path.display(), message)),
+            other => other,
+        })
+    }
+
+    /// Parses a YAML or JSON OpenAPI 3 document
+    pub fn parse(content: &str) -> Result<Self> {
+        let document: Value = serde_yaml::from_str(content)
+            .map_err(|e| AsteriskError::Config(format!("Failed to parse OpenAPI spec: {}", e)))?;
+
+        let version = document.get("openapi").and_then(Value::as_str).unwrap_or("");
+        if !version.starts_with('3') {
+            return Err(AsteriskError::Config(
+                "Only OpenAPI 3.x documents are supported (missing 'openapi: 3.x')".to_string()
+            ));
+        }
+
+        Ok(Self { document })
+    }
+
+    /// Returns every operation in path order
+    pub fn operations(&self) -> Vec<Operation> {
+        let mut operations = Vec::new();
+        let paths = match self.document.get("paths").and_then(Value::as_object) {
+            Some(paths) => paths,
+            None => return operations,
+        };
+
+        for (path, path_item) in paths {
+            let path_item = self.resolve(path_item);
+            let shared: Vec<Value> = self.parameters(path_item.get("parameters"));
+
+            for method in OPERATION_METHODS {
+                let definition = match path_item.get(*method) {
+                    Some(definition) => definition,
+                    None => continue,
+                };
+
+                // Operation parameters override path-level ones with the same name and location
+                let mut parameters = shared.clone();
+                for parameter in self.parameters(definition.get("parameters")) {
+                    parameters.retain(|p| p.get("name") != parameter.get("name") || p.get("in") != parameter.get("in"));
+                    parameters.push(parameter);
+                }
+
+                operations.push(Operation {
+                    operation_id: definition.get("operationId").and_then(Value::as_str).map(String::from),
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                    summary: definition.get("summary").and_then(Value::as_str).map(String::from),
+                    parameters,
+                    definition: definition.clone(),
+                });
+            }
+        }
+
+        operations
+    }
+
+    /// Follows local `$ref` pointers (`#/components/...`)
+    pub fn resolve<'a>(&'a self, value: &'a Value) -> &'a Value {
+        let mut current = value;
+        for _ in 0..MAX_EXAMPLE_DEPTH {
+            match current.get("$ref").and_then(Value::as_str) {
+                Some(reference) => match reference.strip_prefix('#').and_then(|p| self.document.pointer(p)) {
+                    Some(target) => current = target,
+                    None => break,
+                },
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Builds an example value from a schema, preferring declared examples
+    pub fn example_for_schema(&self, schema: &Value) -> Value {
+        self.example_with_depth(schema, 0)
+    }
+
+    fn example_with_depth(&self, schema: &Value, depth: usize) -> Value {
+        let schema = self.resolve(schema);
+        if depth > MAX_EXAMPLE_DEPTH {
+            return Value::Null;
+        }
+
+        if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+            return example.clone();
+        }
+        if let Some(first) = schema.get("examples").and_then(Value::as_array).and_then(|e| e.first()) {
+            return first.clone();
+        }
+        if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+            return first.clone();
+        }
+
+        for combinator in ["allOf", "oneOf", "anyOf"] {
+            if let Some(schemas) = schema.get(combinator).and_then(Value::as_array) {
+                if combinator == "allOf" {
+                    let mut merged = serde_json::Map::new();
+                    for part in schemas {
+                        if let Value::Object(object) = self.example_with_depth(part, depth + 1) {
+                            merged.extend(object);
+                        }
+                    }
+                    return Value::Object(merged);
+                }
+                return schemas.first().map(|s| self.example_with_depth(s, depth + 1)).unwrap_or(Value::Null);
+            }
+        }
+
+        let schema_type = match schema.get("type") {
+            Some(Value::String(t)) => t.as_str(),
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|t| *t != "null").unwrap_or("null"),
+            _ if schema.get("properties").is_some() => "object",
+            _ => "",
+        };
+
+        match schema_type {
+            "object" => {
+                let mut object = serde_json::Map::new();
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, property) in properties {
+                        object.insert(name.clone(), self.example_with_depth(property, depth + 1));
+                    }
+                }
+                Value::Object(object)
+            }
+            "array" => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.example_with_depth(items, depth + 1)]),
+                None => Value::Array(Vec::new()),
+            },
+            "string" => Value::String(match schema.get("format").and_then(Value::as_str) {
+                Some("date-time") => "2024-01-01T00:00:00Z".to_string(),
+                Some("date") => "2024-01-01".to_string(),
+                Some("email") => "user@example.com".to_string(),
+                Some("uuid") => "00000000-0000-0000-0000-000000000000".to_string(),
+                Some("uri") | Some("url") => "https://example.com".to_string(),
+                _ => "string".to_string(),
+            }),
+            "integer" => Value::from(0),
+            "number" => Value::from(0.0),
+            "boolean" => Value::Bool(false),
+            _ => Value::Null,
+        }
+    }
+
+    fn parameters(&self, parameters: Option<&Value>) -> Vec<Value> {
+        parameters
+            .and_then(Value::as_array)
+            .map(|list| list.iter().map(|p| self.resolve(p).clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info: { title: Users, version: "1.0" }
+paths:
+  /users/{id}:
+    parameters:
+      - name: id
+        in: path
+        required: true
+        schema: { type: integer }
+    get:
+      operationId: getUserById
+      summary: Get a user
+      responses:
+        "200":
+          description: OK
+    delete:
+      responses:
+        "204": { description: Deleted }
+components:
+  schemas:
+    User:
+      type: object
+      properties:
+        id: { type: integer, example: 5 }
+        email: { type: string, format: email }
+        tags: { type: array, items: { type: string } }
+"##;
+
+    #[test]
+    fn test_operations() {
+        let spec = OpenApiSpec::parse(SPEC).unwrap();
+        let operations = spec.operations();
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].name(), "getUserById");
+        assert_eq!(operations[0].method, "GET");
+        assert_eq!(operations[0].parameters.len(), 1);
+        assert_eq!(operations[1].name(), "delete_users_id");
+    }
+
+    #[test]
+    fn test_example_for_schema() {
+        let spec = OpenApiSpec::parse(SPEC).unwrap();
+        let example = spec.example_for_schema(&serde_json::json!({ "$ref": "#/components/schemas/User" }));
+        assert_eq!(example, serde_json::json!({ "id": 5, "email": "user@example.com", "tags": ["string"] }));
+    }
+
+    #[test]
+    fn test_rejects_swagger_2() {
+        assert!(OpenApiSpec::parse("swagger: '2.0'\npaths: {}").is_err());
+    }
+}
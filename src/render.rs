@@ -0,0 +1,584 @@
+use colored::*;
+use regex::Regex;
+use serde_json::Value;
+use std::env;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Leading bytes shown for binary bodies
+const MAGIC_BYTES: usize = 16;
+
+/// Bytes shown in a hex dump before the rest is summarised
+const MAX_HEX_DUMP_BYTES: usize = 4096;
+
+/// File signatures used to describe binary bodies
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (b"\xff\xd8\xff", "JPEG image"),
+    (b"GIF87a", "GIF image"),
+    (b"GIF89a", "GIF image"),
+    (b"%PDF-", "PDF document"),
+    (b"PK\x03\x04", "ZIP archive"),
+    (b"\x1f\x8b", "gzip data"),
+    (b"BZh", "bzip2 data"),
+    (b"\x28\xb5\x2f\xfd", "zstd data"),
+    (b"7z\xbc\xaf\x27\x1c", "7-zip archive"),
+    (b"\x00asm", "WebAssembly module"),
+    (b"\x7fELF", "ELF executable"),
+    (b"wOFF", "WOFF font"),
+    (b"wOF2", "WOFF2 font"),
+    (b"OggS", "Ogg media"),
+    (b"ID3", "MP3 audio"),
+    (b"SQLite format 3\x00", "SQLite database"),
+];
+
+/// Indentation used by every renderer
+const INDENT: &str = "  ";
+
+/// HTML elements that never have a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// HTML elements whose content is printed untouched
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "pre", "textarea"];
+
+/// Output settings decided once at startup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    /// Colorize output
+    pub color: bool,
+
+    /// Print JSON object keys in sorted order instead of response order
+    pub sort_keys: bool,
+
+    /// Send long output through `$PAGER`
+    pub pager: bool,
+}
+
+impl RenderOptions {
+    /// Color and paging on a terminal, unless `NO_COLOR` is set
+    pub fn detect(sort_keys: bool, no_pager: bool) -> Self {
+        let terminal = std::io::stdout().is_terminal();
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self {
+            color: terminal && !no_color,
+            sort_keys,
+            pager: terminal && !no_pager,
+        }
+    }
+}
+
+static OPTIONS: OnceLock<RenderOptions> = OnceLock::new();
+
+/// Sets the options used for the rest of the invocation
+pub fn configure(options: RenderOptions) {
+    colored::control::set_override(options.color);
+    let _ = OPTIONS.set(options);
+}
+
+/// Options set by `configure`
+pub fn options() -> RenderOptions {
+    OPTIONS.get().copied().unwrap_or(RenderOptions { color: false, sort_keys: false, pager: false })
+}
+
+/// How a response body is displayed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyKind {
+    Json,
+    Xml,
+    Html,
+    Yaml,
+    Form,
+    Binary,
+    Text,
+}
+
+/// Chooses a renderer from the Content-Type, sniffing the body when it is missing
+pub fn body_kind(content_type: Option<&str>, bytes: &[u8]) -> BodyKind {
+    let media_type = content_type
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .unwrap_or_default();
+    let (top, subtype) = media_type.split_once('/').unwrap_or((media_type.as_str(), ""));
+
+    let declared = if subtype == "json" || subtype.ends_with("+json") {
+        Some(BodyKind::Json)
+    } else if subtype == "html" {
+        Some(BodyKind::Html)
+    } else if subtype == "xml" || subtype.ends_with("+xml") {
+        Some(BodyKind::Xml)
+    } else if subtype.contains("yaml") {
+        Some(BodyKind::Yaml)
+    } else if subtype == "x-www-form-urlencoded" {
+        Some(BodyKind::Form)
+    } else if matches!(top, "image" | "audio" | "video" | "font")
+        || matches!(subtype, "octet-stream" | "pdf" | "zip" | "gzip" | "x-protobuf" | "protobuf" | "wasm" | "msgpack")
+    {
+        Some(BodyKind::Binary)
+    } else if top == "text" {
+        Some(BodyKind::Text)
+    } else {
+        None
+    };
+
+    match declared {
+        Some(kind) => kind,
+        None if bytes.contains(&0) || std::str::from_utf8(bytes).is_err() => BodyKind::Binary,
+        None if serde_json::from_slice::<Value>(bytes).is_ok() && !bytes.is_empty() => BodyKind::Json,
+        None => BodyKind::Text,
+    }
+}
+
+/// Renders a response body for display
+pub fn render_body(content_type: Option<&str>, bytes: &[u8], text: &str, options: &RenderOptions) -> String {
+    match body_kind(content_type, bytes) {
+        BodyKind::Json => match serde_json::from_str::<Value>(text) {
+            Ok(value) => {
+                let mut out = String::new();
+                write_json(&value, 0, options, &mut out);
+                out
+            }
+            Err(_) => text.to_string(),
+        },
+        BodyKind::Xml => indent_markup(text, false, options),
+        BodyKind::Html => indent_markup(text, true, options),
+        BodyKind::Yaml => highlight_yaml(text, options),
+        BodyKind::Form => render_form(bytes, options),
+        BodyKind::Binary => binary_summary(content_type, bytes, options),
+        BodyKind::Text => text.to_string(),
+    }
+}
+
+/// Prints output, through the pager when enabled
+/// `less` is started with `-FRX` so short output is printed without paging.
+pub fn print_paged(output: &str) {
+    if options().pager {
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        let mut words = shlex::split(&pager).unwrap_or_default().into_iter();
+        if let Some(program) = words.next().filter(|program| program != "cat") {
+            let mut command = Command::new(program);
+            command.args(words).stdin(Stdio::piped());
+            if env::var_os("LESS").is_none() {
+                command.env("LESS", "FRX");
+            }
+            if let Ok(mut child) = command.spawn() {
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = stdin.write_all(output.as_bytes());
+                }
+                let _ = child.wait();
+                return;
+            }
+        }
+    }
+    print!("{}", output);
+}
+
+fn paint(text: &str, options: &RenderOptions, style: fn(&str) -> ColoredString) -> String {
+    if options.color {
+        style(text).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn write_json(value: &Value, depth: usize, options: &RenderOptions, out: &mut String) {
+    let quoted = |text: &str| serde_json::to_string(text).unwrap_or_default();
+    match value {
+        Value::Null => out.push_str(&paint("null", options, |t| t.bright_black())),
+        Value::Bool(flag) => out.push_str(&paint(&flag.to_string(), options, |t| t.magenta())),
+        Value::Number(number) => out.push_str(&paint(&number.to_string(), options, |t| t.yellow())),
+        Value::String(text) => out.push_str(&paint(&quoted(text), options, |t| t.green())),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(object) if object.is_empty() => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                out.push_str(&INDENT.repeat(depth + 1));
+                write_json(item, depth + 1, options, out);
+                out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(']');
+        }
+        Value::Object(object) => {
+            let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+            if options.sort_keys {
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+            }
+            out.push_str("{\n");
+            for (index, (key, item)) in entries.iter().enumerate() {
+                out.push_str(&INDENT.repeat(depth + 1));
+                out.push_str(&paint(&quoted(key), options, |t| t.cyan()));
+                out.push_str(": ");
+                write_json(item, depth + 1, options, out);
+                out.push_str(if index + 1 < entries.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push('}');
+        }
+    }
+}
+
+/// A piece of markup: a tag (including comments and declarations) or text
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Tag(&'a str),
+    Text(&'a str),
+}
+
+/// Splits markup into tags and text, keeping quoted `>` inside attributes
+fn tokenize(markup: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = markup;
+    while !rest.is_empty() {
+        let start = match rest.find('<') {
+            Some(0) => 0,
+            Some(start) => {
+                tokens.push(Token::Text(&rest[..start]));
+                start
+            }
+            None => {
+                tokens.push(Token::Text(rest));
+                break;
+            }
+        };
+        rest = &rest[start..];
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else if rest.starts_with("<![CDATA[") {
+            rest.find("]]>").map(|i| i + 3)
+        } else {
+            let mut quote = None;
+            rest.char_indices().find_map(|(i, c)| {
+                match (quote, c) {
+                    (None, '"') | (None, '\'') => quote = Some(c),
+                    (Some(q), c) if q == c => quote = None,
+                    (None, '>') => return Some(i + 1),
+                    _ => {}
+                }
+                None
+            })
+        }
+        .unwrap_or(rest.len());
+
+        tokens.push(Token::Tag(&rest[..end]));
+        rest = &rest[end..];
+    }
+    tokens
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Re-indents XML or HTML one element per line; short text stays inline
+fn indent_markup(markup: &str, html: bool, options: &RenderOptions) -> String {
+    let tokens = tokenize(markup);
+    let mut lines: Vec<String> = Vec::new();
+    let mut depth: usize = 0;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let pad = INDENT.repeat(depth);
+        match tokens[index] {
+            Token::Text(text) => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    lines.push(format!("{}{}", pad, text));
+                }
+            }
+            Token::Tag(tag) if tag.starts_with("</") => {
+                depth = depth.saturating_sub(1);
+                lines.push(format!("{}{}", INDENT.repeat(depth), paint_tag(tag, options)));
+            }
+            Token::Tag(tag) if tag.starts_with("<!--") => {
+                lines.push(format!("{}{}", pad, paint(tag, options, |t| t.bright_black())));
+            }
+            Token::Tag(tag) if tag.starts_with("<?") || tag.starts_with("<!") || tag.ends_with("/>") => {
+                lines.push(format!("{}{}", pad, paint_tag(tag, options)));
+            }
+            Token::Tag(tag) => {
+                let name = tag_name(tag);
+                if html && VOID_ELEMENTS.contains(&name.as_str()) {
+                    lines.push(format!("{}{}", pad, paint_tag(tag, options)));
+                } else if html && RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                    // Everything up to the matching close tag is kept as-is
+                    let mut raw = String::new();
+                    let mut close = None;
+                    for (offset, token) in tokens[index + 1..].iter().enumerate() {
+                        match token {
+                            Token::Tag(candidate) if candidate.starts_with("</") && tag_name(candidate) == name => {
+                                close = Some((offset, *candidate));
+                                break;
+                            }
+                            Token::Tag(text) | Token::Text(text) => raw.push_str(text),
+                        }
+                    }
+                    lines.push(format!("{}{}", pad, paint_tag(tag, options)));
+                    if !raw.trim().is_empty() {
+                        lines.push(raw.trim_matches('\n').to_string());
+                    }
+                    if let Some((offset, close_tag)) = close {
+                        lines.push(format!("{}{}", pad, paint_tag(close_tag, options)));
+                        index += offset + 1;
+                    } else {
+                        index = tokens.len();
+                    }
+                } else {
+                    // `<a>short text</a>` stays on one line
+                    match (tokens.get(index + 1), tokens.get(index + 2)) {
+                        (Some(Token::Text(text)), Some(Token::Tag(close)))
+                            if close.starts_with("</") && tag_name(close) == name && !text.contains('\n') && text.len() <= 80 =>
+                        {
+                            lines.push(format!("{}{}{}{}", pad, paint_tag(tag, options), text.trim(), paint_tag(close, options)));
+                            index += 2;
+                        }
+                        (Some(Token::Tag(close)), _) if close.starts_with("</") && tag_name(close) == name => {
+                            lines.push(format!("{}{}{}", pad, paint_tag(tag, options), paint_tag(close, options)));
+                            index += 1;
+                        }
+                        _ => {
+                            lines.push(format!("{}{}", pad, paint_tag(tag, options)));
+                            depth += 1;
+                        }
+                    }
+                }
+            }
+        }
+        index += 1;
+    }
+
+    lines.join("\n")
+}
+
+/// Colors the element name, attribute names and attribute values of a tag
+fn paint_tag(tag: &str, options: &RenderOptions) -> String {
+    if !options.color {
+        return tag.to_string();
+    }
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let attribute = ATTRIBUTE.get_or_init(|| {
+        Regex::new(r#"([^\s=/<>"']+)(\s*=\s*)("[^"]*"|'[^']*'|[^\s>]+)"#).expect("valid attribute pattern")
+    });
+
+    let body = tag.trim_end_matches(['>', '/', '?']);
+    let closing = &tag[body.len()..];
+    let prefix = body.len() - body.trim_start_matches(['<', '/', '?', '!']).len();
+    let name_end = body[prefix..].find(char::is_whitespace).map_or(body.len(), |i| prefix + i);
+    let (head, attributes) = body.split_at(name_end);
+    let attributes = attribute.replace_all(attributes, |captures: &regex::Captures| {
+        format!("{}{}{}", captures[1].cyan(), &captures[2], captures[3].green())
+    });
+    format!("{}{}{}", head.blue(), attributes, closing.blue())
+}
+
+/// Colors YAML keys and comments; the text itself is left unchanged
+fn highlight_yaml(text: &str, options: &RenderOptions) -> String {
+    if !options.color {
+        return text.trim_end().to_string();
+    }
+    static KEY: OnceLock<Regex> = OnceLock::new();
+    let key = KEY.get_or_init(|| Regex::new(r"^(\s*(?:- )?)([^\s#:'][^:#]*?):(\s|$)").expect("valid key pattern"));
+
+    text.trim_end()
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with('#') {
+                line.bright_black().to_string()
+            } else {
+                key.replace(line, |captures: &regex::Captures| {
+                    format!("{}{}:{}", &captures[1], captures[2].cyan(), &captures[3])
+                })
+                .to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decodes `application/x-www-form-urlencoded` bodies into aligned `key = value` lines
+fn render_form(bytes: &[u8], options: &RenderOptions) -> String {
+    let pairs: Vec<(String, String)> = url::form_urlencoded::parse(bytes).into_owned().collect();
+    let width = pairs.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+    pairs.iter()
+        .map(|(key, value)| {
+            let padded = format!("{:width$}", key, width = width);
+            format!("{} = {}", paint(&padded, options, |t| t.cyan()), value)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats a byte count with binary units, e.g. `1.5 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Names the file format from its leading bytes
+pub fn describe_magic(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        return Some("WebP image");
+    }
+    SIGNATURES.iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+        .map(|(_, description)| *description)
+}
+
+/// Describes a binary body instead of printing it: size, type and leading bytes
+fn binary_summary(content_type: Option<&str>, bytes: &[u8], options: &RenderOptions) -> String {
+    let media_type = content_type
+        .and_then(|value| value.split(';').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("unknown type");
+    let mut summary = format!("Binary body: {}, {}", format_size(bytes.len() as u64), media_type);
+    if let Some(description) = describe_magic(bytes) {
+        summary.push_str(&format!(" ({})", description));
+    }
+    if !bytes.is_empty() {
+        summary.push('\n');
+        summary.push_str(&hex_line(0, &bytes[..bytes.len().min(MAGIC_BYTES)], options));
+    }
+    summary.push('\n');
+    summary.push_str(&paint("Use -o FILE to save it", options, |t| t.italic()));
+    summary
+}
+
+/// Formats bytes like `hexdump -C`, up to `MAX_HEX_DUMP_BYTES`
+pub fn hex_dump(bytes: &[u8], options: &RenderOptions) -> String {
+    let mut lines = Vec::new();
+    for (row, chunk) in bytes[..bytes.len().min(MAX_HEX_DUMP_BYTES)].chunks(16).enumerate() {
+        lines.push(hex_line(row * 16, chunk, options));
+    }
+    if bytes.len() > MAX_HEX_DUMP_BYTES {
+        lines.push(format!("... {} more bytes", bytes.len() - MAX_HEX_DUMP_BYTES));
+    }
+    lines.join("\n")
+}
+
+/// Formats up to 16 bytes as one `hexdump -C` row
+fn hex_line(offset: usize, chunk: &[u8], options: &RenderOptions) -> String {
+    let mut hex = String::new();
+    for (i, byte) in chunk.iter().enumerate() {
+        hex.push_str(&format!("{:02x} ", byte));
+        if i == 7 {
+            hex.push(' ');
+        }
+    }
+    let ascii: String = chunk.iter()
+        .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+        .collect();
+    let offset = format!("{:08x}", offset);
+    format!("{}  {:<49} |{}|", paint(&offset, options, |t| t.bright_black()), hex, ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: RenderOptions = RenderOptions { color: false, sort_keys: false, pager: false };
+
+    fn render(content_type: &str, body: &[u8], options: &RenderOptions) -> String {
+        render_body(Some(content_type), body, &String::from_utf8_lossy(body), options)
+    }
+
+    #[test]
+    fn test_body_kind() {
+        assert_eq!(body_kind(Some("application/problem+json; charset=utf-8"), b"{}"), BodyKind::Json);
+        assert_eq!(body_kind(Some("application/atom+xml"), b""), BodyKind::Xml);
+        assert_eq!(body_kind(Some("text/html"), b""), BodyKind::Html);
+        assert_eq!(body_kind(Some("application/x-yaml"), b""), BodyKind::Yaml);
+        assert_eq!(body_kind(Some("image/png"), b"\x89PNG"), BodyKind::Binary);
+        assert_eq!(body_kind(None, br#"{"a":1}"#), BodyKind::Json);
+        assert_eq!(body_kind(None, b"\x00\x01"), BodyKind::Binary);
+        assert_eq!(body_kind(Some("text/plain"), b"{}"), BodyKind::Text);
+    }
+
+    #[test]
+    fn test_json_key_order() {
+        let body = br#"{"b":1,"a":[true,null],"c":{}}"#;
+        assert_eq!(render("application/json", body, &PLAIN), "{\n  \"b\": 1,\n  \"a\": [\n    true,\n    null\n  ],\n  \"c\": {}\n}");
+
+        let sorted = RenderOptions { sort_keys: true, ..PLAIN };
+        assert!(render("application/json", body, &sorted).starts_with("{\n  \"a\": ["));
+    }
+
+    #[test]
+    fn test_indent_xml_and_html() {
+        let xml = br#"<?xml version="1.0"?><users><user id="1"><name>John</name><tags/></user></users>"#;
+        assert_eq!(render("application/xml", xml, &PLAIN), concat!(
+            "<?xml version=\"1.0\"?>\n",
+            "<users>\n",
+            "  <user id=\"1\">\n",
+            "    <name>John</name>\n",
+            "    <tags/>\n",
+            "  </user>\n",
+            "</users>",
+        ));
+
+        let html = b"<!DOCTYPE html><html><head><meta charset=\"utf-8\"><script>if (a > b) { x(); }</script></head><body><p>Hi</p></body></html>";
+        assert_eq!(render("text/html", html, &PLAIN), concat!(
+            "<!DOCTYPE html>\n",
+            "<html>\n",
+            "  <head>\n",
+            "    <meta charset=\"utf-8\">\n",
+            "    <script>\n",
+            "if (a > b) { x(); }\n",
+            "    </script>\n",
+            "  </head>\n",
+            "  <body>\n",
+            "    <p>Hi</p>\n",
+            "  </body>\n",
+            "</html>",
+        ));
+    }
+
+    #[test]
+    fn test_form_and_binary_summary() {
+        assert_eq!(render("application/x-www-form-urlencoded", b"name=John+Doe&id=5&q=a%26b", &PLAIN),
+            "name = John Doe\nid   = 5\nq    = a&b");
+
+        let png = render("image/png", b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR!", &PLAIN);
+        assert_eq!(png, concat!(
+            "Binary body: 17 bytes, image/png (PNG image)\n",
+            "00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n",
+            "Use -o FILE to save it",
+        ));
+
+        let unknown = render_body(None, b"\x00\x01\x02", "", &PLAIN);
+        assert!(unknown.starts_with("Binary body: 3 bytes, unknown type\n"));
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(hex_dump(b"\x1f\x8b\x08\x00 gzip data.....!", &PLAIN), concat!(
+            "00000000  1f 8b 08 00 20 67 7a 69  70 20 64 61 74 61 2e 2e  |.... gzip data..|\n",
+            "00000010  2e 2e 2e 21                                       |...!|",
+        ));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 bytes");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
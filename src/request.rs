@@ -0,0 +1,348 @@
+use crate::cli::RequestOverrides;
+use crate::config::{ConfigManager, RawExpectation, RawRequest, ResolvedConfig, Secret};
+use crate::contract::{self, ContractResult};
+use crate::error::{AsteriskError, Result};
+use crate::http::HttpResponse;
+use colored::*;
+use reqwest::header::HeaderMap;
+use reqwest::Url;
+use std::fs;
+
+/// A fully resolved request ready to be sent
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    /// HTTP method
+    pub method: String,
+
+    /// Absolute request URL including query string
+    pub url: String,
+
+    /// Merged profile and request headers
+    pub headers: HeaderMap,
+
+    /// Request body
+    pub body: Option<String>,
+
+    /// Enable verbose output
+    pub verbose: bool,
+
+    /// Resolved credentials, used to mask exported requests
+    pub secrets: Vec<Secret>,
+}
+
+/// Resolves a saved request against a profile, applying CLI overrides field by field
+pub fn prepare_saved_request(
+    config_manager: &mut ConfigManager,
+    request: &RawRequest,
+    overrides: &RequestOverrides,
+) -> Result<PreparedRequest> {
+    let mut resolved_config = config_manager.load_resolved_config(
+        overrides.profile.as_deref(),
+        overrides.url.as_deref(),
+        overrides.token.as_deref(),
+        None,
+        overrides.verbose,
+    )?;
+
+    let endpoint = match overrides.endpoint {
+        Some(ref endpoint) => config_manager.resolve_inline(endpoint)?,
+        None => config_manager.resolve_value(&request.endpoint)?,
+    };
+    let url = build_request_url(&resolved_config.url, &endpoint);
+    restrict_credentials(&mut resolved_config, &url);
+
+    // Profile headers first, then request headers (or the CLI replacement)
+    let mut headers = resolved_config.headers;
+    match overrides.headers {
+        Some(ref headers_str) => {
+            let header_strings: Vec<String> = headers_str
+                .split(',')
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+                .collect();
+            config_manager.apply_headers(&header_strings, &mut headers)?;
+        }
+        None => {
+            if let Some(ref header_strings) = request.headers {
+                config_manager.apply_headers(header_strings, &mut headers)?;
+            }
+        }
+    }
+
+    let method = overrides
+        .method
+        .clone()
+        .or_else(|| request.method.clone())
+        .unwrap_or_else(|| "GET".to_string());
+
+    let body = if let Some(ref body) = overrides.body {
+        Some(config_manager.resolve_inline(body)?)
+    } else if let Some(ref body_file) = overrides.body_file {
+        Some(read_body_file(body_file)?)
+    } else if let Some(ref body) = request.body {
+        Some(config_manager.resolve_value(body)?)
+    } else if let Some(ref body_file) = request.body_file {
+        Some(config_manager.resolve_value(&read_body_file(body_file)?)?)
+    } else {
+        None
+    };
+
+    // Profile query parameters, then saved parameters sorted by name;
+    // CLI parameters replace matching keys
+    let mut query: Vec<(String, String)> = resolved_config.query.clone();
+    if let Some(ref saved_query) = request.query {
+        let mut keys: Vec<&String> = saved_query.keys().collect();
+        keys.sort();
+        for key in keys {
+            query.push((key.clone(), config_manager.resolve_value(&saved_query[key])?));
+        }
+    }
+    for param in &overrides.query {
+        let (key, value) = param.split_once('=').ok_or_else(|| AsteriskError::Config(
+            format!("Invalid query parameter '{}'. Expected 'key=value'", param)
+        ))?;
+        query.retain(|(existing, _)| existing != key);
+        query.push((key.to_string(), value.to_string()));
+    }
+
+    let url = append_query(&url, &query)?;
+
+    Ok(PreparedRequest {
+        method,
+        url,
+        headers,
+        body,
+        verbose: resolved_config.verbose,
+        secrets: resolved_config.secrets,
+    })
+}
+
+/// Builds the final request URL from base URL and endpoint
+/// Absolute endpoints (containing a scheme) are used as-is
+pub fn build_request_url(base_url: &str, endpoint: &str) -> String {
+    if endpoint.contains("://") {
+        return endpoint.to_string();
+    }
+
+    let trimmed_endpoint = endpoint.trim_start_matches('/');
+
+    if base_url.ends_with('/') {
+        format!("{}{}", base_url, trimmed_endpoint)
+    } else {
+        format!("{}/{}", base_url, trimmed_endpoint)
+    }
+}
+
+/// Builds the URL for an endpoint with the profile's query parameters
+/// An absolute endpoint on another origin is sent without the profile credentials.
+pub fn target_url(resolved_config: &mut ResolvedConfig, endpoint: &str) -> Result<String> {
+    let url = build_request_url(&resolved_config.url, endpoint);
+    restrict_credentials(resolved_config, &url);
+    append_query(&url, &resolved_config.query)
+}
+
+/// Drops the profile credentials for a URL on another origin, with a note when there were any
+fn restrict_credentials(resolved_config: &mut ResolvedConfig, url: &str) {
+    if resolved_config.restrict_to_origin(url) {
+        eprintln!(
+            "{} {} is not on the profile URL's origin; sending it without the profile credentials",
+            "Note:".bold().yellow(), url
+        );
+    }
+}
+
+/// Appends URL-encoded query parameters to a URL
+pub fn append_query(url: &str, params: &[(String, String)]) -> Result<String> {
+    if params.is_empty() {
+        return Ok(url.to_string());
+    }
+
+    let mut parsed = Url::parse(url)
+        .map_err(|e| AsteriskError::Config(format!("Invalid URL '{}': {}", url, e)))?;
+    parsed.query_pairs_mut().extend_pairs(params);
+    Ok(parsed.to_string())
+}
+
+/// Checks a response against saved request expectations
+/// `contract` is the OpenAPI check for the response, if a spec is configured.
+/// Returns a description of every failed expectation
+pub fn check_expectations(
+    expect: &RawExpectation,
+    response: &HttpResponse,
+    contract: Option<&ContractResult>,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    if let Some(expected_status) = expect.status {
+        if expected_status != response.status {
+            failures.push(format!("expected status {}, got {}", expected_status, response.status));
+        }
+    }
+
+    // A body saved with `-o` was never read, so checks on it fail rather than pass silently
+    let unread = |check: String| {
+        response.saved_to.as_ref()
+            .map(|path| format!("{}: the body was saved to {} and not checked", check, path.display()))
+    };
+
+    if let Some(ref substrings) = expect.body_contains {
+        for substring in substrings {
+            if let Some(failure) = unread(format!("expected body to contain '{}'", substring)) {
+                failures.push(failure);
+            } else if !response.body.contains(substring.as_str()) {
+                failures.push(format!("expected body to contain '{}'", substring));
+            }
+        }
+    }
+
+    if expect.contract == Some(true) {
+        match contract {
+            None => failures.push("contract check requires an 'openapi' spec in the configuration".to_string()),
+            Some(ContractResult::Unmatched) => failures.push("no operation in the OpenAPI spec matches the request".to_string()),
+            Some(ContractResult::Valid { .. }) => {}
+            Some(ContractResult::SpecError(error)) => failures.push(format!("contract: {}", error)),
+            Some(ContractResult::Invalid { operation, violations }) => {
+                for violation in violations {
+                    failures.push(format!("{} contract: {}", operation, violation));
+                }
+            }
+        }
+    }
+
+    if let Some(ref schema_path) = expect.schema {
+        if let Some(failure) = unread("schema".to_string()) {
+            failures.push(failure);
+            return failures;
+        }
+        match load_schema(schema_path) {
+            Ok(schema) => match serde_json::from_str(&response.body) {
+                Ok(body) => {
+                    for violation in contract::validate_schema(&schema, &schema, &body) {
+                        failures.push(format!("schema: {}", violation));
+                    }
+                }
+                Err(_) => failures.push("schema: response body is not valid JSON".to_string()),
+            },
+            Err(e) => failures.push(e.to_string()),
+        }
+    }
+
+    failures
+}
+
+/// Reads a JSON or YAML schema file
+fn load_schema(path: &str) -> Result<serde_json::Value> {
+    let content = fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+        format!("Failed to read schema file {}: {}", path, e)
+    ))?;
+    serde_yaml::from_str(&content).map_err(|e| AsteriskError::Config(
+        format!("Failed to parse schema file {}: {}", path, e)
+    ))
+}
+
+fn read_body_file(path: &str) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| AsteriskError::Config(
+        format!("Failed to read body file {}: {}", path, e)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_request_url() {
+        assert_eq!(build_request_url("http://localhost:3000", "/users"), "http://localhost:3000/users");
+        assert_eq!(build_request_url("http://localhost:3000/", "users"), "http://localhost:3000/users");
+        assert_eq!(build_request_url("http://localhost:3000", "https://other.example.com/x"), "https://other.example.com/x");
+    }
+
+    #[test]
+    fn test_append_query() {
+        let params = vec![
+            ("page".to_string(), "2".to_string()),
+            ("q".to_string(), "a b&c".to_string()),
+        ];
+        assert_eq!(
+            append_query("http://localhost:3000/users", &params).unwrap(),
+            "http://localhost:3000/users?page=2&q=a+b%26c"
+        );
+        assert_eq!(
+            append_query("http://localhost:3000/users?x=1", &params[..1]).unwrap(),
+            "http://localhost:3000/users?x=1&page=2"
+        );
+        assert_eq!(append_query("not a url", &[]).unwrap(), "not a url");
+    }
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_expectations() {
+        let expect = RawExpectation {
+            status: Some(201),
+            body_contains: Some(vec!["\"id\"".to_string(), "john".to_string()]),
+            ..Default::default()
+        };
+        assert!(check_expectations(&expect, &response(201, r#"{"id":1,"name":"john"}"#), None).is_empty());
+
+        let failures = check_expectations(&expect, &response(400, r#"{"error":"bad"}"#), None);
+        assert_eq!(failures.len(), 3);
+        assert_eq!(failures[0], "expected status 201, got 400");
+    }
+
+    #[test]
+    fn test_check_saved_response_expectations() {
+        let expect = RawExpectation {
+            status: Some(200),
+            body_contains: Some(vec!["john".to_string()]),
+            ..Default::default()
+        };
+        let saved = HttpResponse {
+            saved_to: Some(std::path::PathBuf::from("user.json")),
+            ..response(200, "")
+        };
+        assert_eq!(check_expectations(&expect, &saved, None), vec![
+            "expected body to contain 'john': the body was saved to user.json and not checked".to_string(),
+        ]);
+
+        let failures = check_expectations(&expect, &HttpResponse { status: 404, ..saved }, None);
+        assert_eq!(failures[0], "expected status 200, got 404");
+    }
+
+    #[test]
+    fn test_check_contract_and_schema_expectations() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema_path = dir.path().join("user.json");
+        fs::write(&schema_path, r#"{"type":"object","required":["id"],"properties":{"id":{"type":"integer"}}}"#).unwrap();
+
+        let expect = RawExpectation {
+            contract: Some(true),
+            schema: Some(schema_path.display().to_string()),
+            ..Default::default()
+        };
+        let invalid = ContractResult::Invalid {
+            operation: "getUser".to_string(),
+            violations: vec![contract::Violation { pointer: "#/id".to_string(), message: "expected integer".to_string() }],
+        };
+
+        let failures = check_expectations(&expect, &response(200, r#"{"id":"5"}"#), Some(&invalid));
+        assert_eq!(failures, vec![
+            "getUser contract: #/id: expected integer".to_string(),
+            "schema: #/id: expected integer, got string".to_string(),
+        ]);
+
+        let failures = check_expectations(&expect, &response(200, r#"{"id":5}"#), None);
+        assert_eq!(failures, vec!["contract check requires an 'openapi' spec in the configuration".to_string()]);
+
+        // A spec that fails to load fails the check instead of the request
+        let unloaded = ContractResult::SpecError("Failed to read spec file openapi.yaml".to_string());
+        let failures = check_expectations(&expect, &response(200, r#"{"id":5}"#), Some(&unloaded));
+        assert_eq!(failures, vec!["contract: Failed to read spec file openapi.yaml".to_string()]);
+    }
+}
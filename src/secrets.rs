@@ -0,0 +1,298 @@
+use crate::error::{AsteriskError, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prefix of values encrypted with `asterisk secret set`
+pub const ENCRYPTED_PREFIX: &str = "enc:";
+
+/// Key file kept next to asterisk.config, out of version control
+pub const KEY_FILENAME: &str = "asterisk.key";
+
+/// Key file to use instead of the one next to asterisk.config
+const KEY_FILE_ENV: &str = "ASTERISK_SECRET_KEY_FILE";
+
+/// Passphrase to use instead of a key file
+const PASSPHRASE_ENV: &str = "ASTERISK_SECRET_PASSPHRASE";
+
+/// New passphrase for `secret rotate-key`
+const NEW_PASSPHRASE_ENV: &str = "ASTERISK_SECRET_NEW_PASSPHRASE";
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// PBKDF2-HMAC-SHA256 rounds for passphrases; key files are already random
+const PASSPHRASE_ROUNDS: u32 = 600_000;
+const KEY_FILE_ROUNDS: u32 = 1;
+
+/// Key material for `enc:` values: the random bytes of a key file, or a passphrase
+/// The ChaCha20-Poly1305 key is derived from the material and a salt stored with each
+/// value. New values share this key's salt, so a key is derived once per salt rather than
+/// once per value; each value still has its own nonce.
+pub struct SecretKey {
+    material: Vec<u8>,
+    rounds: u32,
+    /// Key file the material is stored in; `None` for a passphrase
+    key_file: Option<PathBuf>,
+    /// Salt for values encrypted with this key
+    salt: [u8; SALT_LEN],
+    /// Derived keys by salt
+    ciphers: HashMap<[u8; SALT_LEN], ChaCha20Poly1305>,
+}
+
+impl SecretKey {
+    /// Loads the key for the project in `config_dir`: `ASTERISK_SECRET_PASSPHRASE`, then
+    /// the `ASTERISK_SECRET_KEY_FILE` or asterisk.key file
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        if let Some(passphrase) = env_value(PASSPHRASE_ENV) {
+            return Ok(Self::from_passphrase(&passphrase));
+        }
+        let path = key_file_path(config_dir);
+        if !path.exists() {
+            return Err(AsteriskError::SecretSource(format!(
+                "No key to decrypt enc: values: {} does not exist and {} is not set",
+                path.display(), PASSPHRASE_ENV
+            )));
+        }
+        let content = fs::read_to_string(&path).map_err(|e| AsteriskError::SecretSource(
+            format!("Failed to read {}: {}", path.display(), e)
+        ))?;
+        let material = BASE64.decode(content.trim()).ok()
+            .filter(|material| material.len() == KEY_LEN)
+            .ok_or_else(|| AsteriskError::SecretSource(format!("{} is not a valid key file", path.display())))?;
+        Ok(Self::new(material, KEY_FILE_ROUNDS, Some(path)))
+    }
+
+    /// Like `load`, but creates a new key file when there is no key yet
+    /// Returns the path of the created key file.
+    pub fn load_or_create(config_dir: &Path) -> Result<(Self, Option<PathBuf>)> {
+        let path = key_file_path(config_dir);
+        if env_value(PASSPHRASE_ENV).is_some() || path.exists() {
+            return Ok((Self::load(config_dir)?, None));
+        }
+        let key = Self::generate(path.clone());
+        write_key_file(&path, &key.material)?;
+        Ok((key, Some(path)))
+    }
+
+    /// Replacement for this key, for `secret rotate-key`: a new random key for the same
+    /// key file, or the `ASTERISK_SECRET_NEW_PASSPHRASE` passphrase. Not saved yet.
+    pub fn rotate(&self) -> Result<Self> {
+        match self.key_file {
+            Some(ref path) => Ok(Self::generate(path.clone())),
+            None => {
+                let passphrase = env_value(NEW_PASSPHRASE_ENV).ok_or_else(|| AsteriskError::SecretSource(
+                    format!("Set {} to rotate a passphrase", NEW_PASSPHRASE_ENV)
+                ))?;
+                Ok(Self::from_passphrase(&passphrase))
+            }
+        }
+    }
+
+    /// Writes a rotated key file next to the one it replaces, as `<file>.new`
+    /// Nothing uses it until `replace_key_file`; returns `None` for a passphrase.
+    pub fn stage(&self) -> Result<Option<PathBuf>> {
+        let Some(ref path) = self.key_file else {
+            return Ok(None);
+        };
+        let staged = suffixed(path, "new");
+        write_key_file(&staged, &self.material)?;
+        Ok(Some(staged))
+    }
+
+    /// Moves the staged key file into place, keeping the file it replaces as `<file>.old`
+    /// The current key file is restored when the staged one cannot be moved. Returns the
+    /// key file, or `None` for a passphrase.
+    pub fn replace_key_file(&self) -> Result<Option<&Path>> {
+        let Some(ref path) = self.key_file else {
+            return Ok(None);
+        };
+        let backup = suffixed(path, "old");
+        if path.exists() {
+            fs::rename(path, &backup).map_err(|e| AsteriskError::SecretSource(
+                format!("Failed to move {} to {}: {}", path.display(), backup.display(), e)
+            ))?;
+        }
+        let staged = suffixed(path, "new");
+        if let Err(e) = fs::rename(&staged, path) {
+            let _ = fs::rename(&backup, path);
+            return Err(AsteriskError::SecretSource(
+                format!("Failed to move {} to {}: {}", staged.display(), path.display(), e)
+            ));
+        }
+        Ok(Some(path))
+    }
+
+    /// Removes a key file written by `stage` that will not be used
+    pub fn discard_staged(&self) {
+        if let Some(ref path) = self.key_file {
+            let _ = fs::remove_file(suffixed(path, "new"));
+        }
+    }
+
+    /// Whether this key decrypts the `enc:` values of the config files in `config_dir`
+    /// A passphrase applies to every file; a key file only to files it is found for.
+    pub fn decrypts(&self, config_dir: &Path) -> bool {
+        match self.key_file {
+            Some(ref path) => key_file_path(config_dir) == *path,
+            None => true,
+        }
+    }
+
+    /// Where the key comes from, for messages
+    pub fn describe(&self) -> String {
+        match self.key_file {
+            Some(ref path) => path.display().to_string(),
+            None => PASSPHRASE_ENV.to_string(),
+        }
+    }
+
+    fn new(material: Vec<u8>, rounds: u32, key_file: Option<PathBuf>) -> Self {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        SecretKey { material, rounds, key_file, salt, ciphers: HashMap::new() }
+    }
+
+    fn generate(key_file: PathBuf) -> Self {
+        let mut material = vec![0; KEY_LEN];
+        OsRng.fill_bytes(&mut material);
+        Self::new(material, KEY_FILE_ROUNDS, Some(key_file))
+    }
+
+    fn from_passphrase(passphrase: &str) -> Self {
+        Self::new(passphrase.as_bytes().to_vec(), PASSPHRASE_ROUNDS, None)
+    }
+
+    /// Encrypts new values with the salt of an existing one, so the values of a file
+    /// need a single key derivation
+    pub fn share_salt(&mut self, value: &str) {
+        if let Ok((salt, _, _)) = split_payload(value) {
+            self.salt = salt;
+        }
+    }
+
+    /// Encrypts a value as `enc:` followed by base64 of salt, nonce and ciphertext
+    pub fn encrypt(&mut self, plaintext: &str) -> Result<String> {
+        let salt = self.salt;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher(salt).encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| AsteriskError::SecretSource("Failed to encrypt value".to_string()))?;
+
+        let payload = [salt.as_slice(), nonce.as_slice(), &ciphertext].concat();
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(payload)))
+    }
+
+    /// Decrypts an `enc:` value
+    pub fn decrypt(&mut self, value: &str) -> Result<String> {
+        let (salt, nonce, ciphertext) = split_payload(value)?;
+        let plaintext = self.cipher(salt).decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| AsteriskError::SecretSource(
+                format!("Failed to decrypt enc: value with {} (encrypted with another key?)", self.describe())
+            ))?;
+        String::from_utf8(plaintext)
+            .map_err(|_| AsteriskError::SecretSource("Decrypted value is not valid UTF-8".to_string()))
+    }
+
+    fn cipher(&mut self, salt: [u8; SALT_LEN]) -> &ChaCha20Poly1305 {
+        let (material, rounds) = (&self.material, self.rounds);
+        self.ciphers.entry(salt).or_insert_with(|| {
+            let mut key = Key::default();
+            pbkdf2::pbkdf2_hmac::<Sha256>(material, &salt, rounds, &mut key);
+            ChaCha20Poly1305::new(&key)
+        })
+    }
+}
+
+/// Salt, nonce and ciphertext of an `enc:` value
+fn split_payload(value: &str) -> Result<([u8; SALT_LEN], Vec<u8>, Vec<u8>)> {
+    let payload = value.strip_prefix(ENCRYPTED_PREFIX)
+        .and_then(|encoded| BASE64.decode(encoded.trim()).ok())
+        .filter(|payload| payload.len() > SALT_LEN + NONCE_LEN)
+        .ok_or_else(|| AsteriskError::SecretSource("Malformed enc: value".to_string()))?;
+    let (salt, rest) = payload.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let salt = salt.try_into().expect("salt has SALT_LEN bytes");
+    Ok((salt, nonce.to_vec(), ciphertext.to_vec()))
+}
+
+/// Writes key material as base64, readable only by the owner
+fn write_key_file(path: &Path, material: &[u8]) -> Result<()> {
+    fs::write(path, format!("{}\n", BASE64.encode(material))).map_err(|e| AsteriskError::SecretSource(
+        format!("Failed to write {}: {}", path.display(), e)
+    ))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// `path` with `.suffix` appended, e.g. asterisk.key.old
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), suffix))
+}
+
+/// Whether a value was encrypted with `asterisk secret set`
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// The `ASTERISK_SECRET_KEY_FILE` path, or asterisk.key in `config_dir`
+pub fn key_file_path(config_dir: &Path) -> PathBuf {
+    match env_value(KEY_FILE_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => config_dir.join(KEY_FILENAME),
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (mut key, created) = SecretKey::load_or_create(dir.path()).unwrap();
+        assert_eq!(created, Some(dir.path().join(KEY_FILENAME)));
+
+        let encrypted = key.encrypt("s3cret token").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_ne!(encrypted, key.encrypt("s3cret token").unwrap());
+        assert_eq!(SecretKey::load(dir.path()).unwrap().decrypt(&encrypted).unwrap(), "s3cret token");
+        assert!(key.decrypt("enc:bm90IGVub3VnaA==").is_err());
+
+        // The rotated key is staged first, then replaces the file and the old one is kept
+        let rotated = key.rotate().unwrap();
+        assert_eq!(rotated.stage().unwrap(), Some(dir.path().join("asterisk.key.new")));
+        assert_eq!(SecretKey::load(dir.path()).unwrap().decrypt(&encrypted).unwrap(), "s3cret token");
+        rotated.replace_key_file().unwrap();
+        assert!(matches!(SecretKey::load(dir.path()).unwrap().decrypt(&encrypted), Err(AsteriskError::SecretSource(_))));
+        assert!(dir.path().join("asterisk.key.old").exists());
+        assert!(!dir.path().join("asterisk.key.new").exists());
+
+        // Values of one key share its salt, so the key is derived once
+        let mut passphrase = SecretKey::new(b"correct horse".to_vec(), 10, None);
+        let first = passphrase.encrypt("value").unwrap();
+        let second = passphrase.encrypt("other").unwrap();
+        assert_eq!(passphrase.decrypt(&first).unwrap(), "value");
+        assert_eq!(passphrase.decrypt(&second).unwrap(), "other");
+        assert_eq!(passphrase.ciphers.len(), 1);
+        assert!(key.decrypt(&first).is_err());
+
+        let mut other = SecretKey::new(b"correct horse".to_vec(), 10, None);
+        other.share_salt(&first);
+        assert_eq!(split_payload(&other.encrypt("x").unwrap()).unwrap().0, split_payload(&first).unwrap().0);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::cli::StreamArgs;
+use crate::error::{AsteriskError, Result};
+use crate::http::HttpClient;
+use crate::logger::Logger;
+use crate::request::PreparedRequest;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use std::time::{Duration, Instant};
+
+/// Delay before reconnecting when the server sent no `retry:` field
+const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// Limits and reconnect behaviour for `--stream`
+#[derive(Debug, Clone, Default)]
+pub struct StreamOptions {
+    /// Stop after this many events, lines or chunks
+    pub max_events: Option<usize>,
+
+    /// Stop after this much time
+    pub max_time: Option<Duration>,
+
+    /// Reconnect when an event stream closes, sending `Last-Event-ID`
+    pub reconnect: bool,
+
+    /// `Last-Event-ID` for the first connection
+    pub last_event_id: Option<String>,
+}
+
+impl From<&StreamArgs> for StreamOptions {
+    fn from(args: &StreamArgs) -> Self {
+        Self {
+            max_events: args.max_events,
+            max_time: args.max_time.map(Duration::from_secs_f64),
+            reconnect: args.reconnect,
+            last_event_id: args.last_event_id.clone(),
+        }
+    }
+}
+
+/// A dispatched Server-Sent Event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseEvent {
+    /// Event type; `message` when the server sent none
+    pub event: Option<String>,
+
+    /// Last event ID after this event
+    pub id: Option<String>,
+
+    /// Data lines joined with newlines
+    pub data: String,
+
+    /// Reconnection delay requested by the server
+    pub retry: Option<u64>,
+}
+
+/// Incremental `text/event-stream` parser
+/// Input may be split anywhere; events are returned once their blank line arrives.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: String,
+    event: SseEvent,
+    has_data: bool,
+}
+
+impl SseParser {
+    pub fn feed(&mut self, text: &str) -> Vec<SseEvent> {
+        self.buffer.push_str(text);
+        let mut events = Vec::new();
+
+        while let Some(end) = self.buffer.find(['\n', '\r']) {
+            // A CR at the end of the buffer may be the first half of CRLF
+            if self.buffer[end..].starts_with('\r') && end + 1 == self.buffer.len() {
+                break;
+            }
+            let line = self.buffer[..end].to_string();
+            let skip = if self.buffer[end..].starts_with("\r\n") { 2 } else { 1 };
+            self.buffer.drain(..end + skip);
+
+            if line.is_empty() {
+                if self.has_data {
+                    let id = self.event.id.clone();
+                    events.push(std::mem::take(&mut self.event));
+                    self.event.id = id;
+                } else {
+                    self.event.event = None;
+                }
+                self.has_data = false;
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line.as_str(), ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event.event = Some(value.to_string()),
+                "id" if !value.contains('\0') => self.event.id = Some(value.to_string()),
+                "retry" => self.event.retry = value.parse().ok().or(self.event.retry),
+                "data" => {
+                    if self.has_data {
+                        self.event.data.push('\n');
+                    }
+                    self.event.data.push_str(value);
+                    self.has_data = true;
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// How the body of a streamed response is split for display
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Framing {
+    Events,
+    Lines,
+    Chunks,
+}
+
+impl Framing {
+    fn from_content_type(content_type: &str) -> Self {
+        match content_type.split(';').next().unwrap_or("").trim().to_lowercase().as_str() {
+            "text/event-stream" => Framing::Events,
+            "application/x-ndjson" | "application/ndjson" | "application/jsonl" | "application/json-seq"
+            | "application/stream+json" => Framing::Lines,
+            _ => Framing::Chunks,
+        }
+    }
+}
+
+/// Sends a request and prints its body as it arrives
+/// Event streams are parsed into events; NDJSON is printed per line and anything else
+/// per received chunk. Ends when the server closes the stream or a limit is reached.
+pub async fn run(client: &HttpClient, prepared: &PreparedRequest, options: &StreamOptions, logger: &Logger) -> Result<()> {
+    let started = Instant::now();
+    let deadline = options.max_time.map(|limit| started + limit);
+    let mut last_event_id = options.last_event_id.clone();
+    let mut retry = DEFAULT_RETRY;
+    let mut received = 0;
+
+    loop {
+        let mut headers = prepared.headers.clone();
+        if let Some(ref id) = last_event_id {
+            let value = HeaderValue::from_str(id).map_err(|_| AsteriskError::InvalidHeaders)?;
+            headers.insert("last-event-id", value);
+        }
+
+        let opened = until(deadline, client.open(&prepared.url, &prepared.method, headers, prepared.body.clone())).await;
+        let Some(response) = opened else {
+            logger.stream_closed(received, "time limit reached");
+            return Ok(());
+        };
+        let mut response = response?;
+        let content_type = response.headers().get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let status = response.status().as_u16();
+        logger.stream_opened(status, &content_type);
+
+        if !response.status().is_success() {
+            if let Some(body) = until(deadline, response.text()).await {
+                logger.chunk(&body?);
+            }
+            return Err(AsteriskError::HttpStatus { request: "Stream request".to_string(), status });
+        }
+
+        let framing = Framing::from_content_type(&content_type);
+        let reconnects = options.reconnect && framing == Framing::Events;
+        let mut parser = SseParser::default();
+        let mut pending = String::new();
+        let mut undecoded = Vec::new();
+        let mut closed = "stream closed".to_string();
+
+        loop {
+            let Some(next) = until(deadline, response.chunk()).await else {
+                logger.stream_closed(received, "time limit reached");
+                return Ok(());
+            };
+            // A dropped connection is another way for an event stream to end
+            let chunk = match next {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) if reconnects => {
+                    closed = format!("connection lost ({})", e);
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            undecoded.extend_from_slice(&chunk);
+            let text = take_text(&mut undecoded);
+
+            let items: Vec<Item> = match framing {
+                Framing::Events => parser.feed(&text).into_iter().map(Item::Event).collect(),
+                Framing::Lines => {
+                    pending.push_str(&text);
+                    let complete = pending.rfind('\n').map(|end| pending.drain(..=end).collect::<String>());
+                    complete.iter()
+                        .flat_map(|lines| lines.lines())
+                        .map(|line| line.trim_start_matches('\u{1e}').trim())
+                        .filter(|line| !line.is_empty())
+                        .map(|line| Item::Chunk(line.to_string()))
+                        .collect()
+                }
+                Framing::Chunks if text.is_empty() => Vec::new(),
+                Framing::Chunks => vec![Item::Chunk(text)],
+            };
+
+            for item in items {
+                match item {
+                    Item::Event(event) => {
+                        if event.id.is_some() {
+                            last_event_id = event.id.clone();
+                        }
+                        if let Some(millis) = event.retry {
+                            retry = Duration::from_millis(millis);
+                        }
+                        logger.event(&event);
+                    }
+                    Item::Chunk(text) => logger.chunk(&text),
+                }
+                received += 1;
+                if options.max_events.is_some_and(|max| received >= max) {
+                    logger.stream_closed(received, "event limit reached");
+                    return Ok(());
+                }
+            }
+        }
+
+        if !pending.trim().is_empty() {
+            logger.chunk(pending.trim());
+            received += 1;
+        }
+
+        if !reconnects {
+            logger.stream_closed(received, "closed by server");
+            return Ok(());
+        }
+        if deadline.is_some_and(|deadline| Instant::now() + retry >= deadline) {
+            logger.stream_closed(received, "time limit reached");
+            return Ok(());
+        }
+        logger.reconnecting(&closed, retry, last_event_id.as_deref());
+        tokio::time::sleep(retry).await;
+    }
+}
+
+/// Runs `future` until the `--max-time` deadline; `None` once it has passed
+async fn until<T>(deadline: Option<Instant>, future: impl std::future::Future<Output = T>) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline.saturating_duration_since(Instant::now()), future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Takes the decodable text from `bytes`, keeping a UTF-8 sequence split across chunks
+fn take_text(bytes: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(error) if error.error_len().is_none() => error.valid_up_to(),
+        Err(_) => bytes.len(),
+    };
+    let text = String::from_utf8_lossy(&bytes[..complete]).into_owned();
+    bytes.drain(..complete);
+    text
+}
+
+enum Item {
+    Event(SseEvent),
+    Chunk(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(": keep-alive\n\nevent: upd").is_empty());
+
+        let events = parser.feed("ate\nid: 7\ndata: {\"a\":1}\ndata: second\r\n\r");
+        assert!(events.is_empty());
+
+        let events = parser.feed("\ndata:plain\n\n");
+        assert_eq!(events, vec![
+            SseEvent {
+                event: Some("update".to_string()),
+                id: Some("7".to_string()),
+                data: "{\"a\":1}\nsecond".to_string(),
+                retry: None,
+            },
+            SseEvent { event: None, id: Some("7".to_string()), data: "plain".to_string(), retry: None },
+        ]);
+    }
+
+    #[test]
+    fn test_retry_and_events_without_data() {
+        let mut parser = SseParser::default();
+        let events = parser.feed("retry: 500\nevent: ping\n\ndata: x\n\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, None);
+        assert_eq!(events[0].retry, Some(500));
+    }
+
+    #[test]
+    fn test_take_text_keeps_split_characters() {
+        let mut bytes = "caf\u{e9}".as_bytes()[..4].to_vec();
+        assert_eq!(take_text(&mut bytes), "caf");
+        bytes.push(0xa9);
+        assert_eq!(take_text(&mut bytes), "\u{e9}");
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_framing() {
+        assert_eq!(Framing::from_content_type("text/event-stream; charset=utf-8"), Framing::Events);
+        assert_eq!(Framing::from_content_type("application/x-ndjson"), Framing::Lines);
+        assert_eq!(Framing::from_content_type("text/plain"), Framing::Chunks);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::error::{AsteriskError, Result};
+use crate::logger::Logger;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::header::HeaderMap;
+use std::io::{BufRead, IsTerminal};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::error::ProtocolError;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{Error, Message};
+
+/// How long to wait for the server's close frame after sending ours
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How messages to send are supplied
+pub enum Input {
+    /// Lines typed at a prompt, with `/ping`, `/close` and `/quit` commands
+    Interactive,
+
+    /// Lines sent in order (commands included), then replies are awaited for `wait`
+    Scripted { messages: Vec<String>, wait: Duration },
+}
+
+/// Converts an HTTP(S) URL to its WebSocket equivalent
+pub fn websocket_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Reads messages for scripted mode, one per non-empty line; `-` reads stdin
+pub fn read_messages(path: &str) -> Result<Vec<String>> {
+    let content = if path == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    Ok(content.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect())
+}
+
+/// Whether stdin is a terminal a user can type messages into
+pub fn stdin_is_interactive() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// A line of input: a message, or a `/` command (`//` escapes a leading slash)
+#[derive(Debug, PartialEq)]
+enum Command {
+    Send(String),
+    Ping(String),
+    Close(u16, String),
+    Quit,
+}
+
+fn parse_command(line: &str) -> Command {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+    match word {
+        "/ping" => Command::Ping(rest.to_string()),
+        "/close" => {
+            let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+            match code.parse() {
+                Ok(code) => Command::Close(code, reason.to_string()),
+                Err(_) => Command::Close(1000, rest.to_string()),
+            }
+        }
+        "/quit" | "/exit" => Command::Quit,
+        _ => Command::Send(line.strip_prefix("//").map(|text| format!("/{}", text)).unwrap_or_else(|| line.to_string())),
+    }
+}
+
+/// Connects, exchanges messages and reports how the connection closed
+/// Closes other than normal (1000) or going away (1001) are returned as errors.
+pub async fn run(url: &str, headers: &HeaderMap, input: Input, logger: &Logger) -> Result<()> {
+    let mut request = url.into_client_request()?;
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_str().as_bytes()).map_err(|_| AsteriskError::InvalidHeaders)?;
+        let value = HeaderValue::from_bytes(value.as_bytes()).map_err(|_| AsteriskError::InvalidHeaders)?;
+        request.headers_mut().insert(name, value);
+    }
+
+    let (socket, response) = tokio_tungstenite::connect_async(request).await?;
+    logger.ws_connected(url, response.status().as_u16());
+    let (mut sink, mut stream) = socket.split();
+
+    // Outgoing commands come from a blocking stdin reader or the script
+    let (sender, mut commands) = mpsc::unbounded_channel();
+    let wait = match input {
+        Input::Interactive => {
+            logger.ws_prompt_help();
+            std::thread::spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    let Ok(line) = line else { break };
+                    if sender.send(parse_command(&line)).is_err() {
+                        break;
+                    }
+                }
+                let _ = sender.send(Command::Quit);
+            });
+            None
+        }
+        Input::Scripted { messages, wait } => {
+            for message in messages {
+                let _ = sender.send(parse_command(&message));
+            }
+            drop(sender);
+            Some(wait)
+        }
+    };
+
+    let mut commands_open = true;
+    // Set once our close frame is sent; the server must answer it by then
+    let mut close_deadline: Option<tokio::time::Instant> = None;
+    loop {
+        // Once the script is sent, the connection is closed after `wait` without messages
+        let idle = async {
+            match wait {
+                Some(wait) if !commands_open && close_deadline.is_none() => tokio::time::sleep(wait).await,
+                _ => std::future::pending().await,
+            }
+        };
+        let close_timeout = async {
+            match close_deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            command = commands.recv(), if commands_open => match command {
+                Some(Command::Send(text)) => {
+                    logger.ws_sent(&text);
+                    sink.send(Message::Text(text)).await?;
+                }
+                Some(Command::Ping(payload)) => {
+                    logger.ws_sent_control("ping", &payload);
+                    sink.send(Message::Ping(payload.into_bytes())).await?;
+                }
+                Some(Command::Close(code, reason)) => {
+                    logger.ws_sent_control("close", &format!("{} {}", code, reason));
+                    sink.send(Message::Close(Some(CloseFrame { code: CloseCode::from(code), reason: reason.into() }))).await?;
+                    // Nothing more can be sent after a close frame
+                    close_deadline = Some(tokio::time::Instant::now() + CLOSE_TIMEOUT);
+                    commands_open = false;
+                }
+                Some(Command::Quit) => {
+                    sink.send(Message::Close(Some(CloseFrame { code: CloseCode::Normal, reason: "".into() }))).await?;
+                    close_deadline = Some(tokio::time::Instant::now() + CLOSE_TIMEOUT);
+                    commands_open = false;
+                }
+                None => commands_open = false,
+            },
+            message = stream.next() => match message {
+                Some(Ok(Message::Close(frame))) => {
+                    let (code, reason) = frame
+                        .map(|frame| (u16::from(frame.code), frame.reason.into_owned()))
+                        .unwrap_or((1005, String::new()));
+                    logger.ws_closed(code, &reason);
+                    return match code {
+                        1000 | 1001 | 1005 => Ok(()),
+                        _ => Err(AsteriskError::WebSocket(format!("connection closed with code {}", code))),
+                    };
+                }
+                Some(Ok(message)) => logger.ws_received(&message),
+                Some(Err(Error::Protocol(ProtocolError::ResetWithoutClosingHandshake))) | None => {
+                    logger.ws_closed(1006, "connection dropped without a close frame");
+                    return Err(AsteriskError::WebSocket("connection closed abnormally (1006)".to_string()));
+                }
+                Some(Err(error)) => return Err(error.into()),
+            },
+            _ = idle => {
+                sink.send(Message::Close(Some(CloseFrame { code: CloseCode::Normal, reason: "".into() }))).await?;
+                close_deadline = Some(tokio::time::Instant::now() + CLOSE_TIMEOUT);
+            }
+            _ = close_timeout => {
+                logger.ws_closed(1006, "no close frame from the server");
+                return Err(AsteriskError::WebSocket(format!(
+                    "server did not complete the close handshake within {}s", CLOSE_TIMEOUT.as_secs()
+                )));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("https://api.example.com/chat/room1"), "wss://api.example.com/chat/room1");
+        assert_eq!(websocket_url("http://localhost:3000/ws"), "ws://localhost:3000/ws");
+        assert_eq!(websocket_url("ws://localhost:3000/ws"), "ws://localhost:3000/ws");
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("{\"type\":\"join\"}"), Command::Send("{\"type\":\"join\"}".to_string()));
+        assert_eq!(parse_command("/ping hello"), Command::Ping("hello".to_string()));
+        assert_eq!(parse_command("/close 4000 bye now"), Command::Close(4000, "bye now".to_string()));
+        assert_eq!(parse_command("/close"), Command::Close(1000, String::new()));
+        assert_eq!(parse_command("//ping is text"), Command::Send("/ping is text".to_string()));
+        assert_eq!(parse_command("/quit"), Command::Quit);
+    }
+}